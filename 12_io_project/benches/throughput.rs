@@ -0,0 +1,98 @@
+/* -------------------- Benchmark de lectura -------------------- */
+/*
+  Compara el camino viejo ("fs::read_to_string" + "lines()") contra
+  "input::open" + "search_input", que mapea en memoria los archivos grandes.
+
+  $ cargo bench
+
+  Se genera un log falso en el directorio temporal y cada estrategia lo
+  busca varias veces; se reporta la mejor vuelta en MB/s.
+*/
+
+use io_project::input::{self, Strategy};
+use io_project::Config;
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+const ROUNDS: usize = 5;
+const QUERY: &str = "request-4242";
+const SIZES: [usize; 3] = [256 * 1024, 16 * 1024 * 1024, 128 * 1024 * 1024];
+
+fn main() {
+  for &size in SIZES.iter() {
+    let path = env::temp_dir().join(format!("io_project-bench-{}", size));
+    write_log(&path, size);
+
+    let strategy = input::open(&path, None).unwrap().strategy();
+    println!("{} ({:?}):", human(size), strategy);
+    if strategy == Strategy::Buffered {
+      println!("  (below input::MMAP_THRESHOLD, read through a buffer)");
+    }
+
+    let expected = run_read_to_string(&path);
+    report("read_to_string + lines()", size, || {
+      assert_eq!(expected, run_read_to_string(&path))
+    });
+    report("input::open + search_input", size, || {
+      assert_eq!(expected, run_input(&path))
+    });
+
+    fs::remove_file(&path).unwrap();
+  }
+}
+
+fn run_read_to_string(path: &Path) -> usize {
+  let contents = fs::read_to_string(path).unwrap();
+  io_project::search(QUERY, &contents).len()
+}
+
+fn run_input(path: &Path) -> usize {
+  let args = vec!["bench", QUERY, path.to_str().unwrap()];
+  let config = Config::new(args.into_iter().map(String::from)).unwrap();
+  let mut input = input::open(path, None).unwrap();
+
+  let mut found = 0;
+  io_project::search_input(&config, &mut input, |_| found += 1).unwrap();
+  found
+}
+
+fn report<F: FnMut()>(name: &str, size: usize, mut f: F) {
+  let best = (0..ROUNDS)
+    .map(|_| {
+      let start = Instant::now();
+      f();
+      start.elapsed()
+    })
+    .min()
+    .unwrap_or(Duration::from_secs(0));
+
+  let secs = best.as_secs_f64().max(f64::EPSILON);
+  let mb = size as f64 / (1024.0 * 1024.0);
+  println!("  {:<28} {:>10.2?} {:>10.1} MB/s", name, best, mb / secs);
+}
+
+fn write_log(path: &Path, size: usize) {
+  let mut contents = String::with_capacity(size + 128);
+  let mut id = 0;
+
+  while contents.len() < size {
+    contents.push_str(&format!(
+      "2020-07-31T12:00:00Z INFO request-{} GET /api/items 200 {}ms\n",
+      id % 10_000,
+      id % 97
+    ));
+    id += 1;
+  }
+
+  fs::write(path, contents).unwrap();
+}
+
+fn human(size: usize) -> String {
+  if size >= 1024 * 1024 {
+    format!("{} MiB", size / (1024 * 1024))
+  } else {
+    format!("{} KiB", size / 1024)
+  }
+}
@@ -0,0 +1,94 @@
+/* -------------------- Archivos binarios -------------------- */
+/*
+  Igual que grep, un archivo se considera binario si tiene un byte NUL en el
+  primer bloque. Los archivos de texto con UTF-8 invalido no son binarios,
+  se buscan reemplazando los bytes invalidos por "�".
+
+  Que hacer con un archivo binario lo decide la politica "--binary":
+
+  - skip:    se ignora sin imprimir nada.
+  - matches: solo se imprime "Binary file X matches" (por defecto).
+  - text:    se busca como texto, escapando los bytes no imprimibles (\x00).
+*/
+
+// Cantidad de bytes que se revisan para buscar un NUL
+pub const DETECTION_BLOCK: usize = 8 * 1024;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum BinaryPolicy {
+  Skip,
+  Matches,
+  Text,
+}
+
+impl BinaryPolicy {
+  pub fn from_name(name: &str) -> Result<BinaryPolicy, &'static str> {
+    match name {
+      "skip" => Ok(BinaryPolicy::Skip),
+      "matches" => Ok(BinaryPolicy::Matches),
+      "text" => Ok(BinaryPolicy::Text),
+      _ => Err("Binary policy must be skip, matches or text"),
+    }
+  }
+}
+
+pub fn is_binary(bytes: &[u8]) -> bool {
+  let block = &bytes[..bytes.len().min(DETECTION_BLOCK)];
+  block.contains(&0)
+}
+
+// Deja el texto valido como esta y escapa controles y bytes invalidos
+pub fn escape(bytes: &[u8]) -> String {
+  let mut escaped = String::with_capacity(bytes.len());
+
+  for chunk in bytes.utf8_chunks() {
+    for c in chunk.valid().chars() {
+      if c == '\t' || !c.is_control() {
+        escaped.push(c);
+      } else if c.is_ascii() {
+        escaped.push_str(&format!("\\x{:02X}", c as u32));
+      } else {
+        escaped.extend(c.escape_unicode());
+      }
+    }
+
+    for byte in chunk.invalid() {
+      escaped.push_str(&format!("\\x{:02X}", byte));
+    }
+  }
+
+  escaped
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn nul_in_first_block_is_binary() {
+    assert!(is_binary(b"ELF\x00\x01\x02"));
+    assert!(!is_binary("plain text, ñandú \u{7f}".as_bytes()));
+    assert!(!is_binary(b"invalid utf-8 \xff\xfe"));
+  }
+
+  #[test]
+  fn nul_after_first_block_is_text() {
+    let mut bytes = vec![b'a'; DETECTION_BLOCK];
+    bytes.push(0);
+
+    assert!(!is_binary(&bytes));
+  }
+
+  #[test]
+  fn escapes_non_printable_bytes() {
+    assert_eq!("key\\x00value\\xFF\tañejo", escape(b"key\x00value\xff\ta\xc3\xb1ejo"));
+    assert_eq!("bell\\x07 \\u{85}", escape("bell\u{7} \u{85}".as_bytes()));
+  }
+
+  #[test]
+  fn policy_names() {
+    assert_eq!(Ok(BinaryPolicy::Skip), BinaryPolicy::from_name("skip"));
+    assert_eq!(Ok(BinaryPolicy::Text), BinaryPolicy::from_name("text"));
+    assert!(BinaryPolicy::from_name("print").is_err());
+  }
+}
@@ -0,0 +1,263 @@
+/* -------------------- Modo follow (tail -f) -------------------- */
+/*
+  Mantiene el archivo abierto despues de llegar al final (EOF) y devuelve
+  las lineas nuevas que se le van agregando, como lo hace "tail -f".
+
+  Tambien detecta la rotacion de logs:
+
+  - Truncado: el archivo ahora es mas pequeño que la posicion leida, se
+    vuelve a leer desde el inicio.
+  - Reemplazado: el path apunta a otro archivo (otro inode), se termina de
+    leer el archivo viejo y se abre el nuevo desde el inicio.
+
+  Las lineas se devuelven como bytes, igual que en una busqueda normal, y
+  el archivo se revisa con la misma deteccion de binarios (un NUL en el
+  primer bloque) cada vez que se empieza a leer desde el inicio.
+*/
+
+use std::fs::{self, File, Metadata};
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use memchr::memchr;
+
+use crate::binary;
+
+// Cada cuanto se revisa si el archivo cambio
+pub const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+pub struct Follower {
+  path: PathBuf,
+  file: File,
+  pos: u64,
+  id: Option<(u64, u64)>,
+  // Bytes de una linea que todavia no termina con '\n'
+  pending: Vec<u8>,
+  binary: bool,
+}
+
+impl Follower {
+  // Abre el archivo desde el inicio, el primer poll() devuelve todo su contenido
+  pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Follower> {
+    let path = path.as_ref().to_path_buf();
+    let file = File::open(&path)?;
+    let id = file_id(&file.metadata()?);
+
+    Ok(Follower {
+      path,
+      file,
+      pos: 0,
+      id,
+      pending: Vec::new(),
+      binary: false,
+    })
+  }
+
+  // Devuelve las lineas completas agregadas desde el ultimo poll()
+  pub fn poll(&mut self) -> io::Result<Vec<Vec<u8>>> {
+    let mut lines = Vec::new();
+    self.read_available(&mut lines)?;
+
+    if self.replaced() {
+      // Lo que quedo sin '\n' en el archivo viejo ya no va a terminar
+      self.flush_pending(&mut lines);
+      self.file = File::open(&self.path)?;
+      self.id = file_id(&self.file.metadata()?);
+      self.pos = 0;
+      self.binary = false;
+      self.read_available(&mut lines)?;
+    } else if self.file.metadata()?.len() < self.pos {
+      self.pending.clear();
+      self.file.seek(SeekFrom::Start(0))?;
+      self.pos = 0;
+      self.binary = false;
+      self.read_available(&mut lines)?;
+    }
+
+    Ok(lines)
+  }
+
+  // Si el archivo que se esta leyendo es binario. Solo se sabe despues de
+  // leer su primer bloque, las lineas de antes ya se devolvieron como texto.
+  pub fn is_binary(&self) -> bool {
+    self.binary
+  }
+
+  fn read_available(&mut self, lines: &mut Vec<Vec<u8>>) -> io::Result<()> {
+    // Lo que ya estaba en "pending" no tiene '\n', se busca solo en lo nuevo
+    let old = self.pending.len();
+    let first = self.pos;
+    self.pos += self.file.read_to_end(&mut self.pending)? as u64;
+
+    // La parte del primer bloque que llego en esta lectura
+    if first < binary::DETECTION_BLOCK as u64 {
+      let len = (self.pos.min(binary::DETECTION_BLOCK as u64) - first) as usize;
+      self.binary |= binary::is_binary(&self.pending[old..old + len]);
+    }
+
+    // Se recorre una sola vez y se quita lo leido al final, quitar linea por
+    // linea moveria el resto del buffer cada vez
+    let mut start = 0;
+    let mut from = old;
+    while let Some(len) = memchr(b'\n', &self.pending[from..]) {
+      lines.push(to_line(&self.pending[start..from + len]));
+      start = from + len + 1;
+      from = start;
+    }
+    self.pending.drain(..start);
+
+    Ok(())
+  }
+
+  fn flush_pending(&mut self, lines: &mut Vec<Vec<u8>>) {
+    if !self.pending.is_empty() {
+      lines.push(to_line(&self.pending));
+      self.pending.clear();
+    }
+  }
+
+  // Si el path no existe (en medio de una rotacion) se sigue con el archivo actual
+  fn replaced(&self) -> bool {
+    match fs::metadata(&self.path) {
+      Ok(meta) => file_id(&meta) != self.id,
+      Err(_) => false,
+    }
+  }
+}
+
+// Igual que lines(), sin el "\r" de los finales "\r\n"
+fn to_line(bytes: &[u8]) -> Vec<u8> {
+  bytes.strip_suffix(b"\r").unwrap_or(bytes).to_vec()
+}
+
+#[cfg(unix)]
+fn file_id(meta: &Metadata) -> Option<(u64, u64)> {
+  use std::os::unix::fs::MetadataExt;
+  Some((meta.dev(), meta.ino()))
+}
+
+// Sin inodes solo se puede detectar el truncado
+#[cfg(not(unix))]
+fn file_id(_meta: &Metadata) -> Option<(u64, u64)> {
+  None
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use std::env;
+  use std::fs::OpenOptions;
+  use std::io::Write;
+
+  fn temp_path(name: &str) -> PathBuf {
+    env::temp_dir().join(format!("io_project-{}-{}", std::process::id(), name))
+  }
+
+  // Las lineas de un poll() como texto
+  fn poll(follower: &mut Follower) -> Vec<String> {
+    let lines = follower.poll().unwrap();
+    lines.iter().map(|line| String::from_utf8_lossy(line).into_owned()).collect()
+  }
+
+  fn append(path: &Path, text: &str) {
+    let mut file = OpenOptions::new()
+      .create(true)
+      .append(true)
+      .open(path)
+      .unwrap();
+    file.write_all(text.as_bytes()).unwrap();
+  }
+
+  #[test]
+  fn reads_appended_lines() {
+    let path = temp_path("appended");
+    fs::write(&path, "one\ntwo\n").unwrap();
+
+    let mut follower = Follower::open(&path).unwrap();
+    assert_eq!(vec!["one", "two"], poll(&mut follower));
+    assert!(poll(&mut follower).is_empty());
+
+    append(&path, "thr");
+    assert!(poll(&mut follower).is_empty());
+    append(&path, "ee\r\nfour\n");
+    assert_eq!(vec!["three", "four"], poll(&mut follower));
+
+    fs::remove_file(&path).unwrap();
+  }
+
+  #[test]
+  fn reads_many_lines_in_one_poll() {
+    let path = temp_path("many");
+    let text: String = (0..10_000).map(|i| format!("line {}\n", i)).collect();
+    fs::write(&path, format!("{}unfinished", text)).unwrap();
+
+    let mut follower = Follower::open(&path).unwrap();
+    let lines = poll(&mut follower);
+    assert_eq!(10_000, lines.len());
+    assert_eq!("line 9999", lines[9_999]);
+
+    append(&path, " line\n");
+    assert_eq!(vec!["unfinished line"], poll(&mut follower));
+
+    fs::remove_file(&path).unwrap();
+  }
+
+  #[test]
+  fn detects_binary_files() {
+    let path = temp_path("binary");
+    fs::write(&path, "text\n").unwrap();
+
+    let mut follower = Follower::open(&path).unwrap();
+    assert_eq!(vec!["text"], poll(&mut follower));
+    assert!(!follower.is_binary());
+
+    append(&path, "ELF\0\n");
+    assert_eq!(vec![b"ELF\0".to_vec()], follower.poll().unwrap());
+    assert!(follower.is_binary());
+
+    // Otro archivo empieza sin saberse si es binario
+    fs::write(&path, "new\n").unwrap();
+    assert_eq!(vec!["new"], poll(&mut follower));
+    assert!(!follower.is_binary());
+
+    fs::remove_file(&path).unwrap();
+  }
+
+  #[test]
+  fn restarts_after_truncation() {
+    let path = temp_path("truncated");
+    fs::write(&path, "old line\nanother old line\n").unwrap();
+
+    let mut follower = Follower::open(&path).unwrap();
+    poll(&mut follower);
+
+    fs::write(&path, "new\n").unwrap();
+    assert_eq!(vec!["new"], poll(&mut follower));
+
+    fs::remove_file(&path).unwrap();
+  }
+
+  #[cfg(unix)]
+  #[test]
+  fn switches_to_replaced_file() {
+    let path = temp_path("replaced");
+    let rotated = temp_path("replaced.1");
+    fs::write(&path, "first\n").unwrap();
+
+    let mut follower = Follower::open(&path).unwrap();
+    assert_eq!(vec!["first"], poll(&mut follower));
+
+    append(&path, "last of old\n");
+    fs::rename(&path, &rotated).unwrap();
+    fs::write(&path, "first of new\n").unwrap();
+
+    assert_eq!(
+      vec!["last of old", "first of new"],
+      poll(&mut follower)
+    );
+
+    fs::remove_file(&path).unwrap();
+    fs::remove_file(&rotated).unwrap();
+  }
+}
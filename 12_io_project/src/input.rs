@@ -0,0 +1,237 @@
+/* -------------------- Lectura de la entrada -------------------- */
+/*
+  Para archivos regulares grandes es mas rapido mapear el archivo en memoria
+  (mmap) que copiarlo completo a un String con "fs::read_to_string": el
+  sistema operativo carga las paginas a medida que la busqueda las recorre.
+
+  Para archivos pequeños, pipes o archivos especiales (/dev/stdin, /proc/...)
+  se usa una lectura con buffer, igual que si el mapeo falla.
+*/
+
+use crate::binary;
+use crate::range::ByteRange;
+use memchr::memchr;
+use memmap2::Mmap;
+use std::fs::{File, Metadata};
+use std::io::{self, BufRead, BufReader, Cursor, Read, Seek, SeekFrom};
+use std::path::Path;
+
+// Por debajo de este tamaño el costo de crear el mapeo no vale la pena
+pub const MMAP_THRESHOLD: u64 = 1024 * 1024;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Strategy {
+  Mmap,
+  Buffered,
+}
+
+// La entrada se recorre linea por linea y se puede dejar de leer en
+// cualquier momento: lo que queda del archivo nunca se lee.
+pub enum Input {
+  Mapped { map: Mmap, start: usize, end: usize },
+  // Los primeros bytes se guardan aparte para detectar archivos binarios
+  Buffered(Vec<u8>, Box<dyn BufRead>),
+}
+
+impl Input {
+  pub fn from_reader<R: Read + 'static>(reader: R) -> io::Result<Input> {
+    let mut reader = BufReader::new(reader);
+    let mut head = Vec::new();
+    (&mut reader)
+      .take(binary::DETECTION_BLOCK as u64)
+      .read_to_end(&mut head)?;
+
+    let rest = Cursor::new(head.clone()).chain(reader);
+    Ok(Input::Buffered(head, Box::new(BufReader::new(rest))))
+  }
+
+  // El inicio de la entrada, hasta binary::DETECTION_BLOCK bytes
+  pub fn head(&self) -> &[u8] {
+    match self {
+      Input::Mapped { map, start, end } => {
+        let end = (*end).min(start + binary::DETECTION_BLOCK);
+        &map[*start..end]
+      }
+      Input::Buffered(head, _) => head,
+    }
+  }
+
+  pub fn strategy(&self) -> Strategy {
+    match self {
+      Input::Mapped { .. } => Strategy::Mmap,
+      Input::Buffered(..) => Strategy::Buffered,
+    }
+  }
+
+  // Llama a "f" con cada linea (sin "\n" ni "\r") hasta que devuelva false.
+  // Devuelve cuantos bytes se recorrieron, contando los fines de linea.
+  pub fn for_each_line<F>(&mut self, mut f: F) -> io::Result<u64>
+  where
+    F: FnMut(&[u8]) -> bool,
+  {
+    let mut scanned = 0;
+
+    match self {
+      Input::Mapped { map, start, end } => {
+        let mut rest = &map[*start..*end];
+
+        while !rest.is_empty() {
+          let (line, next) = match memchr(b'\n', rest) {
+            Some(i) => (&rest[..i], &rest[i + 1..]),
+            None => (rest, &rest[rest.len()..]),
+          };
+          scanned += (rest.len() - next.len()) as u64;
+          rest = next;
+
+          if !f(trim_cr(line)) {
+            break;
+          }
+        }
+      }
+      Input::Buffered(_, reader) => {
+        let mut line = Vec::new();
+
+        while reader.read_until(b'\n', &mut line)? > 0 {
+          scanned += line.len() as u64;
+          let bytes = line.strip_suffix(b"\n").unwrap_or(&line);
+
+          if !f(trim_cr(bytes)) {
+            break;
+          }
+          line.clear();
+        }
+      }
+    }
+
+    Ok(scanned)
+  }
+}
+
+fn trim_cr(line: &[u8]) -> &[u8] {
+  line.strip_suffix(b"\r").unwrap_or(line)
+}
+
+// Heuristica: solo archivos regulares con un tamaño minimo se mapean
+pub fn strategy(meta: &Metadata) -> Strategy {
+  if meta.is_file() && meta.len() >= MMAP_THRESHOLD {
+    Strategy::Mmap
+  } else {
+    Strategy::Buffered
+  }
+}
+
+pub fn open<P: AsRef<Path>>(path: P, bytes: Option<ByteRange>) -> io::Result<Input> {
+  let mut file = File::open(path)?;
+  let meta = file.metadata()?;
+  let start = bytes.map_or(0, |range| range.start);
+
+  if strategy(&meta) == Strategy::Mmap {
+    // SAFETY: si otro proceso trunca el archivo mientras esta mapeado la
+    // lectura puede fallar; es el mismo compromiso que hacen grep y ripgrep.
+    if let Ok(map) = unsafe { Mmap::map(&file) } {
+      let len = map.len() as u64;
+      let end = bytes.and_then(|range| range.end).unwrap_or(len).min(len);
+      let start = start.min(end);
+
+      return Ok(Input::Mapped {
+        map,
+        start: start as usize,
+        end: end as usize,
+      });
+    }
+  }
+
+  if start > 0 && file.seek(SeekFrom::Start(start)).is_err() {
+    // Pipes y archivos especiales no se pueden mover, se descarta el inicio
+    io::copy(&mut (&mut file).take(start), &mut io::sink())?;
+  }
+
+  match bytes.and_then(|range| range.span()) {
+    Some(len) => Input::from_reader(file.take(len)),
+    None => Input::from_reader(file),
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use std::env;
+  use std::fs;
+
+  fn lines(input: &mut Input) -> Vec<String> {
+    let mut lines = Vec::new();
+    input
+      .for_each_line(|line| {
+        lines.push(String::from_utf8_lossy(line).into_owned());
+        true
+      })
+      .unwrap();
+    lines
+  }
+
+  #[test]
+  fn small_files_are_buffered() {
+    let mut input = open("poem.txt", None).unwrap();
+
+    assert_eq!(Strategy::Buffered, input.strategy());
+    assert!(input.head().starts_with("I’m nobody!".as_bytes()));
+    assert_eq!(9, lines(&mut input).len());
+  }
+
+  #[test]
+  fn large_files_are_mapped() {
+    let path = env::temp_dir().join(format!("io_project-{}-large", std::process::id()));
+    let contents = "needle in a haystack\r\n".repeat(60_000);
+    fs::write(&path, &contents).unwrap();
+
+    let mut input = open(&path, None).unwrap();
+
+    assert_eq!(Strategy::Mmap, input.strategy());
+    assert_eq!(binary::DETECTION_BLOCK, input.head().len());
+    assert_eq!(vec!["needle in a haystack"; 60_000], lines(&mut input));
+
+    fs::remove_file(&path).unwrap();
+  }
+
+  #[cfg(unix)]
+  #[test]
+  fn special_files_are_buffered() {
+    let meta = fs::metadata("/dev/null").unwrap();
+    assert_eq!(Strategy::Buffered, strategy(&meta));
+  }
+
+  #[test]
+  fn byte_range_limits_both_strategies() {
+    let path = env::temp_dir().join(format!("io_project-{}-bytes", std::process::id()));
+    let contents = "0123456789\n".repeat(MMAP_THRESHOLD as usize / 10);
+    fs::write(&path, &contents).unwrap();
+    let range = ByteRange::parse("15:38").unwrap();
+
+    let mut mapped = open(&path, Some(range)).unwrap();
+    assert_eq!(Strategy::Mmap, mapped.strategy());
+    assert_eq!(vec!["456789", "0123456789", "01234"], lines(&mut mapped));
+
+    let range = ByteRange::parse("6:13").unwrap();
+    let mut buffered = open("poem.txt", Some(range)).unwrap();
+    assert_eq!(Strategy::Buffered, buffered.strategy());
+    assert_eq!(vec!["nobody!"], lines(&mut buffered));
+
+    fs::remove_file(&path).unwrap();
+  }
+
+  #[test]
+  fn stops_reading_when_asked() {
+    let mut input = Input::from_reader(Cursor::new(b"one\ntwo\nthree".to_vec())).unwrap();
+    let mut seen = Vec::new();
+
+    let scanned = input
+      .for_each_line(|line| {
+        seen.push(line.to_vec());
+        seen.len() < 2
+      })
+      .unwrap();
+
+    assert_eq!(vec![b"one".to_vec(), b"two".to_vec()], seen);
+    assert_eq!(8, scanned);
+  }
+}
@@ -1,6 +1,22 @@
+use std::borrow::Cow; // Texto prestado o propio (UTF-8 invalido o escapado)
 use std::error::Error; // Trait Object Manejo de errores
-use std::fs; // Filesystem maneja archivos
 use std::env; // Variable de entorno
+use std::io; // Errores de lectura de la entrada
+use std::thread; // sleep() entre cada revision del modo follow
+use std::time::{Duration, Instant}; // Tiempos de "--stats"
+
+pub mod binary;
+pub mod follow;
+pub mod input;
+pub mod range;
+pub mod stats;
+
+use binary::BinaryPolicy;
+use follow::Follower;
+use input::Input;
+use memchr::memmem::Finder;
+use range::{ByteRange, LineRange};
+use stats::Stats;
 
 /*
   Con el nuevo conocimiento acerca de los iteradores, podemos
@@ -14,6 +30,12 @@ pub struct Config {
   pub query: String,
   pub filename: String,
   pub case_insensitive: bool,
+  pub follow: bool,
+  pub binary: BinaryPolicy,
+  pub lines: Option<LineRange>,
+  pub bytes: Option<ByteRange>,
+  pub max_count: Option<usize>,
+  pub stats: bool,
 }
 
 // impl Config {
@@ -39,50 +61,365 @@ pub struct Config {
 
 // Reimplementación con iterators
 impl Config {
-  pub fn new(mut args: std::env::Args) -> Result<Config, &'static str> {
+  // Acepta cualquier iterador de Strings (env::args() o un Vec en los tests)
+  pub fn new(
+    mut args: impl Iterator<Item = String>,
+  ) -> Result<Config, &'static str> {
     args.next(); // nombre del programa
 
+    let mut follow = false;
+    let mut binary = BinaryPolicy::Matches;
+    let mut lines = None;
+    let mut bytes = None;
+    let mut max_count = None;
+    let mut stats = false;
+    let mut positional = Vec::new();
+
+    // Las opciones pueden ir antes o despues de query y filename, y su valor
+    // puede ir pegado ("--binary=skip") o como el siguiente argumento
+    while let Some(arg) = args.next() {
+      let (name, inline) = split_option(&arg);
+
+      match name {
+        "-f" | "--follow" => follow = true,
+        "--binary" => {
+          let value = option_value(inline, &mut args)?;
+          binary = BinaryPolicy::from_name(&value)?;
+        }
+        "--lines" => {
+          let value = option_value(inline, &mut args)?;
+          lines = Some(LineRange::parse(&value)?);
+        }
+        "--bytes" => {
+          let value = option_value(inline, &mut args)?;
+          bytes = Some(ByteRange::parse(&value)?);
+        }
+        "-m" | "--max-count" => {
+          let value = option_value(inline, &mut args)?;
+          let max = value.parse().map_err(|_| "Max count must be a number")?;
+          max_count = Some(max);
+        }
+        "--stats" => stats = true,
+        _ if name.starts_with("--") => return Err("Unknown option"),
+        _ => positional.push(arg.clone()),
+      }
+    }
+
+    // El modo follow nunca termina, no habria un resumen que mostrar
+    if follow && stats {
+      return Err("--stats can't be used with --follow");
+    }
+    // Ni un rango fijo, el archivo cambia y se rota mientras se lee
+    if follow && (lines.is_some() || bytes.is_some()) {
+      return Err("--lines and --bytes can't be used with --follow");
+    }
+
+    let mut positional = positional.into_iter();
+
     // next() -> query
-    let query = match args.next() {
+    let query = match positional.next() {
       Some(arg) => arg,
       None => return Err("Didn't get a query string"),
     };
 
     // next() -> filename
-    let filename = match args.next() {
+    let filename = match positional.next() {
       Some(arg) => arg,
       None => return Err("Dind't get a file name"),
     };
 
-    let case_insensitive = env::var("CASE_INSENSITIVE").is_err();
+    let case_insensitive = env::var("CASE_INSENSITIVE").is_ok();
 
     Ok(Config {
       query,
       filename,
       case_insensitive,
+      follow,
+      binary,
+      lines,
+      bytes,
+      max_count,
+      stats,
     })
   }
 }
 
+// "--name=value" -> ("--name", Some("value"))
+fn split_option(arg: &str) -> (&str, Option<&str>) {
+  match arg.find('=') {
+    Some(i) if arg.starts_with("--") => (&arg[..i], Some(&arg[i + 1..])),
+    _ => (arg, None),
+  }
+}
+
+fn option_value(
+  inline: Option<&str>,
+  args: &mut impl Iterator<Item = String>,
+) -> Result<String, &'static str> {
+  match inline {
+    Some(value) => Ok(value.to_string()),
+    None => args.next().ok_or("Missing value for option"),
+  }
+}
+
 // Box<dyn Error> -> Retornará cualquier error (dinamico)
 pub fn run(config: Config) -> Result<(), Box<dyn Error>> {
+  if config.follow {
+    return follow(&config);
+  }
+
   // ? -> Retornará el error de manera esperada
-  let contents = fs::read_to_string(config.filename)?;
+  // Mapeado en memoria o leido con buffer, segun el tipo y tamaño del archivo
+  let started = Instant::now();
+  let mut input = input::open(&config.filename, config.bytes)?;
+  let opened = started.elapsed();
 
-  let results = if config.case_insensitive {
-    search(&config.query, &contents)
-  } else {
-    search_case_insensitive(&config.query, &contents)
-  };
+  let mut stats = search_input(&config, &mut input, |line| println!("{}", line))?;
 
-  for line in results {
-    println!("{}", line);
+  if config.stats {
+    stats.read_time += opened;
+    println!();
+    println!("{}", stats);
   }
 
   // () -> indica que no necesita retornar valor
   Ok(())
 }
 
+// Llama a "emit" con cada linea a imprimir. Deja de leer la entrada al pasar
+// el rango de "--lines" o al llegar a "--max-count" coincidencias.
+//
+// Los tiempos de las estadisticas solo se miden con "--stats", medir cada
+// linea tiene su costo.
+pub fn search_input<F>(
+  config: &Config,
+  input: &mut Input,
+  mut emit: F,
+) -> io::Result<Stats>
+where
+  F: FnMut(&str),
+{
+  let started = Instant::now();
+  let binary = binary::is_binary(input.head());
+
+  // El archivo se abrio aunque no se recorra, cuenta como buscado
+  if binary && config.binary == BinaryPolicy::Skip || config.max_count == Some(0) {
+    return Ok(Stats {
+      files: 1,
+      ..Stats::default()
+    });
+  }
+
+  // Los archivos binarios buscados como texto se escapan
+  let escape = binary && config.binary == BinaryPolicy::Text;
+  let matcher = Matcher::new(config);
+  let mut lines = 0;
+  let mut matched_lines = 0;
+  let mut matches = 0;
+  let mut match_time = Duration::default();
+  let mut output_time = Duration::default();
+
+  let bytes = input.for_each_line(|bytes| {
+    lines += 1;
+
+    if let Some(range) = config.lines {
+      if range.is_past(lines) {
+        return false;
+      }
+      if !range.contains(lines) {
+        return true;
+      }
+    }
+
+    let found = timed(config.stats, &mut match_time, || {
+      matcher
+        .find(bytes, escape)
+        .map(|line| (matcher.count(&line), line))
+    });
+    let (count, line) = match found {
+      Some(found) => found,
+      None => return true,
+    };
+    matched_lines += 1;
+    matches += count;
+
+    if binary && config.binary == BinaryPolicy::Matches {
+      let message = format!("Binary file {} matches", config.filename);
+      timed(config.stats, &mut output_time, || emit(&message));
+      return false;
+    }
+
+    timed(config.stats, &mut output_time, || emit(&line));
+    config.max_count.is_none_or(|max| matched_lines < max as u64)
+  })?;
+
+  let read_time = if config.stats {
+    started.elapsed().saturating_sub(match_time + output_time)
+  } else {
+    Duration::default()
+  };
+
+  Ok(Stats {
+    files: 1,
+    bytes,
+    lines,
+    matched_lines,
+    matches,
+    read_time,
+    match_time,
+    output_time,
+  })
+}
+
+// Suma a "total" lo que tarda "f", si "enabled" es true
+fn timed<T, F: FnOnce() -> T>(enabled: bool, total: &mut Duration, f: F) -> T {
+  if !enabled {
+    return f();
+  }
+
+  let started = Instant::now();
+  let result = f();
+  *total += started.elapsed();
+  result
+}
+
+// Imprime las coincidencias del archivo y se queda esperando lineas nuevas,
+// solo termina al llegar a "--max-count" coincidencias o si ocurre un error
+fn follow(config: &Config) -> Result<(), Box<dyn Error>> {
+  if config.max_count == Some(0) {
+    return Ok(());
+  }
+
+  let mut follower = Follower::open(&config.filename)?;
+  let mut search = FollowSearch::new(config);
+
+  loop {
+    let lines = follower.poll()?;
+    if !search.search(&follower, &lines, |line| println!("{}", line)) {
+      return Ok(());
+    }
+
+    thread::sleep(follow::POLL_INTERVAL);
+  }
+}
+
+// Lo que el modo follow recuerda entre un poll() y otro
+struct FollowSearch<'a> {
+  config: &'a Config,
+  matcher: Matcher,
+  // Ya se imprimio "Binary file X matches" para el archivo actual
+  reported: bool,
+  // Lineas impresas, para "--max-count"
+  printed: usize,
+}
+
+impl<'a> FollowSearch<'a> {
+  fn new(config: &'a Config) -> FollowSearch<'a> {
+    FollowSearch {
+      config,
+      matcher: Matcher::new(config),
+      reported: false,
+      printed: 0,
+    }
+  }
+
+  // Igual que search_input(), con la politica de "--binary" del archivo
+  // que el follower esta leyendo. Devuelve false al llegar a "--max-count".
+  fn search<F: FnMut(&str)>(&mut self, follower: &Follower, lines: &[Vec<u8>], mut emit: F) -> bool {
+    let binary = follower.is_binary();
+    if !binary {
+      self.reported = false;
+    }
+    if binary && self.config.binary == BinaryPolicy::Skip {
+      return true;
+    }
+
+    let escape = binary && self.config.binary == BinaryPolicy::Text;
+    for bytes in lines {
+      let line = match self.matcher.find(bytes, escape) {
+        Some(line) => line,
+        None => continue,
+      };
+
+      if binary && self.config.binary == BinaryPolicy::Matches {
+        if self.reported {
+          return true;
+        }
+        emit(&format!("Binary file {} matches", self.config.filename));
+        self.reported = true;
+      } else {
+        emit(&line);
+      }
+
+      self.printed += 1;
+      if self.config.max_count.is_some_and(|max| self.printed >= max) {
+        return false;
+      }
+    }
+
+    true
+  }
+}
+
+// La query se prepara una sola vez, no en cada linea
+struct Matcher {
+  query: String,
+  case_insensitive: bool,
+  finder: Finder<'static>,
+}
+
+impl Matcher {
+  fn new(config: &Config) -> Matcher {
+    let query = if config.case_insensitive {
+      config.query.to_lowercase()
+    } else {
+      config.query.clone()
+    };
+    let finder = Finder::new(&query).into_owned();
+
+    Matcher {
+      query,
+      case_insensitive: config.case_insensitive,
+      finder,
+    }
+  }
+
+  fn is_match(&self, line: &str) -> bool {
+    if self.case_insensitive {
+      line.to_lowercase().contains(&self.query)
+    } else {
+      line.contains(&self.query)
+    }
+  }
+
+  // Cuantas veces aparece la query en una linea que ya coincidio
+  fn count(&self, line: &str) -> u64 {
+    if self.case_insensitive {
+      line.to_lowercase().matches(&self.query).count() as u64
+    } else {
+      line.matches(&self.query).count() as u64
+    }
+  }
+
+  // Devuelve la linea como texto solo si coincide. Los bytes invalidos se
+  // reemplazan por "�", o se escapan si "escape" es true.
+  fn find<'a>(&self, bytes: &'a [u8], escape: bool) -> Option<Cow<'a, str>> {
+    if escape {
+      return Some(binary::escape(bytes))
+        .filter(|line| self.is_match(line))
+        .map(Cow::Owned);
+    }
+
+    if self.case_insensitive {
+      return Some(String::from_utf8_lossy(bytes)).filter(|line| self.is_match(line));
+    }
+
+    // Sin pasar a minusculas se compara directo sobre los bytes, y solo se
+    // decodifican las lineas que coinciden
+    self.finder.find(bytes).map(|_| String::from_utf8_lossy(bytes))
+  }
+}
+
 // Version vieja
 // pub fn search<'a>(query: &str, contents: &'a str) -> Vec<&'a str> {
 //   let mut results = Vec::new();
@@ -137,6 +474,10 @@ pub fn search_case_insensitive<'a>(
 #[cfg(test)]
 mod test {
   use super::*;
+  use std::cell::Cell;
+  use std::fs::{self, OpenOptions};
+  use std::io::{Cursor, Read, Write};
+  use std::rc::Rc;
 
   #[test]
   fn case_sensitive() {
@@ -163,4 +504,272 @@ Trust me.";
       search_case_insensitive(query, contents)
     );
   }
-}
\ No newline at end of file
+
+  fn args(list: &[&str]) -> impl Iterator<Item = String> {
+    let list: Vec<String> = list.iter().map(|arg| arg.to_string()).collect();
+    list.into_iter()
+  }
+
+  #[test]
+  fn config_with_follow_flag() {
+    let config = Config::new(args(&["minigrep", "--follow", "id", "app.log"]));
+    let config = config.unwrap();
+
+    assert_eq!("id", config.query);
+    assert_eq!("app.log", config.filename);
+    assert!(config.follow);
+  }
+
+  #[test]
+  fn config_with_binary_policy() {
+    let config = Config::new(args(&["minigrep", "id", "a.out", "--binary=skip"]));
+    assert_eq!(BinaryPolicy::Skip, config.unwrap().binary);
+
+    let config = Config::new(args(&["minigrep", "--binary", "text", "id", "a.out"]));
+    assert_eq!(BinaryPolicy::Text, config.unwrap().binary);
+
+    let config = Config::new(args(&["minigrep", "id", "a.out"]));
+    assert_eq!(BinaryPolicy::Matches, config.unwrap().binary);
+
+    assert!(Config::new(args(&["minigrep", "id", "a.out", "--binary"])).is_err());
+  }
+
+  fn config(query: &str, binary: BinaryPolicy) -> Config {
+    Config {
+      query: query.to_string(),
+      filename: String::from("a.out"),
+      case_insensitive: false,
+      follow: false,
+      binary,
+      lines: None,
+      bytes: None,
+      max_count: None,
+      stats: false,
+    }
+  }
+
+  fn output(config: &Config, bytes: &[u8]) -> Vec<String> {
+    let mut input = Input::from_reader(Cursor::new(bytes.to_vec())).unwrap();
+    let mut output = Vec::new();
+
+    search_input(config, &mut input, |line| output.push(line.to_string())).unwrap();
+    output
+  }
+
+  #[test]
+  fn invalid_utf8_is_searched_as_text() {
+    let bytes = b"caf\xe9 latte\nespresso";
+
+    assert_eq!(
+      vec!["caf\u{FFFD} latte"],
+      output(&config("latte", BinaryPolicy::Skip), bytes)
+    );
+  }
+
+  #[test]
+  fn binary_policies() {
+    let bytes = b"\x7fELF\x00\x01\nversion=1.2\x00\r\nother";
+
+    let skip = output(&config("version", BinaryPolicy::Skip), bytes);
+    assert!(skip.is_empty());
+
+    let matches = output(&config("version", BinaryPolicy::Matches), bytes);
+    assert_eq!(vec!["Binary file a.out matches"], matches);
+
+    let matches = output(&config("missing", BinaryPolicy::Matches), bytes);
+    assert!(matches.is_empty());
+
+    let text = output(&config("version", BinaryPolicy::Text), bytes);
+    assert_eq!(vec!["version=1.2\\x00"], text);
+  }
+
+  // Lo que imprime el modo follow en cada poll()
+  // Deja de hacer poll() cuando search() devuelve false
+  fn follow_output(config: &Config, polls: &[&[u8]]) -> Vec<Vec<String>> {
+    let path = env::temp_dir().join(format!(
+      "io_project-{}-follow-{:?}",
+      std::process::id(),
+      config.binary
+    ));
+    fs::write(&path, "").unwrap();
+    let mut follower = Follower::open(&path).unwrap();
+    let mut search = FollowSearch::new(config);
+
+    let mut output = Vec::new();
+    for bytes in polls {
+      let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+      file.write_all(bytes).unwrap();
+
+      let mut printed = Vec::new();
+      let lines = follower.poll().unwrap();
+      let more = search.search(&follower, &lines, |line| printed.push(line.to_string()));
+      output.push(printed);
+      if !more {
+        break;
+      }
+    }
+
+    fs::remove_file(&path).unwrap();
+    output
+  }
+
+  #[test]
+  fn follow_uses_the_binary_policy() {
+    let polls: [&[u8]; 2] = [b"\x7fELF\x00\nversion=1\n", b"version=2\x00\n"];
+
+    let skip = follow_output(&config("version", BinaryPolicy::Skip), &polls);
+    assert_eq!(vec![Vec::<String>::new(), vec![]], skip);
+
+    let matches = follow_output(&config("version", BinaryPolicy::Matches), &polls);
+    assert_eq!(vec![vec!["Binary file a.out matches".to_string()], vec![]], matches);
+
+    let text = follow_output(&config("version", BinaryPolicy::Text), &polls);
+    assert_eq!(vec![vec!["version=1"], vec!["version=2\\x00"]], text);
+
+    let plain = follow_output(&config("id", BinaryPolicy::Skip), &[b"id 1\n", b"other\nid 2\n"]);
+    assert_eq!(vec![vec!["id 1"], vec!["id 2"]], plain);
+  }
+
+  #[test]
+  fn follow_stops_at_max_count() {
+    let mut config = config("id", BinaryPolicy::Matches);
+    config.max_count = Some(3);
+    let polls: [&[u8]; 3] = [b"id 1\nother\n", b"id 2\nid 3\nid 4\n", b"id 5\n"];
+
+    assert_eq!(vec![vec!["id 1"], vec!["id 2", "id 3"]], follow_output(&config, &polls));
+  }
+
+  #[test]
+  fn config_rejects_ranges_with_follow() {
+    assert!(Config::new(args(&["minigrep", "-f", "--lines", "1:5", "id", "app.log"])).is_err());
+    assert!(Config::new(args(&["minigrep", "-f", "--bytes=0:10", "id", "app.log"])).is_err());
+    assert!(Config::new(args(&["minigrep", "-f", "-m", "1", "id", "app.log"])).is_ok());
+  }
+
+  #[test]
+  fn config_with_ranges_and_max_count() {
+    let config = Config::new(args(&[
+      "minigrep",
+      "--lines",
+      "100:500",
+      "--bytes=0:4096",
+      "-m",
+      "3",
+      "id",
+      "app.log",
+    ]))
+    .unwrap();
+
+    assert_eq!(Some(LineRange { first: 100, last: Some(500) }), config.lines);
+    assert_eq!(Some(ByteRange { start: 0, end: Some(4096) }), config.bytes);
+    assert_eq!(Some(3), config.max_count);
+
+    assert!(Config::new(args(&["minigrep", "-m", "many", "id", "app.log"])).is_err());
+    assert!(Config::new(args(&["minigrep", "--lines", "5", "id", "app.log"])).is_err());
+  }
+
+  #[test]
+  fn line_range_and_max_count() {
+    let contents = b"id 1\nid 2\nother\nid 4\nid 5\nid 6";
+
+    let mut lines = config("id", BinaryPolicy::Matches);
+    lines.lines = Some(LineRange::parse("2:4").unwrap());
+    assert_eq!(vec!["id 2", "id 4"], output(&lines, contents));
+
+    let mut max = config("id", BinaryPolicy::Matches);
+    max.max_count = Some(3);
+    assert_eq!(vec!["id 1", "id 2", "id 4"], output(&max, contents));
+
+    max.max_count = Some(0);
+    assert!(output(&max, contents).is_empty());
+  }
+
+  // Cuenta los bytes que realmente se leen de la entrada
+  struct CountingReader {
+    inner: Cursor<Vec<u8>>,
+    read: Rc<Cell<usize>>,
+  }
+
+  impl Read for CountingReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+      let n = self.inner.read(buf)?;
+      self.read.set(self.read.get() + n);
+      Ok(n)
+    }
+  }
+
+  #[test]
+  fn stops_reading_after_range_and_max_count() {
+    let contents = "request-1 ok\n".repeat(100_000).into_bytes();
+
+    for (lines, max_count) in [(Some("10:20"), None), (None, Some(5))] {
+      let read = Rc::new(Cell::new(0));
+      let reader = CountingReader {
+        inner: Cursor::new(contents.clone()),
+        read: Rc::clone(&read),
+      };
+      let mut input = Input::from_reader(reader).unwrap();
+
+      let mut config = config("request", BinaryPolicy::Matches);
+      config.lines = lines.map(|range| LineRange::parse(range).unwrap());
+      config.max_count = max_count;
+
+      let mut found = 0;
+      search_input(&config, &mut input, |_| found += 1).unwrap();
+
+      assert!(found > 0);
+      assert!(read.get() < contents.len() / 10);
+    }
+  }
+
+  #[test]
+  fn config_with_stats() {
+    assert!(Config::new(args(&["minigrep", "--stats", "id", "app.log"])).unwrap().stats);
+    assert!(Config::new(args(&["minigrep", "--stats", "-f", "id", "app.log"])).is_err());
+  }
+
+  #[test]
+  fn search_statistics() {
+    let mut config = config("id", BinaryPolicy::Matches);
+    config.stats = true;
+    config.max_count = Some(2);
+    let contents = b"id 1 id\nnone\nid 3\nid 4\n";
+    let mut input = Input::from_reader(Cursor::new(contents.to_vec())).unwrap();
+
+    let stats = search_input(&config, &mut input, |_| ()).unwrap();
+
+    assert_eq!(1, stats.files);
+    assert_eq!(3, stats.lines);
+    assert_eq!(18, stats.bytes);
+    assert_eq!(2, stats.matched_lines);
+    assert_eq!(3, stats.matches);
+
+    // Sin "--stats" se cuenta igual pero no se mide el tiempo
+    config.stats = false;
+    let mut input = Input::from_reader(Cursor::new(contents.to_vec())).unwrap();
+    let stats = search_input(&config, &mut input, |_| ()).unwrap();
+
+    assert_eq!((1, 3, 18, 2, 3), (stats.files, stats.lines, stats.bytes, stats.matched_lines, stats.matches));
+    assert_eq!(Duration::default(), stats.read_time);
+    assert_eq!(Duration::default(), stats.match_time);
+    assert_eq!(Duration::default(), stats.output_time);
+  }
+
+  #[test]
+  fn skipped_files_are_counted() {
+    let mut skip = config("id", BinaryPolicy::Skip);
+    let mut input = Input::from_reader(Cursor::new(b"id\x00".to_vec())).unwrap();
+    let stats = search_input(&skip, &mut input, |_| ()).unwrap();
+    assert_eq!(Stats { files: 1, ..Stats::default() }, stats);
+
+    skip.max_count = Some(0);
+    let mut input = Input::from_reader(Cursor::new(b"id 1\n".to_vec())).unwrap();
+    let stats = search_input(&skip, &mut input, |_| ()).unwrap();
+    assert_eq!(Stats { files: 1, ..Stats::default() }, stats);
+  }
+
+  #[test]
+  fn config_rejects_unknown_option() {
+    assert!(Config::new(args(&["minigrep", "--nope", "id", "app.log"])).is_err());
+  }
+}
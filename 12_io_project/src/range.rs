@@ -0,0 +1,118 @@
+/* -------------------- Rangos de busqueda -------------------- */
+/*
+  Restringen la busqueda a una parte de la entrada, con la forma
+  "INICIO:FIN" donde cualquiera de los dos puede omitirse:
+
+  - Lineas ("--lines 100:500"): se cuentan desde 1 y el fin se incluye,
+    como en "sed -n 100,500p".
+  - Bytes ("--bytes 4096:8192"): son posiciones desde 0 y el fin no se
+    incluye. Si el inicio cae en medio de una linea, esa linea se busca
+    desde ese byte.
+
+  Con "--bytes" las lineas de "--lines" se cuentan desde el inicio del rango.
+*/
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct LineRange {
+  pub first: u64,
+  pub last: Option<u64>,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct ByteRange {
+  pub start: u64,
+  pub end: Option<u64>,
+}
+
+impl LineRange {
+  pub fn parse(text: &str) -> Result<LineRange, &'static str> {
+    let (first, last) = parse_bounds(text).ok_or("Line range must be FIRST:LAST")?;
+    let first = first.unwrap_or(1);
+
+    if first == 0 || last.is_some_and(|last| last < first) {
+      return Err("Line range must start at 1 and not end before it starts");
+    }
+
+    Ok(LineRange { first, last })
+  }
+
+  pub fn contains(&self, line: u64) -> bool {
+    line >= self.first && !self.is_past(line)
+  }
+
+  // Despues de esta linea ya no hay nada que leer
+  pub fn is_past(&self, line: u64) -> bool {
+    self.last.is_some_and(|last| line > last)
+  }
+}
+
+impl ByteRange {
+  pub fn parse(text: &str) -> Result<ByteRange, &'static str> {
+    let (start, end) = parse_bounds(text).ok_or("Byte range must be START:END")?;
+    let start = start.unwrap_or(0);
+
+    if end.is_some_and(|end| end < start) {
+      return Err("Byte range must not end before it starts");
+    }
+
+    Ok(ByteRange { start, end })
+  }
+
+  pub fn span(&self) -> Option<u64> {
+    self.end.map(|end| end - self.start)
+  }
+}
+
+// "10:20" -> (Some(10), Some(20)), "10:" -> (Some(10), None)
+fn parse_bounds(text: &str) -> Option<(Option<u64>, Option<u64>)> {
+  let mut parts = text.splitn(2, ':');
+  let start = parse_bound(parts.next()?)?;
+  let end = parse_bound(parts.next()?)?;
+
+  Some((start, end))
+}
+
+fn parse_bound(text: &str) -> Option<Option<u64>> {
+  if text.is_empty() {
+    Some(None)
+  } else {
+    text.parse().ok().map(Some)
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn parses_line_ranges() {
+    let range = LineRange::parse("100:500").unwrap();
+    assert_eq!(LineRange { first: 100, last: Some(500) }, range);
+    assert!(!range.contains(99));
+    assert!(range.contains(100));
+    assert!(range.contains(500));
+    assert!(range.is_past(501));
+
+    assert_eq!(LineRange { first: 1, last: Some(5) }, LineRange::parse(":5").unwrap());
+    assert_eq!(LineRange { first: 7, last: None }, LineRange::parse("7:").unwrap());
+  }
+
+  #[test]
+  fn rejects_invalid_line_ranges() {
+    assert!(LineRange::parse("100").is_err());
+    assert!(LineRange::parse("0:5").is_err());
+    assert!(LineRange::parse("9:5").is_err());
+    assert!(LineRange::parse("a:b").is_err());
+  }
+
+  #[test]
+  fn parses_byte_ranges() {
+    let range = ByteRange::parse("4096:8192").unwrap();
+    assert_eq!(ByteRange { start: 4096, end: Some(8192) }, range);
+    assert_eq!(Some(4096), range.span());
+
+    assert_eq!(ByteRange { start: 0, end: Some(10) }, ByteRange::parse(":10").unwrap());
+    assert_eq!(None, ByteRange::parse("10:").unwrap().span());
+    assert!(ByteRange::parse("10:5").is_err());
+  }
+}
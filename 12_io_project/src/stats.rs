@@ -0,0 +1,83 @@
+/* -------------------- Estadisticas de la busqueda -------------------- */
+/*
+  Con "--stats" se imprime un resumen despues de las coincidencias. El
+  tiempo se divide en fases para saber si una busqueda esta limitada por
+  la lectura (I/O) o por la comparacion de lineas (CPU):
+
+  - read:   abrir el archivo y recorrerlo buscando los fines de linea.
+  - match:  comparar cada linea con la query.
+  - output: imprimir las lineas que coinciden.
+
+  Un archivo que se salta sin recorrerlo ("--binary skip" o "--max-count
+  0") igual cuenta como buscado, con 0 bytes y 0 lineas.
+*/
+
+use std::fmt;
+use std::time::Duration;
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Stats {
+  pub files: u64,
+  pub bytes: u64,
+  pub lines: u64,
+  pub matched_lines: u64,
+  pub matches: u64,
+  pub read_time: Duration,
+  pub match_time: Duration,
+  pub output_time: Duration,
+}
+
+impl Stats {
+  pub fn elapsed(&self) -> Duration {
+    self.read_time + self.match_time + self.output_time
+  }
+}
+
+impl fmt::Display for Stats {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    writeln!(f, "{} files searched", self.files)?;
+    writeln!(f, "{} bytes scanned", self.bytes)?;
+    writeln!(f, "{} lines scanned", self.lines)?;
+    writeln!(f, "{} matched lines", self.matched_lines)?;
+    writeln!(f, "{} matches", self.matches)?;
+    write!(
+      f,
+      "{:.6} seconds elapsed (read {:.6}, match {:.6}, output {:.6})",
+      self.elapsed().as_secs_f64(),
+      self.read_time.as_secs_f64(),
+      self.match_time.as_secs_f64(),
+      self.output_time.as_secs_f64()
+    )
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn summary() {
+    let stats = Stats {
+      files: 1,
+      bytes: 2048,
+      lines: 40,
+      matched_lines: 3,
+      matches: 4,
+      read_time: Duration::from_millis(3),
+      match_time: Duration::from_millis(2),
+      output_time: Duration::from_micros(500),
+    };
+
+    assert_eq!(Duration::from_micros(5500), stats.elapsed());
+    assert_eq!(
+      "\
+1 files searched
+2048 bytes scanned
+40 lines scanned
+3 matched lines
+4 matches
+0.005500 seconds elapsed (read 0.003000, match 0.002000, output 0.000500)",
+      stats.to_string()
+    );
+  }
+}
@@ -0,0 +1,207 @@
+// Client for the multiplayer server: sends the lines typed by the player
+// as guesses and prints the server messages as they arrive, from another
+// thread so the other players' guesses show up while typing.
+//
+// The input is read on a third thread: reading stdin can't be interrupted,
+// and the game has to end as soon as the server says BYE, even if the
+// player is in the middle of typing.
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{Shutdown, TcpStream, ToSocketAddrs};
+use std::sync::mpsc;
+use std::thread;
+
+use crate::i18n::Catalog;
+use crate::puzzle::Command;
+
+// Turns a protocol message into something readable
+pub fn describe(message: &str, catalog: &Catalog) -> String {
+    let words: Vec<&str> = message.split_whitespace().collect();
+
+    match words.as_slice() {
+        ["WELCOME", name, round, low, high] => catalog.format(
+            "client.welcome",
+            &[("name", name), ("round", round), ("low", low), ("high", high)],
+        ),
+        ["JOIN", name] => catalog.format("client.join", &[("name", name)]),
+        ["LEAVE", name] => catalog.format("client.leave", &[("name", name)]),
+        ["FEEDBACK", name, guess, "too-small"] => {
+            catalog.format("client.too-small", &[("name", name), ("guess", guess)])
+        }
+        ["FEEDBACK", name, guess, "too-big"] => catalog.format("client.too-big", &[("name", name), ("guess", guess)]),
+        ["WINNER", name, secret, guesses] => {
+            let guesses = match guesses.parse() {
+                Ok(guesses) => catalog.plural("guesses", guesses),
+                Err(_) => guesses.to_string(),
+            };
+            catalog.format("client.winner", &[("name", name), ("secret", secret), ("guesses", &guesses)])
+        }
+        ["ROUND", round, low, high] => {
+            catalog.format("client.round", &[("round", round), ("low", low), ("high", high)])
+        }
+        // The reason comes from the server, in English
+        ["ERROR", ..] => catalog.format("client.error", &[("message", &message["ERROR".len()..].trim())]),
+        ["BYE"] => catalog.text("client.bye").to_string(),
+        _ => message.to_string(),
+    }
+}
+
+// What the main thread of the client waits for
+enum Event {
+    Typed(io::Result<String>),
+    EndOfInput,
+    // The printer thread saw BYE or the connection closed
+    ServerClosed,
+}
+
+// Returns the output when the server says BYE or closes the connection.
+// "quit" or the end of the input leave the game. If the server ends first,
+// the thread reading "input" is left behind, blocked until the next line.
+pub fn run<A, R, W>(addr: A, name: &str, catalog: Catalog, input: R, output: W) -> io::Result<W>
+where
+    A: ToSocketAddrs,
+    R: BufRead + Send + 'static,
+    W: Write + Send + 'static,
+{
+    let mut stream = TcpStream::connect(addr)?;
+    let reader = BufReader::new(stream.try_clone()?);
+    writeln!(stream, "NAME {}", name)?;
+
+    let (events, received) = mpsc::channel();
+
+    let closed = events.clone();
+    let printer = thread::spawn(move || -> io::Result<W> {
+        let printed = print(reader, output, &catalog);
+        let _ = closed.send(Event::ServerClosed);
+        printed
+    });
+
+    thread::spawn(move || {
+        for line in input.lines() {
+            // Nobody is waiting for it anymore
+            if events.send(Event::Typed(line)).is_err() {
+                return;
+            }
+        }
+        let _ = events.send(Event::EndOfInput);
+    });
+
+    for event in received {
+        let line = match event {
+            Event::Typed(line) => line?,
+            Event::EndOfInput | Event::ServerClosed => break,
+        };
+        let line = line.trim();
+
+        if Command::parse(line) == Some(Command::Quit) {
+            break;
+        }
+        // The server is gone: nothing else to send
+        if writeln!(stream, "GUESS {}", line).is_err() {
+            break;
+        }
+    }
+    // The server may already have closed the connection
+    if writeln!(stream, "QUIT").is_err() {
+        let _ = stream.shutdown(Shutdown::Both);
+    }
+
+    printer.join().expect("the printer thread panicked")
+}
+
+// Prints the server messages until BYE or the end of the connection
+fn print<R: BufRead, W: Write>(reader: R, mut output: W, catalog: &Catalog) -> io::Result<W> {
+    for line in reader.lines() {
+        let line = line?;
+        writeln!(output, "{}", describe(&line, catalog))?;
+        output.flush()?;
+
+        if line == "BYE" {
+            break;
+        }
+    }
+
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::difficulty::Difficulty;
+    use crate::game::Game;
+    use crate::i18n::Locale;
+    use crate::server::Server;
+    use std::io::{Cursor, Read};
+
+    #[test]
+    fn describes_messages() {
+        let catalog = Catalog::new(Locale::English);
+        let describe = |message| describe(message, &catalog);
+
+        assert_eq!(
+            "Welcome ana! Round 1: guess a number between 1 and 100",
+            describe("WELCOME ana 1 1 100")
+        );
+        assert_eq!("ana guessed 20: too small", describe("FEEDBACK ana 20 too-small"));
+        assert_eq!("ana wins! The number was 42 (3 guesses)", describe("WINNER ana 42 3"));
+        assert_eq!("ana wins! The number was 42 (1 guess)", describe("WINNER ana 42 1"));
+        assert_eq!("Error: unknown command", describe("ERROR unknown command"));
+        assert_eq!("Server closed the game", describe("BYE"));
+        assert_eq!("SOMETHING new", describe("SOMETHING new"));
+    }
+
+    #[test]
+    fn describes_messages_in_spanish() {
+        let catalog = Catalog::new(Locale::Spanish);
+
+        assert_eq!("ana dijo 20: muy grande", describe("FEEDBACK ana 20 too-big", &catalog));
+        assert_eq!("¡ana gana! El número era 42 (3 intentos)", describe("WINNER ana 42 3", &catalog));
+    }
+
+    #[test]
+    fn plays_against_a_local_server() {
+        let secret = Game::new(Difficulty::Normal, 4).secret();
+        let server = Server::bind("127.0.0.1:0", Difficulty::Normal, 4).unwrap();
+        let addr = server.local_addr().unwrap();
+        let server = thread::spawn(move || server.run(Some(1)));
+
+        let script = format!("abc\n{}\n", secret);
+        let catalog = Catalog::new(Locale::English);
+        let output = run(addr, "ana", catalog, Cursor::new(script), Vec::new()).unwrap();
+        server.join().unwrap().unwrap();
+
+        let expected = [
+            "Welcome ana! Round 1: guess a number between 1 and 100",
+            "ana joined",
+            "Error: \"abc\" is not a number",
+            &format!("ana wins! The number was {} (1 guess)", secret),
+            "Server closed the game",
+        ];
+        assert_eq!(expected.join("\n") + "\n", String::from_utf8(output).unwrap());
+    }
+
+    // Input that never ends: a player who doesn't type anything else
+    struct Silent(mpsc::Receiver<()>);
+
+    impl Read for Silent {
+        fn read(&mut self, _buf: &mut [u8]) -> io::Result<usize> {
+            let _ = self.0.recv();
+            Ok(0)
+        }
+    }
+
+    #[test]
+    fn stops_when_the_server_ends_the_game() {
+        let secret = Game::new(Difficulty::Normal, 6).secret();
+        let server = Server::bind("127.0.0.1:0", Difficulty::Normal, 6).unwrap();
+        let addr = server.local_addr().unwrap();
+        let server = thread::spawn(move || server.run(Some(1)));
+
+        // Kept until the end of the test, so the input stays open
+        let (_typing, silent) = mpsc::channel();
+        let input = BufReader::new(Cursor::new(format!("{}\n", secret)).chain(Silent(silent)));
+        let output = run(addr, "ana", Catalog::new(Locale::English), input, Vec::new()).unwrap();
+        server.join().unwrap().unwrap();
+
+        assert!(String::from_utf8(output).unwrap().ends_with("Server closed the game\n"));
+    }
+}
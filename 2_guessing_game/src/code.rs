@@ -0,0 +1,185 @@
+// Mastermind-style variant: break a code of LENGTH digits from 1 to
+// SYMBOLS (repeats allowed). The feedback only says how many digits are
+// in the right place and how many are in the code but somewhere else.
+use rand::{Rng, SeedableRng};
+use rand::prng::ChaChaRng;
+
+use crate::game::State;
+use crate::i18n::Catalog;
+use crate::puzzle::Puzzle;
+
+pub const LENGTH: usize = 4;
+pub const SYMBOLS: u8 = 6;
+pub const MAX_ATTEMPTS: u32 = 10;
+
+// (right place, wrong place); every digit of the secret is counted once
+pub fn score(secret: &[u8], guess: &[u8]) -> (u32, u32) {
+    let mut right = 0;
+    let mut secret_left = [0u32; 10];
+    let mut guess_left = [0u32; 10];
+
+    for (&s, &g) in secret.iter().zip(guess) {
+        if s == g {
+            right += 1;
+        } else {
+            secret_left[usize::from(s)] += 1;
+            guess_left[usize::from(g)] += 1;
+        }
+    }
+
+    let misplaced = secret_left.iter().zip(&guess_left).map(|(&s, &g)| s.min(g)).sum();
+
+    (right, misplaced)
+}
+
+// Digits can be separated by spaces: "1234" or "1 2 3 4"
+pub fn parse(input: &str, catalog: &Catalog) -> Result<Vec<u8>, String> {
+    let mut code = Vec::new();
+
+    for c in input.chars().filter(|c| !c.is_whitespace()) {
+        match c.to_digit(10) {
+            Some(digit) if (1..=u32::from(SYMBOLS)).contains(&digit) => code.push(digit as u8),
+            _ => return Err(catalog.format("code.digits", &[("symbols", &SYMBOLS)])),
+        }
+    }
+
+    if code.len() != LENGTH {
+        return Err(catalog.format("code.length", &[("length", &LENGTH)]));
+    }
+
+    Ok(code)
+}
+
+pub struct Code {
+    secret: Vec<u8>,
+    attempts: u32,
+    state: State,
+}
+
+impl Code {
+    pub fn new(seed: u64) -> Code {
+        let mut rng = ChaChaRng::seed_from_u64(seed);
+
+        Code::with_secret((0..LENGTH).map(|_| rng.gen_range(1, SYMBOLS + 1)).collect())
+    }
+
+    pub fn with_secret(secret: Vec<u8>) -> Code {
+        Code {
+            secret,
+            attempts: 0,
+            state: State::Playing,
+        }
+    }
+}
+
+impl Puzzle for Code {
+    fn rules(&self, catalog: &Catalog) -> String {
+        let attempts = catalog.plural("attempts", u64::from(MAX_ATTEMPTS));
+        catalog.format(
+            "code.rules",
+            &[("length", &LENGTH), ("symbols", &SYMBOLS), ("attempts", &attempts)],
+        )
+    }
+
+    fn prompt(&self, catalog: &Catalog) -> String {
+        catalog.text("code.prompt").to_string()
+    }
+
+    fn max_attempts(&self) -> u32 {
+        MAX_ATTEMPTS
+    }
+
+    fn attempts(&self) -> u32 {
+        self.attempts
+    }
+
+    fn state(&self) -> State {
+        self.state
+    }
+
+    fn attempt(&mut self, input: &str, catalog: &Catalog) -> Result<String, String> {
+        assert_eq!(State::Playing, self.state, "the game is over");
+
+        let guess = parse(input, catalog)?;
+        self.attempts += 1;
+
+        let (right, misplaced) = score(&self.secret, &guess);
+        let feedback = catalog.format("code.feedback", &[("right", &right), ("misplaced", &misplaced)]);
+
+        if guess == self.secret {
+            self.state = State::Won;
+            return Ok(format!("{}\n{}", feedback, catalog.text("win")));
+        }
+        if self.attempts == MAX_ATTEMPTS {
+            self.state = State::Lost;
+        }
+
+        Ok(feedback)
+    }
+
+    fn quit(&mut self) {
+        assert_eq!(State::Playing, self.state, "the game is over");
+        self.state = State::Quit;
+    }
+
+    fn reveal(&self, catalog: &Catalog) -> String {
+        let code: String = self.secret.iter().map(|digit| digit.to_string()).collect();
+        catalog.format("code.reveal", &[("code", &code)])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::i18n::Locale;
+
+    #[test]
+    fn scores_guesses() {
+        assert_eq!((4, 0), score(&[1, 2, 3, 4], &[1, 2, 3, 4]));
+        assert_eq!((0, 4), score(&[1, 2, 3, 4], &[4, 3, 2, 1]));
+        assert_eq!((0, 0), score(&[1, 2, 3, 4], &[5, 5, 6, 6]));
+        assert_eq!((2, 1), score(&[1, 2, 3, 4], &[1, 3, 5, 4]));
+    }
+
+    #[test]
+    fn repeated_digits_are_counted_once() {
+        assert_eq!((1, 0), score(&[1, 2, 3, 4], &[1, 1, 1, 1]));
+        assert_eq!((0, 2), score(&[1, 1, 2, 2], &[2, 2, 3, 3]));
+        assert_eq!((1, 1), score(&[6, 6, 5, 5], &[6, 5, 1, 1]));
+    }
+
+    #[test]
+    fn parses_codes() {
+        let catalog = Catalog::new(Locale::English);
+        assert_eq!(Ok(vec![1, 2, 3, 4]), parse("1234\n", &catalog));
+        assert_eq!(Ok(vec![6, 5, 6, 1]), parse(" 6 5 6 1 ", &catalog));
+        assert_eq!(Err("Use digits from 1 to 6".to_string()), parse("1237", &catalog));
+        assert_eq!(Err("Use digits from 1 to 6".to_string()), parse("12a4", &catalog));
+        assert_eq!(Err("The code has 4 digits".to_string()), parse("123", &catalog));
+    }
+
+    #[test]
+    fn same_seed_same_code() {
+        let code = Code::new(8);
+
+        assert_eq!(code.secret, Code::new(8).secret);
+        assert_eq!(LENGTH, code.secret.len());
+        assert!(code.secret.iter().all(|digit| (1..=SYMBOLS).contains(digit)));
+    }
+
+    #[test]
+    fn plays_until_broken() {
+        let catalog = Catalog::new(Locale::English);
+        let mut code = Code::with_secret(vec![1, 1, 2, 2]);
+
+        assert_eq!(Ok("1 in the right place, 2 in the wrong place".to_string()), code.attempt("1213", &catalog));
+        assert!(code.attempt("11", &catalog).is_err());
+        assert_eq!(
+            Ok("4 in the right place, 0 in the wrong place\nYou win!".to_string()),
+            code.attempt("1122", &catalog)
+        );
+        assert_eq!(State::Won, code.state());
+        assert_eq!(2, code.attempts());
+        assert_eq!("The code was 1122", code.reveal(&catalog));
+    }
+}
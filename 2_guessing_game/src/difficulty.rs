@@ -0,0 +1,97 @@
+// Difficulty levels: each one has its own range of secret numbers,
+// a maximum number of attempts and a score multiplier.
+use std::time::Duration;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Difficulty {
+    Easy,
+    Normal,
+    Hard,
+}
+
+// Guessing faster than this gives a time bonus
+const BONUS_SECONDS: u64 = 60;
+
+impl Difficulty {
+    pub const ALL: [Difficulty; 3] = [Difficulty::Easy, Difficulty::Normal, Difficulty::Hard];
+
+    // Accepts the name or the number shown in the menu
+    pub fn parse(text: &str) -> Option<Difficulty> {
+        match text.trim().to_lowercase().as_str() {
+            "1" | "easy" => Some(Difficulty::Easy),
+            "2" | "normal" => Some(Difficulty::Normal),
+            "3" | "hard" => Some(Difficulty::Hard),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Difficulty::Easy => "easy",
+            Difficulty::Normal => "normal",
+            Difficulty::Hard => "hard",
+        }
+    }
+
+    // Inclusive range of the secret number
+    pub fn range(self) -> (u32, u32) {
+        match self {
+            Difficulty::Easy => (1, 50),
+            Difficulty::Normal => (1, 100),
+            Difficulty::Hard => (1, 1000),
+        }
+    }
+
+    pub fn max_attempts(self) -> u32 {
+        match self {
+            Difficulty::Easy => 10,
+            Difficulty::Normal => 7,
+            Difficulty::Hard => 10,
+        }
+    }
+
+    fn multiplier(self) -> u32 {
+        match self {
+            Difficulty::Easy => 1,
+            Difficulty::Normal => 2,
+            Difficulty::Hard => 3,
+        }
+    }
+
+    // Up to 100 points for the attempts left and 50 for the time,
+    // multiplied by the difficulty
+    pub fn score(self, attempts: u32, elapsed: Duration) -> u32 {
+        let max = self.max_attempts();
+        let attempts_left = max.saturating_sub(attempts) + 1;
+        let attempt_points = 100 * attempts_left / max;
+
+        let seconds_left = BONUS_SECONDS.saturating_sub(elapsed.as_secs());
+        let time_points = (50 * seconds_left / BONUS_SECONDS) as u32;
+
+        (attempt_points + time_points) * self.multiplier()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_names_and_menu_numbers() {
+        assert_eq!(Some(Difficulty::Easy), Difficulty::parse("1"));
+        assert_eq!(Some(Difficulty::Hard), Difficulty::parse(" Hard\n"));
+        assert_eq!(None, Difficulty::parse("impossible"));
+    }
+
+    #[test]
+    fn score_rewards_few_attempts_and_speed() {
+        let instant = Duration::from_secs(0);
+        let slow = Duration::from_secs(600);
+
+        assert_eq!(150, Difficulty::Easy.score(1, instant));
+        assert_eq!(10, Difficulty::Easy.score(10, slow));
+        assert_eq!(450, Difficulty::Hard.score(1, instant));
+        assert!(Difficulty::Normal.score(3, instant) > Difficulty::Normal.score(4, instant));
+        assert!(Difficulty::Normal.score(3, instant) > Difficulty::Normal.score(3, slow));
+    }
+}
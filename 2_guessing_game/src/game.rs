@@ -0,0 +1,405 @@
+// The game as a state machine, separated from stdin/stdout and from
+// thread_rng() so it can be driven by tests with a fixed seed and
+// scripted input.
+use std::cmp::Ordering;
+use rand::{Rng, SeedableRng};
+use rand::prng::ChaChaRng;
+
+use crate::difficulty::Difficulty;
+use crate::guess::{Guess, GuessError};
+use crate::i18n::Catalog;
+use crate::puzzle::Puzzle;
+use crate::solver::Interval;
+
+// Result of a single guess
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    TooSmall,
+    TooBig,
+    Win,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum State {
+    Playing,
+    Won,
+    Lost,
+    // The player left before the end
+    Quit,
+}
+
+// Distance to the secret compared with the previous guess
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Trend {
+    Warmer,
+    Colder,
+    Same,
+}
+
+pub struct Game {
+    difficulty: Difficulty,
+    secret: u32,
+    history: Vec<(u32, Outcome)>,
+    state: State,
+}
+
+impl Game {
+    // ChaCha always gives the same numbers for the same seed, on every
+    // platform and version of rand 0.5
+    pub fn new(difficulty: Difficulty, seed: u64) -> Game {
+        let mut rng = ChaChaRng::seed_from_u64(seed);
+        let (low, high) = difficulty.range();
+
+        // gen_range excludes the upper bound
+        Game::with_secret(difficulty, rng.gen_range(low, high + 1))
+    }
+
+    pub fn with_secret(difficulty: Difficulty, secret: u32) -> Game {
+        Game {
+            difficulty,
+            secret,
+            history: Vec::new(),
+            state: State::Playing,
+        }
+    }
+
+    pub fn difficulty(&self) -> Difficulty {
+        self.difficulty
+    }
+
+    pub fn secret(&self) -> u32 {
+        self.secret
+    }
+
+    pub fn attempts(&self) -> u32 {
+        self.history.len() as u32
+    }
+
+    pub fn state(&self) -> State {
+        self.state
+    }
+
+    // Invalid input doesn't count as an attempt
+    pub fn guess(&mut self, input: &str) -> Result<Outcome, GuessError> {
+        let (low, high) = self.difficulty.range();
+        let guess = Guess::parse(input, low, high)?;
+
+        Ok(self.submit(guess))
+    }
+
+    // Panics if the game is already over
+    pub fn submit(&mut self, guess: Guess) -> Outcome {
+        assert_eq!(State::Playing, self.state, "the game is over");
+
+        let outcome = match guess.value().cmp(&self.secret) {
+            Ordering::Less => Outcome::TooSmall,
+            Ordering::Greater => Outcome::TooBig,
+            Ordering::Equal => Outcome::Win,
+        };
+        self.history.push((guess.value(), outcome));
+
+        if outcome == Outcome::Win {
+            self.state = State::Won;
+        } else if self.attempts() == self.difficulty.max_attempts() {
+            self.state = State::Lost;
+        }
+
+        outcome
+    }
+
+    // None until there are two guesses
+    pub fn trend(&self) -> Option<Trend> {
+        let distance = |guess: u32| (i64::from(guess) - i64::from(self.secret)).abs();
+
+        match self.history.as_slice() {
+            [.., (previous, _), (last, _)] => Some(match distance(*last).cmp(&distance(*previous)) {
+                Ordering::Less => Trend::Warmer,
+                Ordering::Greater => Trend::Colder,
+                Ordering::Equal => Trend::Same,
+            }),
+            _ => None,
+        }
+    }
+
+    // The numbers still possible after the previous guesses
+    pub fn narrowed(&self) -> (u32, u32) {
+        let (low, high) = self.difficulty.range();
+        let mut interval = Interval { low, high };
+
+        for &(guess, outcome) in &self.history {
+            interval.update(guess, outcome);
+        }
+
+        (interval.low, interval.high)
+    }
+}
+
+impl Puzzle for Game {
+    fn rules(&self, catalog: &Catalog) -> String {
+        let (low, high) = self.difficulty.range();
+        let attempts = catalog.plural("attempts", u64::from(self.difficulty.max_attempts()));
+
+        catalog.format("number.rules", &[("low", &low), ("high", &high), ("attempts", &attempts)])
+    }
+
+    fn prompt(&self, catalog: &Catalog) -> String {
+        catalog.text("number.prompt").to_string()
+    }
+
+    fn max_attempts(&self) -> u32 {
+        self.difficulty.max_attempts()
+    }
+
+    fn attempts(&self) -> u32 {
+        self.history.len() as u32
+    }
+
+    fn state(&self) -> State {
+        self.state
+    }
+
+    fn attempt(&mut self, input: &str, catalog: &Catalog) -> Result<String, String> {
+        let message = match self.guess(input).map_err(|e| e.message(catalog))? {
+            Outcome::TooSmall => catalog.text("number.too-small"),
+            Outcome::TooBig => catalog.text("number.too-big"),
+            Outcome::Win => return Ok(catalog.text("win").to_string()),
+        };
+
+        let trend = match self.trend() {
+            _ if self.state != State::Playing => None,
+            Some(Trend::Warmer) => Some(catalog.text("number.warmer")),
+            Some(Trend::Colder) => Some(catalog.text("number.colder")),
+            Some(Trend::Same) => Some(catalog.text("number.same")),
+            None => None,
+        };
+
+        Ok(match trend {
+            Some(trend) => format!("{}\n{}", message, trend),
+            None => message.to_string(),
+        })
+    }
+
+    fn quit(&mut self) {
+        assert_eq!(State::Playing, self.state, "the game is over");
+        self.state = State::Quit;
+    }
+
+    fn reveal(&self, catalog: &Catalog) -> String {
+        catalog.format("number.reveal", &[("secret", &self.secret)])
+    }
+
+    fn hint(&self, catalog: &Catalog) -> Option<String> {
+        let (low, high) = self.narrowed();
+        Some(catalog.format("number.hint", &[("low", &low), ("high", &high)]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::i18n::Locale;
+    use crate::puzzle;
+    use std::io::Cursor;
+
+    fn play_script(game: &mut Game, script: &str) -> (State, String) {
+        let mut output = Vec::new();
+        let catalog = Catalog::new(Locale::English);
+        let state = puzzle::play(game, &catalog, Cursor::new(script), &mut output).unwrap();
+
+        (state, String::from_utf8(output).unwrap())
+    }
+
+    #[test]
+    fn same_seed_same_secret() {
+        for &difficulty in Difficulty::ALL.iter() {
+            let (low, high) = difficulty.range();
+
+            for seed in 0..50 {
+                let secret = Game::new(difficulty, seed).secret();
+                assert_eq!(secret, Game::new(difficulty, seed).secret());
+                assert!(secret >= low && secret <= high);
+            }
+        }
+    }
+
+    #[test]
+    fn different_seeds_spread_the_secret() {
+        let secrets: Vec<u32> = (0..20).map(|seed| Game::new(Difficulty::Hard, seed).secret()).collect();
+
+        assert!(secrets.iter().any(|&secret| secret != secrets[0]));
+    }
+
+    #[test]
+    fn guesses_compare_with_the_secret() {
+        let mut game = Game::with_secret(Difficulty::Normal, 42);
+
+        assert_eq!(Ok(Outcome::TooSmall), game.guess("10\n"));
+        assert_eq!(Ok(Outcome::TooBig), game.guess(" 90 "));
+        assert_eq!(State::Playing, game.state());
+        assert_eq!(Ok(Outcome::Win), game.guess("42"));
+        assert_eq!(State::Won, game.state());
+        assert_eq!(3, game.attempts());
+    }
+
+    #[test]
+    fn invalid_input_is_not_an_attempt() {
+        let mut game = Game::with_secret(Difficulty::Normal, 42);
+
+        assert!(game.guess("forty two").is_err());
+        assert!(game.guess("-3").is_err());
+        assert!(game.guess("101").is_err());
+        assert!(game.guess("").is_err());
+        assert_eq!(0, game.attempts());
+    }
+
+    #[test]
+    fn last_attempt_loses() {
+        let mut game = Game::with_secret(Difficulty::Normal, 42);
+
+        for _ in 0..6 {
+            assert_eq!(Ok(Outcome::TooSmall), game.guess("1"));
+        }
+        assert_eq!(State::Playing, game.state());
+        assert_eq!(Ok(Outcome::TooBig), game.guess("99"));
+        assert_eq!(State::Lost, game.state());
+    }
+
+    #[test]
+    fn winning_on_the_last_attempt_wins() {
+        let mut game = Game::with_secret(Difficulty::Normal, 42);
+
+        for _ in 0..6 {
+            game.guess("1").unwrap();
+        }
+        assert_eq!(Ok(Outcome::Win), game.guess("42"));
+        assert_eq!(State::Won, game.state());
+    }
+
+    #[test]
+    #[should_panic(expected = "the game is over")]
+    fn no_guesses_after_the_end() {
+        let mut game = Game::with_secret(Difficulty::Easy, 7);
+        game.guess("7").unwrap();
+        game.guess("7").unwrap();
+    }
+
+    #[test]
+    fn plays_a_scripted_win() {
+        let mut game = Game::with_secret(Difficulty::Easy, 30);
+        let (state, output) = play_script(&mut game, "25\nabc\n35\n30\n99\n");
+
+        assert_eq!(State::Won, state);
+        let expected = [
+            "I'm thinking of a number between 1 and 50. You have 10 attempts.",
+            "Type \"hint\", \"history\" or \"quit\" at any time.",
+            "Please input your guess: ",
+            "You guessed: 25 (1/10)",
+            "Too small",
+            "Please input your guess: ",
+            "\"abc\" is not a number",
+            "Please input your guess: ",
+            "You guessed: 35 (2/10)",
+            "Too big",
+            "Neither warmer nor colder",
+            "Please input your guess: ",
+            "You guessed: 30 (3/10)",
+            "You win!",
+        ];
+        assert_eq!(expected.join("\n") + "\n", output);
+    }
+
+    #[test]
+    fn plays_a_scripted_loss() {
+        let mut game = Game::with_secret(Difficulty::Normal, 64);
+        let (state, output) = play_script(&mut game, &"1\n".repeat(7));
+
+        assert_eq!(State::Lost, state);
+        assert!(output.contains("You guessed: 1 (7/7)\nToo small\n"));
+        assert!(output.ends_with("You lose! The secret number was 64\n"));
+    }
+
+    #[test]
+    fn seeded_game_is_reproducible() {
+        let script = "500\n250\n750\n";
+        let (_, first) = play_script(&mut Game::new(Difficulty::Hard, 7), script);
+        let (_, second) = play_script(&mut Game::new(Difficulty::Hard, 7), script);
+
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn input_running_out_quits() {
+        let mut game = Game::with_secret(Difficulty::Easy, 30);
+        let (state, output) = play_script(&mut game, "10\n");
+
+        assert_eq!(State::Quit, state);
+        assert_eq!(1, game.attempts());
+        assert!(output.ends_with("Too small\nPlease input your guess: \nBye! The secret number was 30\n"));
+    }
+
+    #[test]
+    fn explains_rejected_input() {
+        let mut game = Game::with_secret(Difficulty::Easy, 30);
+        let (_, output) = play_script(&mut game, "\n0\n51\n");
+
+        assert!(output.contains("Please type a number\n"));
+        assert!(output.contains("0 is out of range, the number is between 1 and 50\n"));
+        assert!(output.contains("51 is out of range, the number is between 1 and 50\n"));
+        assert_eq!(0, game.attempts());
+    }
+
+    #[test]
+    fn commands() {
+        let mut game = Game::with_secret(Difficulty::Easy, 30);
+        let (state, output) = play_script(&mut game, "history\n10\n40\nhint\nhistory\nquit\n20\n");
+
+        assert_eq!(State::Quit, state);
+        assert_eq!(2, game.attempts());
+        assert!(output.contains("No guesses yet\n"));
+        assert!(output.contains("The number is between 11 and 39\n"));
+        assert!(output.contains("  1. 10: Too small\n  2. 40: Too big\n"));
+        assert!(output.ends_with("Bye! The secret number was 30\n"));
+    }
+
+    #[test]
+    fn warmer_and_colder() {
+        let mut game = Game::with_secret(Difficulty::Normal, 50);
+
+        assert_eq!(None, game.trend());
+        game.guess("10").unwrap();
+        assert_eq!(None, game.trend());
+        game.guess("60").unwrap();
+        assert_eq!(Some(Trend::Warmer), game.trend());
+        game.guess("90").unwrap();
+        assert_eq!(Some(Trend::Colder), game.trend());
+        game.guess("10").unwrap();
+        assert_eq!(Some(Trend::Same), game.trend());
+    }
+
+    #[test]
+    fn hint_narrows_with_the_guesses() {
+        let mut game = Game::with_secret(Difficulty::Normal, 50);
+
+        assert_eq!((1, 100), game.narrowed());
+        game.guess("20").unwrap();
+        game.guess("70").unwrap();
+        game.guess("60").unwrap();
+        assert_eq!((21, 59), game.narrowed());
+    }
+
+    #[test]
+    fn speaks_spanish() {
+        let catalog = Catalog::new(Locale::Spanish);
+        let mut game = Game::with_secret(Difficulty::Normal, 50);
+
+        assert_eq!(
+            "Estoy pensando en un número entre 1 y 100. Tienes 7 intentos.",
+            game.rules(&catalog)
+        );
+        assert_eq!(Ok("Muy pequeño".to_string()), game.attempt("10", &catalog));
+        assert_eq!(Ok("Muy grande\nMás caliente".to_string()), game.attempt("60", &catalog));
+        assert_eq!(Err("\"x\" no es un número".to_string()), game.attempt("x", &catalog));
+        assert_eq!(Some("El número está entre 11 y 59".to_string()), game.hint(&catalog));
+        assert_eq!("El número secreto era 50", game.reveal(&catalog));
+    }
+}
@@ -0,0 +1,104 @@
+// Validated player input, like the Guess type of 9_errors but returning
+// the reason instead of panicking: a Guess can only exist inside the
+// range of the game, so the rest of the code doesn't check it again.
+use std::fmt;
+
+use crate::i18n::{Catalog, Locale};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Guess {
+    value: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GuessError {
+    Empty,
+    NotANumber(String),
+    OutOfRange { value: i64, low: u32, high: u32 },
+}
+
+impl GuessError {
+    pub fn message(&self, catalog: &Catalog) -> String {
+        match self {
+            GuessError::Empty => catalog.text("guess.empty").to_string(),
+            GuessError::NotANumber(text) => catalog.format("guess.not-a-number", &[("text", text)]),
+            GuessError::OutOfRange { value, low, high } => catalog.format(
+                "guess.out-of-range",
+                &[("value", value), ("low", low), ("high", high)],
+            ),
+        }
+    }
+}
+
+// In English, like the rest of the multiplayer protocol
+impl fmt::Display for GuessError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.message(&Catalog::new(Locale::English)))
+    }
+}
+
+impl Guess {
+    pub fn new(value: i64, low: u32, high: u32) -> Result<Guess, GuessError> {
+        if value < i64::from(low) || value > i64::from(high) {
+            return Err(GuessError::OutOfRange { value, low, high });
+        }
+
+        Ok(Guess { value: value as u32 })
+    }
+
+    pub fn parse(text: &str, low: u32, high: u32) -> Result<Guess, GuessError> {
+        let text = text.trim();
+        if text.is_empty() {
+            return Err(GuessError::Empty);
+        }
+
+        match text.parse() {
+            Ok(value) => Guess::new(value, low, high),
+            Err(_) => Err(GuessError::NotANumber(text.to_string())),
+        }
+    }
+
+    pub fn value(&self) -> u32 {
+        self.value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_numbers_in_range() {
+        assert_eq!(1, Guess::parse("1", 1, 100).unwrap().value());
+        assert_eq!(100, Guess::parse(" 100\n", 1, 100).unwrap().value());
+    }
+
+    #[test]
+    fn explains_rejected_input() {
+        assert_eq!(Err(GuessError::Empty), Guess::parse(" \n", 1, 100));
+        assert_eq!(Err(GuessError::NotANumber("ten".to_string())), Guess::parse("ten\n", 1, 100));
+        assert_eq!(
+            Err(GuessError::OutOfRange { value: -3, low: 1, high: 100 }),
+            Guess::parse("-3", 1, 100)
+        );
+        assert_eq!(
+            "101 is out of range, the number is between 1 and 100",
+            Guess::parse("101", 1, 100).unwrap_err().to_string()
+        );
+        assert_eq!(
+            "\"99999999999999999999\" is not a number",
+            Guess::parse("99999999999999999999", 1, 100).unwrap_err().to_string()
+        );
+    }
+
+    #[test]
+    fn translated_reasons() {
+        let catalog = Catalog::new(Locale::Spanish);
+
+        assert_eq!("Escribe un número", GuessError::Empty.message(&catalog));
+        assert_eq!(
+            "0 está fuera de rango, el número está entre 1 y 50",
+            Guess::parse("0", 1, 50).unwrap_err().message(&catalog)
+        );
+    }
+}
@@ -0,0 +1,281 @@
+// Message catalog: everything the game prints to the player comes from a
+// bundle in src/locales, one per language, compiled into the binary.
+// Error messages on stderr stay in English, they are for whoever runs
+// the program rather than for the player.
+//
+// Bundle format, one message per line:
+//
+//   # comment
+//   key = text with {placeholders}
+//   key[one] = {n} attempt          plural forms, chosen by plural()
+//   key[other] = {n} attempts
+//
+// One space after "=" is skipped and everything else is kept, trailing
+// spaces included. "\n" inside a text is a line break and "\\" a
+// backslash. A key missing in a bundle falls back to English.
+use std::collections::HashMap;
+use std::env;
+use std::fmt;
+
+const ENGLISH: &str = include_str!("locales/en.txt");
+const SPANISH: &str = include_str!("locales/es.txt");
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Locale {
+    English,
+    Spanish,
+}
+
+impl Locale {
+    // Accepts "es", "es_ES.UTF-8", "en-US"... Only the language counts
+    pub fn parse(name: &str) -> Option<Locale> {
+        let language = name
+            .split(['_', '-', '.', '@'])
+            .next()
+            .unwrap_or("")
+            .to_lowercase();
+
+        match language.as_str() {
+            "en" | "c" | "posix" => Some(Locale::English),
+            "es" => Some(Locale::Spanish),
+            _ => None,
+        }
+    }
+
+    pub fn code(self) -> &'static str {
+        match self {
+            Locale::English => "en",
+            Locale::Spanish => "es",
+        }
+    }
+
+    // --lang first, then the usual variables in the order POSIX reads
+    // them. The first one that is set decides, English if unknown.
+    pub fn from_env(lang: Option<String>) -> Locale {
+        let variables = ["LC_ALL", "LC_MESSAGES", "LANG"];
+        let chosen = lang.or_else(|| {
+            variables
+                .iter()
+                .filter_map(|name| env::var(name).ok())
+                .find(|value| !value.is_empty())
+        });
+
+        chosen.and_then(|name| Locale::parse(&name)).unwrap_or(Locale::English)
+    }
+
+    fn bundle(self) -> &'static str {
+        match self {
+            Locale::English => ENGLISH,
+            Locale::Spanish => SPANISH,
+        }
+    }
+
+    // Both languages only distinguish one from everything else (zero
+    // included: "0 attempts", "0 intentos")
+    fn plural_form(self, n: u64) -> &'static str {
+        match self {
+            Locale::English | Locale::Spanish => {
+                if n == 1 {
+                    "one"
+                } else {
+                    "other"
+                }
+            }
+        }
+    }
+}
+
+pub struct Catalog {
+    locale: Locale,
+    messages: HashMap<String, String>,
+}
+
+impl Catalog {
+    pub fn new(locale: Locale) -> Catalog {
+        let mut messages = parse(ENGLISH);
+        messages.extend(parse(locale.bundle()));
+
+        Catalog { locale, messages }
+    }
+
+    pub fn locale(&self) -> Locale {
+        self.locale
+    }
+
+    // An unknown key is shown as is, so a missing message is visible
+    // without breaking the game
+    pub fn text<'a>(&'a self, key: &'a str) -> &'a str {
+        self.messages.get(key).map(String::as_str).unwrap_or(key)
+    }
+
+    // Replaces every {name} with its value; unknown placeholders are kept
+    pub fn format(&self, key: &str, args: &[(&str, &dyn fmt::Display)]) -> String {
+        fill(self.text(key), args)
+    }
+
+    // The form of key for the number n, with n as {n}
+    pub fn plural(&self, key: &str, n: u64) -> String {
+        let form = format!("{}[{}]", key, self.locale.plural_form(n));
+        fill(self.text(&form), &[("n", &n)])
+    }
+}
+
+fn fill(template: &str, args: &[(&str, &dyn fmt::Display)]) -> String {
+    let mut text = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(open) = rest.find('{') {
+        text.push_str(&rest[..open]);
+        rest = &rest[open..];
+
+        let value = rest
+            .find('}')
+            .and_then(|close| args.iter().find(|(name, _)| *name == &rest[1..close]).map(|arg| (close, arg.1)));
+
+        match value {
+            Some((close, value)) => {
+                text.push_str(&value.to_string());
+                rest = &rest[close + 1..];
+            }
+            None => {
+                text.push('{');
+                rest = &rest[1..];
+            }
+        }
+    }
+    text.push_str(rest);
+
+    text
+}
+
+fn parse(bundle: &str) -> HashMap<String, String> {
+    let mut messages = HashMap::new();
+
+    for line in bundle.lines() {
+        let line = line.trim_end_matches('\r');
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        if let Some(equals) = line.find('=') {
+            let key = line[..equals].trim();
+            let value = &line[equals + 1..];
+            let value = value.strip_prefix(' ').unwrap_or(value);
+
+            messages.insert(key.to_string(), unescape(value));
+        }
+    }
+
+    messages
+}
+
+// Only \n and \\ are escapes; any other backslash is kept
+fn unescape(text: &str) -> String {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        let escaped = match (c, chars.peek()) {
+            ('\\', Some('n')) => '\n',
+            ('\\', Some('\\')) => '\\',
+            _ => {
+                unescaped.push(c);
+                continue;
+            }
+        };
+        chars.next();
+        unescaped.push(escaped);
+    }
+
+    unescaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn placeholders(text: &str) -> Vec<&str> {
+        let mut names: Vec<&str> = text
+            .split('{')
+            .skip(1)
+            .filter_map(|part| part.split('}').next())
+            .collect();
+        names.sort_unstable();
+        names
+    }
+
+    #[test]
+    fn parses_locale_names() {
+        assert_eq!(Some(Locale::Spanish), Locale::parse("es_ES.UTF-8"));
+        assert_eq!(Some(Locale::Spanish), Locale::parse("ES"));
+        assert_eq!(Some(Locale::English), Locale::parse("en-US"));
+        assert_eq!(Some(Locale::English), Locale::parse("C.UTF-8"));
+        assert_eq!(None, Locale::parse("fr_FR"));
+        assert_eq!(Locale::Spanish, Locale::from_env(Some("es".to_string())));
+        assert_eq!(Locale::English, Locale::from_env(Some("klingon".to_string())));
+    }
+
+    #[test]
+    fn every_bundle_has_every_message() {
+        let english = parse(ENGLISH);
+
+        for &locale in [Locale::English, Locale::Spanish].iter() {
+            let bundle = parse(locale.bundle());
+
+            for (key, text) in &english {
+                let translated = bundle.get(key).unwrap_or_else(|| panic!("{} has no {}", locale.code(), key));
+                assert_eq!(placeholders(text), placeholders(translated), "{} in {}", key, locale.code());
+            }
+            for key in bundle.keys() {
+                assert!(english.contains_key(key), "{} isn't an English key", key);
+            }
+        }
+    }
+
+    #[test]
+    fn keeps_spaces_and_line_breaks() {
+        let messages = parse("# comment\n\nprompt = Guess: \nrules = one\\ntwo\nslash = a\\\\nb\nbare=x\n");
+
+        assert_eq!("Guess: ", messages["prompt"]);
+        assert_eq!("one\ntwo", messages["rules"]);
+        assert_eq!("a\\nb", messages["slash"]);
+        assert_eq!("x", messages["bare"]);
+        assert_eq!(4, messages.len());
+    }
+
+    #[test]
+    fn formats_placeholders() {
+        let catalog = Catalog::new(Locale::English);
+
+        assert_eq!(
+            "The number is between 3 and 9",
+            catalog.format("number.hint", &[("low", &3), ("high", &9)])
+        );
+        assert_eq!("{x} and 1", fill("{x} and {y}", &[("y", &1)]));
+        assert_eq!("{ unclosed", fill("{ unclosed", &[]));
+        assert_eq!("no.such.key", catalog.text("no.such.key"));
+    }
+
+    #[test]
+    fn plural_forms() {
+        let english = Catalog::new(Locale::English);
+        let spanish = Catalog::new(Locale::Spanish);
+
+        assert_eq!("1 attempt", english.plural("attempts", 1));
+        assert_eq!("0 attempts", english.plural("attempts", 0));
+        assert_eq!("7 attempts", english.plural("attempts", 7));
+        assert_eq!("1 intento", spanish.plural("attempts", 1));
+        assert_eq!("2 intentos", spanish.plural("attempts", 2));
+    }
+
+    #[test]
+    fn spanish_messages() {
+        let catalog = Catalog::new(Locale::Spanish);
+
+        assert_eq!("Escribe tu número: ", catalog.text("number.prompt"));
+        assert_eq!(
+            "Estoy pensando en un número entre 1 y 100. Tienes 7 intentos.",
+            catalog.format("number.rules", &[("low", &1), ("high", &100), ("attempts", &catalog.plural("attempts", 7))])
+        );
+    }
+}
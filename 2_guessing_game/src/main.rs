@@ -1,54 +1,375 @@
 // Require libs
+use std::env;
 use std::io;
-use std::cmp::Ordering;
+use std::path::PathBuf;
+use std::process;
+use std::time::Instant;
 use rand::Rng;
 
+mod client;
+mod code;
+mod difficulty;
+mod game;
+mod guess;
+mod i18n;
+mod puzzle;
+mod replay;
+mod reverse;
+mod scores;
+mod server;
+mod solver;
+mod word;
+
+use difficulty::Difficulty;
+use game::{Game, State};
+use i18n::{Catalog, Locale};
+use puzzle::{Puzzle, Variant};
+use replay::{Recorder, Replay};
+use scores::{HighScores, Record};
+
 // Main function
 fn main() {
 
-    println!("*** Guess the number! ***");
+    // --lang es|en, otherwise the language of LANG
+    let catalog = Catalog::new(Locale::from_env(option("--lang")));
+
+    println!("{}", catalog.text("title"));
+
+    // --solve <games>: the computer plays against itself
+    if let Some(games) = option("--solve") {
+        let games = games.parse().unwrap_or_else(|_| {
+            eprintln!("--solve needs a number of games, not {}", games);
+            process::exit(2);
+        });
+        solve(games, &catalog);
+        return;
+    }
+
+    // --server <address> [--rounds <n>]: hosts a game for several players
+    if let Some(addr) = option("--server") {
+        serve(&addr, &catalog);
+        return;
+    }
+
+    // --connect <address> [--name <player>]: joins a hosted game
+    if let Some(addr) = option("--connect") {
+        let name = match option("--name") {
+            Some(name) => name,
+            None => ask_name(&catalog),
+        };
+
+        // The client reads it on its own thread, a lock can't be sent there
+        let stdin = io::BufReader::new(io::stdin());
+        if let Err(e) = client::run(addr.as_str(), &name, catalog, stdin, io::stdout()) {
+            eprintln!("Connection to {} failed: {}", addr, e);
+        }
+        return;
+    }
+
+    // --reverse: the human thinks of the number
+    if env::args().any(|arg| arg == "--reverse") {
+        let difficulty = difficulty().unwrap_or(Difficulty::Normal);
+        let (low, high) = difficulty.range();
+
+        let stdin = io::stdin();
+        if let Err(e) = reverse::play(low, high, &catalog, stdin.lock(), io::stdout()) {
+            eprintln!("Failed to read line: {}", e);
+            process::exit(1);
+        }
+        return;
+    }
+
+    // --replay <file>: plays a recorded session again and checks the output
+    if let Some(path) = option("--replay") {
+        replay(&path, &catalog);
+        return;
+    }
+
+    // --variant word|code: guess a word or break a code instead of a number
+    let variant = match option("--variant") {
+        None => Variant::Number,
+        Some(name) => match Variant::parse(&name) {
+            Some(variant) => variant,
+            None => {
+                eprintln!("Unknown variant {}, use number, word or code", name);
+                return;
+            }
+        },
+    };
+    if variant != Variant::Number {
+        let seed = seed();
+        let mut puzzle = variant.puzzle(Difficulty::Normal, seed);
+        let replay = Replay::new(variant, Difficulty::Normal, catalog.locale(), seed);
+        play_recorded(puzzle.as_mut(), &catalog, replay);
+        return;
+    }
+
+    // --name <player> and --difficulty <level> skip the questions
+    let name = match option("--name") {
+        Some(name) => name,
+        None => ask_name(&catalog),
+    };
+    let difficulty = match difficulty() {
+        Some(difficulty) => difficulty,
+        None => match choose_difficulty(&catalog) {
+            Some(difficulty) => difficulty,
+            // Ctrl-D in the menu
+            None => return,
+        },
+    };
 
-    // Generate random number
-    let secret_number = rand::thread_rng().gen_range(1, 101);
+    let seed = seed();
 
     // Print The secret number, interpolation with "{}"
     // println!("The secret number is: {}", secret_number);
 
-    // Loop as While
-    loop {
+    let mut game = Game::new(difficulty, seed);
+    let started = Instant::now();
 
-        println!("Please input your guess: ");
+    let replay = Replay::new(Variant::Number, difficulty, catalog.locale(), seed);
+    let state = match play_recorded(&mut game, &catalog, replay) {
+        Some(state) => state,
+        None => return,
+    };
 
-        // Varible declaration
-        // let declaration like javascript 
-        // mut ---> mutable variable
-        //  String::new(), static method to generate new string
-        let mut guess = String::new();
+    let attempts = game.attempts();
+    match state {
+        State::Won => {
+            let score = difficulty.score(attempts, started.elapsed());
+            let seconds = started.elapsed().as_secs();
+            let plural = catalog.plural("attempts", u64::from(attempts));
+            println!("{}", catalog.format("score", &[("score", &score), ("attempts", &plural), ("seconds", &seconds)]));
+            save_game(Record::new(&name, difficulty, true, attempts, started.elapsed(), score), &catalog);
+        }
+        State::Lost => save_game(Record::new(&name, difficulty, false, attempts, started.elapsed(), 0), &catalog),
+        // The player left before the end
+        State::Quit | State::Playing => {}
+    }
+}
 
-        // .read_line() read the input
-        io::stdin().read_line(&mut guess)
-            .expect("Failed to read line"); // Show error if this exists
+// --difficulty <level>, None when it isn't given
+fn difficulty() -> Option<Difficulty> {
+    let level = option("--difficulty")?;
+    match Difficulty::parse(&level) {
+        Some(difficulty) => Some(difficulty),
+        None => {
+            eprintln!("Unknown difficulty {}, use easy, normal or hard", level);
+            process::exit(2);
+        }
+    }
+}
 
-        // Pass value and declaration of type u32 (unsigned 32-bit number)
-        // match return a value
-        let guess: u32 = match guess.trim().parse() {
-            Ok(num) => num, // Good response
-            Err(_) => continue, // Error
-        };
+// --seed <number> replays the same secret numbers
+fn seed() -> u64 {
+    match option("--seed") {
+        Some(seed) => seed.parse().unwrap_or_else(|_| {
+            eprintln!("--seed needs a number, not {}", seed);
+            process::exit(2);
+        }),
+        None => rand::thread_rng().gen(),
+    }
+}
 
-        println!("You guessed: {}", guess);
+// Plays with stdin and stdout, and saves the session to --record <file>
+// or to a new file in the replays directory
+fn play_recorded(puzzle: &mut dyn Puzzle, catalog: &Catalog, replay: Replay) -> Option<State> {
+    let path = option("--record").map(PathBuf::from);
+    let recorder = Recorder::new(replay);
 
-        // Comparation
-        match guess.cmp(&secret_number) {
-            Ordering::Less => println!("Too small"),
-            Ordering::Greater => println!("Too big"),
-            Ordering::Equal => {
-                println!("You win!");
-                break;
-            }
+    let stdin = io::stdin();
+    let state = puzzle::play(puzzle, catalog, recorder.input(stdin.lock()), recorder.output(io::stdout()));
+
+    let replay = recorder.finish();
+    let saved = match &path {
+        Some(path) => replay.save(path),
+        None => replay.save_new(Replay::default_dir()).map(|_| ()),
+    };
+    if let Err(e) = saved {
+        let path = path.unwrap_or_else(Replay::default_dir);
+        eprintln!("Couldn't save the replay to {}: {}", path.display(), e);
+    }
+
+    match state {
+        Ok(state) => Some(state),
+        Err(e) => {
+            eprintln!("Failed to read line: {}", e);
+            None
+        }
+    }
+}
+
+fn replay(path: &str, catalog: &Catalog) {
+    let replay = match Replay::load(path) {
+        Ok(replay) => replay,
+        Err(e) => {
+            eprintln!("Couldn't read the replay {}: {}", path, e);
+            process::exit(2);
+        }
+    };
+
+    match replay.verify() {
+        Ok(Ok(())) => {
+            let inputs = catalog.plural("inputs", replay.inputs() as u64);
+            println!(
+                "{}",
+                catalog.format(
+                    "replay.ok",
+                    &[("variant", &replay.variant.name()), ("seed", &replay.seed), ("inputs", &inputs)],
+                )
+            );
+        }
+        Ok(Err(mismatch)) => {
+            let line = |text: Option<String>| text.unwrap_or_else(|| catalog.text("replay.nothing").to_string());
+            println!("{}", catalog.format("replay.differs", &[("line", &mismatch.line)]));
+            println!("{}", catalog.format("replay.recorded", &[("text", &line(mismatch.recorded))]));
+            println!("{}", catalog.format("replay.replayed", &[("text", &line(mismatch.replayed))]));
+            process::exit(1);
+        }
+        Err(e) => {
+            eprintln!("Replay failed: {}", e);
+            process::exit(2);
+        }
+    }
+}
+
+fn solve(games: u32, catalog: &Catalog) {
+    let difficulty = difficulty().unwrap_or(Difficulty::Normal);
+    let seed = seed();
+
+    let header = catalog.format(
+        "solve-header",
+        &[
+            ("games", &catalog.plural("games", u64::from(games))),
+            ("difficulty", &difficulty_name(difficulty, catalog)),
+            ("seed", &seed),
+        ],
+    );
+    println!("{}", header);
+    println!("{}", solver::header(catalog));
+    for report in solver::run(difficulty, games, seed) {
+        println!("{}", report);
+    }
+}
+
+fn serve(addr: &str, catalog: &Catalog) {
+    let difficulty = difficulty().unwrap_or(Difficulty::Normal);
+    let rounds = option("--rounds").map(|rounds| {
+        rounds.parse().unwrap_or_else(|_| {
+            eprintln!("--rounds needs a number, not {}", rounds);
+            process::exit(2);
+        })
+    });
+
+    let server = match server::Server::bind(addr, difficulty, seed()) {
+        Ok(server) => server,
+        Err(e) => {
+            eprintln!("Couldn't listen on {}: {}", addr, e);
+            return;
+        }
+    };
+    if let Ok(addr) = server.local_addr() {
+        let difficulty = difficulty_name(difficulty, catalog);
+        println!("{}", catalog.format("listening", &[("address", &addr), ("difficulty", &difficulty)]));
+    }
+
+    if let Err(e) = server.run(rounds) {
+        eprintln!("Server error: {}", e);
+    }
+}
+
+// Stores the game and shows the leaderboard of its difficulty
+fn save_game(record: Record, catalog: &Catalog) {
+    let path = HighScores::default_path();
+    let mut scores = HighScores::load(&path);
+    let difficulty = record.difficulty;
+
+    if scores.skipped > 0 {
+        eprintln!("Skipped {} unreadable lines in {}", scores.skipped, path.display());
+    }
+    if let Err(e) = scores.add(record) {
+        eprintln!("Couldn't save the score to {}: {}", path.display(), e);
+    }
+
+    let top = scores.top(difficulty);
+    let name = difficulty_name(difficulty, catalog);
+    println!("{}", catalog.format("top", &[("count", &scores::TOP), ("difficulty", &name)]));
+    if top.is_empty() {
+        println!("{}", catalog.text("no-wins"));
+    }
+    // Padded before filling the message so the columns line up
+    for (i, record) in top.iter().enumerate() {
+        let row = catalog.format(
+            "leaderboard-row",
+            &[
+                ("rank", &format!("{:>2}", i + 1)),
+                ("name", &format!("{:<16}", record.name)),
+                ("score", &format!("{:>5}", record.score)),
+                ("attempts", &format!("{:>11}", catalog.plural("attempts", u64::from(record.attempts)))),
+                ("seconds", &format!("{:>4}", record.duration.as_secs())),
+                ("date", &scores::format_date(record.date)),
+            ],
+        );
+        println!("{}", row);
+    }
+}
+
+// Value of "--name value" in the command line
+fn option(name: &str) -> Option<String> {
+    let mut args = env::args().skip(1);
+
+    while let Some(arg) = args.next() {
+        if arg == name {
+            return args.next();
+        }
+    }
+
+    None
+}
+
+fn difficulty_name(difficulty: Difficulty, catalog: &Catalog) -> String {
+    catalog.text(&format!("difficulty.{}", difficulty.name())).to_string()
+}
+
+fn ask_name(catalog: &Catalog) -> String {
+    println!("{}", catalog.text("ask-name"));
+
+    let mut name = String::new();
+    io::stdin().read_line(&mut name)
+        .expect("Failed to read line");
+
+    name.trim().to_string()
+}
+
+// None when the input ends
+fn choose_difficulty(catalog: &Catalog) -> Option<Difficulty> {
+    loop {
+        println!("{}", catalog.text("choose-difficulty"));
+        for (i, &difficulty) in Difficulty::ALL.iter().enumerate() {
+            let (low, high) = difficulty.range();
+            let option = catalog.format(
+                "difficulty-option",
+                &[
+                    ("number", &(i + 1)),
+                    ("name", &difficulty_name(difficulty, catalog)),
+                    ("low", &low),
+                    ("high", &high),
+                    ("attempts", &catalog.plural("attempts", u64::from(difficulty.max_attempts()))),
+                ],
+            );
+            println!("{}", option);
+        }
+
+        let mut choice = String::new();
+        match io::stdin().read_line(&mut choice) {
+            Ok(0) | Err(_) => return None,
+            Ok(_) => {}
+        }
+
+        if let Some(difficulty) = Difficulty::parse(&choice) {
+            return Some(difficulty);
         }
     }
 }
 
 // Extract this game sample from:
-// https://doc.rust-lang.org/book/ch02-00-guessing-game-tutorial.html
\ No newline at end of file
+// https://doc.rust-lang.org/book/ch02-00-guessing-game-tutorial.html
@@ -0,0 +1,322 @@
+// Anything that can be guessed in turns: the number of the original game,
+// a word or a secret code. play() only knows this trait, so the commands,
+// the attempt counter and the end of the game work the same for all of
+// them.
+use std::io::{self, BufRead, Write};
+
+use crate::code::Code;
+use crate::difficulty::Difficulty;
+use crate::game::{Game, State};
+use crate::i18n::Catalog;
+use crate::word::Word;
+
+// Every text comes from the catalog, in the language of the player
+pub trait Puzzle {
+    // Shown before the first guess
+    fn rules(&self, catalog: &Catalog) -> String;
+
+    // Shown before reading each guess
+    fn prompt(&self, catalog: &Catalog) -> String;
+
+    fn max_attempts(&self) -> u32;
+
+    fn attempts(&self) -> u32;
+
+    fn state(&self) -> State;
+
+    // The feedback for a valid guess, or why the input isn't one (then it
+    // doesn't count as an attempt). Panics if the game is already over.
+    fn attempt(&mut self, input: &str, catalog: &Catalog) -> Result<String, String>;
+
+    fn quit(&mut self);
+
+    // "The secret number was 42", shown when the game is lost or abandoned
+    fn reveal(&self, catalog: &Catalog) -> String;
+
+    fn hint(&self, _catalog: &Catalog) -> Option<String> {
+        None
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Variant {
+    Number,
+    Word,
+    Code,
+}
+
+impl Variant {
+    pub const ALL: [Variant; 3] = [Variant::Number, Variant::Word, Variant::Code];
+
+    pub fn parse(name: &str) -> Option<Variant> {
+        Variant::ALL.iter().copied().find(|variant| variant.name() == name.trim())
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Variant::Number => "number",
+            Variant::Word => "word",
+            Variant::Code => "code",
+        }
+    }
+
+    // The difficulty only changes the number variant
+    pub fn puzzle(self, difficulty: Difficulty, seed: u64) -> Box<dyn Puzzle> {
+        match self {
+            Variant::Number => Box::new(Game::new(difficulty, seed)),
+            Variant::Word => Box::new(Word::new(seed)),
+            Variant::Code => Box::new(Code::new(seed)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    Quit,
+    Hint,
+    History,
+}
+
+impl Command {
+    // None when the line should be a guess. The Spanish words work in
+    // every language.
+    pub fn parse(line: &str) -> Option<Command> {
+        match line.trim().to_lowercase().as_str() {
+            "quit" | "q" | "salir" => Some(Command::Quit),
+            "hint" | "pista" => Some(Command::Hint),
+            "history" | "historial" => Some(Command::History),
+            _ => None,
+        }
+    }
+}
+
+// Plays until the game ends, the player quits or the input runs out
+// (which is the same as quitting)
+pub fn play<R: BufRead, W: Write>(
+    puzzle: &mut dyn Puzzle,
+    catalog: &Catalog,
+    mut input: R,
+    mut output: W,
+) -> io::Result<State> {
+    writeln!(output, "{}", puzzle.rules(catalog))?;
+    writeln!(output, "{}", catalog.text("commands"))?;
+
+    // Every valid guess with the first line of its feedback
+    let mut history: Vec<(String, String)> = Vec::new();
+
+    while puzzle.state() == State::Playing {
+        writeln!(output, "{}", puzzle.prompt(catalog))?;
+
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 {
+            puzzle.quit();
+            break;
+        }
+
+        match Command::parse(&line) {
+            Some(Command::Quit) => {
+                puzzle.quit();
+                break;
+            }
+            Some(Command::Hint) => {
+                let hint = puzzle
+                    .hint(catalog)
+                    .unwrap_or_else(|| catalog.text("no-hints").to_string());
+                writeln!(output, "{}", hint)?;
+                continue;
+            }
+            Some(Command::History) => {
+                if history.is_empty() {
+                    writeln!(output, "{}", catalog.text("no-guesses"))?;
+                }
+                for (i, (guess, feedback)) in history.iter().enumerate() {
+                    let number = i + 1;
+                    let row = catalog.format("history-row", &[("number", &number), ("guess", guess), ("feedback", feedback)]);
+                    writeln!(output, "{}", row)?;
+                }
+                continue;
+            }
+            None => {}
+        }
+
+        let feedback = match puzzle.attempt(&line, catalog) {
+            Ok(feedback) => feedback,
+            Err(reason) => {
+                writeln!(output, "{}", reason)?;
+                continue;
+            }
+        };
+
+        let guess = line.trim().to_string();
+        let attempt = puzzle.attempts();
+        let max = puzzle.max_attempts();
+        writeln!(output, "{}", catalog.format("guessed", &[("guess", &guess), ("attempt", &attempt), ("max", &max)]))?;
+        writeln!(output, "{}", feedback)?;
+        history.push((guess, feedback.lines().next().unwrap_or("").to_string()));
+    }
+
+    match puzzle.state() {
+        State::Lost => writeln!(output, "{}", catalog.format("lose", &[("secret", &puzzle.reveal(catalog))]))?,
+        State::Quit => writeln!(output, "{}", catalog.format("bye", &[("secret", &puzzle.reveal(catalog))]))?,
+        State::Playing | State::Won => {}
+    }
+
+    Ok(puzzle.state())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::i18n::Locale;
+    use std::io::Cursor;
+
+    // The smallest possible puzzle: guess a letter in three attempts
+    struct Letter {
+        secret: char,
+        attempts: u32,
+        state: State,
+    }
+
+    impl Puzzle for Letter {
+        fn rules(&self, _catalog: &Catalog) -> String {
+            "Guess a letter".to_string()
+        }
+
+        fn prompt(&self, _catalog: &Catalog) -> String {
+            "Your letter: ".to_string()
+        }
+
+        fn max_attempts(&self) -> u32 {
+            3
+        }
+
+        fn attempts(&self) -> u32 {
+            self.attempts
+        }
+
+        fn state(&self) -> State {
+            self.state
+        }
+
+        fn attempt(&mut self, input: &str, _catalog: &Catalog) -> Result<String, String> {
+            let mut chars = input.trim().chars();
+            let letter = match (chars.next(), chars.next()) {
+                (Some(letter), None) if letter.is_ascii_lowercase() => letter,
+                _ => return Err("One lowercase letter, please".to_string()),
+            };
+            self.attempts += 1;
+
+            if letter == self.secret {
+                self.state = State::Won;
+                Ok("Right".to_string())
+            } else {
+                if self.attempts == 3 {
+                    self.state = State::Lost;
+                }
+                Ok("Wrong".to_string())
+            }
+        }
+
+        fn quit(&mut self) {
+            self.state = State::Quit;
+        }
+
+        fn reveal(&self, _catalog: &Catalog) -> String {
+            format!("It was {}", self.secret)
+        }
+    }
+
+    fn play_script(locale: Locale, script: &str) -> (State, String) {
+        let mut puzzle = Letter { secret: 'k', attempts: 0, state: State::Playing };
+        let mut output = Vec::new();
+        let state = play(&mut puzzle, &Catalog::new(locale), Cursor::new(script), &mut output).unwrap();
+
+        (state, String::from_utf8(output).unwrap())
+    }
+
+    #[test]
+    fn parses_commands() {
+        assert_eq!(Some(Command::Quit), Command::parse("QUIT\n"));
+        assert_eq!(Some(Command::Hint), Command::parse("hint"));
+        assert_eq!(Some(Command::History), Command::parse(" history "));
+        assert_eq!(Some(Command::Hint), Command::parse("Pista"));
+        assert_eq!(None, Command::parse("7"));
+    }
+
+    #[test]
+    fn plays_any_puzzle() {
+        let (state, output) = play_script(Locale::English, "a\nAB\nhint\nhistory\nk\n");
+
+        assert_eq!(State::Won, state);
+        let expected = [
+            "Guess a letter",
+            "Type \"hint\", \"history\" or \"quit\" at any time.",
+            "Your letter: ",
+            "You guessed: a (1/3)",
+            "Wrong",
+            "Your letter: ",
+            "One lowercase letter, please",
+            "Your letter: ",
+            "No hints for this game",
+            "Your letter: ",
+            "  1. a: Wrong",
+            "Your letter: ",
+            "You guessed: k (2/3)",
+            "Right",
+        ];
+        assert_eq!(expected.join("\n") + "\n", output);
+    }
+
+    #[test]
+    fn every_variant_asks_for_its_own_guess() {
+        for &locale in &[Locale::English, Locale::Spanish] {
+            let catalog = Catalog::new(locale);
+            let prompts: Vec<String> = Variant::ALL
+                .iter()
+                .map(|variant| variant.puzzle(Difficulty::Easy, 1).prompt(&catalog))
+                .collect();
+
+            let mut unique = prompts.clone();
+            unique.sort();
+            unique.dedup();
+            assert_eq!(Variant::ALL.len(), unique.len(), "{:?}", prompts);
+        }
+
+        let mut output = Vec::new();
+        let mut word = Variant::Word.puzzle(Difficulty::Easy, 1);
+        play(word.as_mut(), &Catalog::new(Locale::English), Cursor::new("quit\n"), &mut output).unwrap();
+        assert!(String::from_utf8(output).unwrap().contains("\nPlease input a word: \n"));
+    }
+
+    #[test]
+    fn reveals_the_secret_at_the_end() {
+        let (state, output) = play_script(Locale::English, "a\nb\nc\n");
+        assert_eq!(State::Lost, state);
+        assert!(output.ends_with("Wrong\nYou lose! It was k\n"));
+
+        let (state, output) = play_script(Locale::English, "a\n");
+        assert_eq!(State::Quit, state);
+        assert!(output.ends_with("Your letter: \nBye! It was k\n"));
+    }
+
+    #[test]
+    fn speaks_spanish() {
+        let (state, output) = play_script(Locale::Spanish, "a\npista\nhistorial\nsalir\n");
+
+        assert_eq!(State::Quit, state);
+        let expected = [
+            "Guess a letter",
+            "Escribe \"pista\", \"historial\" o \"salir\" en cualquier momento.",
+            "Your letter: ",
+            "Dijiste: a (1/3)",
+            "Wrong",
+            "Your letter: ",
+            "Este juego no tiene pistas",
+            "Your letter: ",
+            "  1. a: Wrong",
+            "Your letter: ",
+            "¡Adiós! It was k",
+        ];
+        assert_eq!(expected.join("\n") + "\n", output);
+    }
+}
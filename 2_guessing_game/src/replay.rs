@@ -0,0 +1,605 @@
+// Replays: every session is recorded (seed, each line typed, each line
+// printed, with timestamps) so it can be played again with --replay and
+// checked against the recording. A replay of a real session is a
+// regression test: if the program prints anything different, the replay
+// fails and shows the first line that changed.
+//
+// File format, plain text, one record per line:
+//
+//   guessing_game replay 1         always the first line
+//   variant number                 number, word or code
+//   difficulty normal              only used by the number variant
+//   locale es                      language of the messages, English
+//                                  when missing (replays recorded
+//                                  before there were translations)
+//   seed 1234
+//   started 1760000000             unix timestamp of the session
+//   in 1520 50\n                   a line read: milliseconds since the
+//   out 1521 You guessed: 50\n     start, then the text escaped
+//
+// The text keeps its line ending so a last line without one is replayed
+// as it was. Escapes: \\ \n \r \t. Timestamps are informational: the
+// replay doesn't wait between lines.
+use std::cell::RefCell;
+use std::env;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, Cursor, Read, Write};
+use std::iter::Peekable;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use crate::difficulty::Difficulty;
+use crate::i18n::{Catalog, Locale};
+use crate::puzzle::{self, Variant};
+
+const MAGIC: &str = "guessing_game replay 1";
+
+const DIR_NAME: &str = ".guessing_game_replays";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    In,
+    Out,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Event {
+    pub direction: Direction,
+    // Milliseconds since the start of the session
+    pub elapsed: u64,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Replay {
+    pub variant: Variant,
+    pub difficulty: Difficulty,
+    pub locale: Locale,
+    pub seed: u64,
+    pub started: u64,
+    pub events: Vec<Event>,
+}
+
+// The first line where the replayed output differs (1-based), None when
+// one of them has fewer lines
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mismatch {
+    pub line: usize,
+    pub recorded: Option<String>,
+    pub replayed: Option<String>,
+}
+
+impl Replay {
+    pub fn new(variant: Variant, difficulty: Difficulty, locale: Locale, seed: u64) -> Replay {
+        let started = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|since| since.as_secs())
+            .unwrap_or(0);
+
+        Replay {
+            variant,
+            difficulty,
+            locale,
+            seed,
+            started,
+            events: Vec::new(),
+        }
+    }
+
+    // $GUESSING_GAME_REPLAYS, or a hidden directory in the home directory,
+    // with one file per session
+    pub fn default_dir() -> PathBuf {
+        match env::var_os("GUESSING_GAME_REPLAYS") {
+            Some(dir) => PathBuf::from(dir),
+            None => match env::var_os("HOME") {
+                Some(home) => Path::new(&home).join(DIR_NAME),
+                None => PathBuf::from(DIR_NAME),
+            },
+        }
+    }
+
+    pub fn write<W: Write>(&self, mut output: W) -> io::Result<()> {
+        writeln!(output, "{}", MAGIC)?;
+        writeln!(output, "variant {}", self.variant.name())?;
+        writeln!(output, "difficulty {}", self.difficulty.name())?;
+        writeln!(output, "locale {}", self.locale.code())?;
+        writeln!(output, "seed {}", self.seed)?;
+        writeln!(output, "started {}", self.started)?;
+
+        for event in &self.events {
+            let direction = match event.direction {
+                Direction::In => "in",
+                Direction::Out => "out",
+            };
+            writeln!(output, "{} {} {}", direction, event.elapsed, escape(&event.text))?;
+        }
+
+        Ok(())
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        if let Some(dir) = path.as_ref().parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        let mut file = io::BufWriter::new(File::create(path)?);
+        self.write(&mut file)?;
+        file.flush()
+    }
+
+    // Saves to a new file in "dir", named after the start and the variant.
+    // Two sessions started in the same second get "-2", "-3"... instead of
+    // writing over each other.
+    pub fn save_new<P: AsRef<Path>>(&self, dir: P) -> io::Result<PathBuf> {
+        let dir = dir.as_ref();
+        fs::create_dir_all(dir)?;
+
+        for attempt in 1.. {
+            let name = match attempt {
+                1 => format!("{}-{}.replay", self.started, self.variant.name()),
+                _ => format!("{}-{}-{}.replay", self.started, self.variant.name(), attempt),
+            };
+            let path = dir.join(name);
+
+            let file = match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(file) => file,
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+                Err(e) => return Err(e),
+            };
+            let mut file = io::BufWriter::new(file);
+            self.write(&mut file)?;
+            file.flush()?;
+            return Ok(path);
+        }
+
+        unreachable!()
+    }
+
+    pub fn parse(text: &str) -> Result<Replay, String> {
+        let mut lines = text.lines().enumerate().map(|(i, line)| (i + 1, line)).peekable();
+
+        if lines.next().map(|(_, line)| line) != Some(MAGIC) {
+            return Err(format!("not a replay: the first line must be \"{}\"", MAGIC));
+        }
+
+        let variant = header(&mut lines, "variant")?;
+        let variant = Variant::parse(&variant).ok_or(format!("unknown variant {}", variant))?;
+        let difficulty = header(&mut lines, "difficulty")?;
+        let difficulty = Difficulty::parse(&difficulty).ok_or(format!("unknown difficulty {}", difficulty))?;
+        let locale = match optional_header(&mut lines, "locale") {
+            Some(locale) => Locale::parse(&locale).ok_or(format!("unknown locale {}", locale))?,
+            None => Locale::English,
+        };
+        let seed = header(&mut lines, "seed")?.parse().map_err(|_| "the seed must be a number".to_string())?;
+        let started = header(&mut lines, "started")?.parse().map_err(|_| "started must be a number".to_string())?;
+
+        let mut events = Vec::new();
+        for (number, line) in lines {
+            let mut fields = line.splitn(3, ' ');
+            let direction = match fields.next() {
+                Some("in") => Direction::In,
+                Some("out") => Direction::Out,
+                _ => return Err(format!("line {}: expected \"in\" or \"out\"", number)),
+            };
+            let elapsed = fields
+                .next()
+                .and_then(|elapsed| elapsed.parse().ok())
+                .ok_or(format!("line {}: missing milliseconds", number))?;
+            let text = unescape(fields.next().unwrap_or(""))
+                .ok_or(format!("line {}: invalid escape", number))?;
+
+            events.push(Event { direction, elapsed, text });
+        }
+
+        Ok(Replay { variant, difficulty, locale, seed, started, events })
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Replay> {
+        let text = fs::read_to_string(path)?;
+        Replay::parse(&text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    fn text(&self, direction: Direction) -> String {
+        self.events
+            .iter()
+            .filter(|event| event.direction == direction)
+            .map(|event| event.text.as_str())
+            .collect()
+    }
+
+    pub fn inputs(&self) -> usize {
+        self.events.iter().filter(|event| event.direction == Direction::In).count()
+    }
+
+    // Plays the recorded input again with the same seed and compares the
+    // output, line by line
+    pub fn verify(&self) -> io::Result<Result<(), Mismatch>> {
+        let mut puzzle = self.variant.puzzle(self.difficulty, self.seed);
+        let mut output = Vec::new();
+        let catalog = Catalog::new(self.locale);
+        puzzle::play(puzzle.as_mut(), &catalog, Cursor::new(self.text(Direction::In)), &mut output)?;
+
+        let recorded = self.text(Direction::Out);
+        let replayed = String::from_utf8_lossy(&output);
+        let mut recorded_lines = recorded.split_inclusive('\n');
+        let mut replayed_lines = replayed.split_inclusive('\n');
+
+        for line in 1.. {
+            match (recorded_lines.next(), replayed_lines.next()) {
+                (None, None) => break,
+                (Some(a), Some(b)) if a == b => {}
+                (a, b) => {
+                    return Ok(Err(Mismatch {
+                        line,
+                        recorded: a.map(|a| a.trim_end_matches('\n').to_string()),
+                        replayed: b.map(|b| b.trim_end_matches('\n').to_string()),
+                    }))
+                }
+            }
+        }
+
+        Ok(Ok(()))
+    }
+}
+
+// Collects what goes through a RecordedInput and a RecordedOutput, in the
+// order it happened. Both share it, so it lives behind Rc<RefCell>.
+pub struct Recorder {
+    replay: Rc<RefCell<Replay>>,
+    start: Instant,
+}
+
+impl Recorder {
+    pub fn new(replay: Replay) -> Recorder {
+        Recorder {
+            replay: Rc::new(RefCell::new(replay)),
+            start: Instant::now(),
+        }
+    }
+
+    pub fn input<R: BufRead>(&self, inner: R) -> RecordedInput<R> {
+        RecordedInput {
+            inner,
+            log: self.log(Direction::In),
+        }
+    }
+
+    pub fn output<W: Write>(&self, inner: W) -> RecordedOutput<W> {
+        RecordedOutput {
+            inner,
+            log: self.log(Direction::Out),
+        }
+    }
+
+    fn log(&self, direction: Direction) -> Log {
+        Log {
+            replay: Rc::clone(&self.replay),
+            start: self.start,
+            direction,
+            pending: Vec::new(),
+        }
+    }
+
+    // Call after dropping the input and the output, so their last
+    // unfinished lines are already in the replay
+    pub fn finish(self) -> Replay {
+        match Rc::try_unwrap(self.replay) {
+            Ok(replay) => replay.into_inner(),
+            Err(shared) => shared.borrow().clone(),
+        }
+    }
+}
+
+// Bytes of one direction, kept until the end of the line
+struct Log {
+    replay: Rc<RefCell<Replay>>,
+    start: Instant,
+    direction: Direction,
+    pending: Vec<u8>,
+}
+
+impl Log {
+    fn record(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.pending.push(byte);
+            if byte == b'\n' {
+                self.push();
+            }
+        }
+    }
+
+    fn push(&mut self) {
+        if self.pending.is_empty() {
+            return;
+        }
+
+        let event = Event {
+            direction: self.direction,
+            elapsed: self.start.elapsed().as_millis() as u64,
+            text: String::from_utf8_lossy(&self.pending).into_owned(),
+        };
+        self.pending.clear();
+        self.replay.borrow_mut().events.push(event);
+    }
+}
+
+impl Drop for Log {
+    fn drop(&mut self) {
+        self.push();
+    }
+}
+
+pub struct RecordedInput<R> {
+    inner: R,
+    log: Log,
+}
+
+impl<R: BufRead> Read for RecordedInput<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.log.record(&buf[..read]);
+        Ok(read)
+    }
+}
+
+impl<R: BufRead> BufRead for RecordedInput<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.inner.fill_buf()
+    }
+
+    // Only what the game consumes is recorded, not what the buffer read
+    // ahead. fill_buf() here doesn't read: it returns the same buffer.
+    fn consume(&mut self, amount: usize) {
+        if let Ok(buf) = self.inner.fill_buf() {
+            self.log.record(&buf[..amount.min(buf.len())]);
+        }
+        self.inner.consume(amount);
+    }
+}
+
+pub struct RecordedOutput<W> {
+    inner: W,
+    log: Log,
+}
+
+impl<W: Write> Write for RecordedOutput<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.log.record(&buf[..written]);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+// The value of a "key value" line
+fn header<'a, I>(lines: &mut Peekable<I>, key: &str) -> Result<String, String>
+where
+    I: Iterator<Item = (usize, &'a str)>,
+{
+    match lines.peek() {
+        Some(&(number, _)) => optional_header(lines, key)
+            .ok_or(format!("line {}: expected \"{} <value>\"", number, key)),
+        None => Err(format!("missing \"{}\"", key)),
+    }
+}
+
+// Like header(), but leaves the line for the next one if it has another key
+fn optional_header<'a, I>(lines: &mut Peekable<I>, key: &str) -> Option<String>
+where
+    I: Iterator<Item = (usize, &'a str)>,
+{
+    let &(_, line) = lines.peek()?;
+    let value = line.strip_prefix(key)?.strip_prefix(' ')?.to_string();
+    lines.next();
+    Some(value)
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            _ => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+fn unescape(text: &str) -> Option<String> {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut chars = text.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+
+        match chars.next()? {
+            '\\' => unescaped.push('\\'),
+            'n' => unescaped.push('\n'),
+            'r' => unescaped.push('\r'),
+            't' => unescaped.push('\t'),
+            _ => return None,
+        }
+    }
+
+    Some(unescaped)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Plays a session through a Recorder, like main does with stdin/stdout
+    fn record(variant: Variant, seed: u64, script: &str) -> (Replay, Vec<u8>) {
+        record_in(Locale::English, variant, seed, script)
+    }
+
+    fn record_in(locale: Locale, variant: Variant, seed: u64, script: &str) -> (Replay, Vec<u8>) {
+        let recorder = Recorder::new(Replay::new(variant, Difficulty::Easy, locale, seed));
+        let mut output = Vec::new();
+        {
+            let mut puzzle = variant.puzzle(Difficulty::Easy, seed);
+            let input = recorder.input(Cursor::new(script.to_string()));
+            let catalog = Catalog::new(locale);
+            puzzle::play(puzzle.as_mut(), &catalog, input, recorder.output(&mut output)).unwrap();
+        }
+
+        (recorder.finish(), output)
+    }
+
+    fn round_trip(replay: &Replay) -> Replay {
+        let mut file = Vec::new();
+        replay.write(&mut file).unwrap();
+        Replay::parse(&String::from_utf8(file).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn escapes_round_trip() {
+        for text in &["plain", "two\nlines\n", "tab\there", "back\\slash\\n", "crlf\r\n", ""] {
+            assert_eq!(Some(text.to_string()), unescape(&escape(text)));
+            assert!(!escape(text).contains('\n'));
+        }
+        assert_eq!(None, unescape("bad \\q escape"));
+        assert_eq!(None, unescape("ends with \\"));
+    }
+
+    #[test]
+    fn records_every_line_in_order() {
+        let (replay, output) = record(Variant::Number, 3, "abc\n10\nquit\n");
+
+        let inputs: Vec<&str> = replay
+            .events
+            .iter()
+            .filter(|event| event.direction == Direction::In)
+            .map(|event| event.text.as_str())
+            .collect();
+        assert_eq!(vec!["abc\n", "10\n", "quit\n"], inputs);
+        assert_eq!(String::from_utf8(output).unwrap(), replay.text(Direction::Out));
+
+        // The first guess is read after the first prompt
+        let first_input = replay.events.iter().position(|e| e.direction == Direction::In).unwrap();
+        assert_eq!("Please input your guess: \n", replay.events[first_input - 1].text);
+        assert!(replay.events.windows(2).all(|pair| pair[0].elapsed <= pair[1].elapsed));
+    }
+
+    #[test]
+    fn saved_replays_verify() {
+        for &variant in Variant::ALL.iter() {
+            let (replay, _) = record(variant, 11, "1\n1234\ncrane\nhint\nhistory\n");
+            let replay = round_trip(&replay);
+
+            assert_eq!(Ok(()), replay.verify().unwrap(), "{:?}", variant);
+        }
+    }
+
+    #[test]
+    fn replays_in_the_recorded_language() {
+        let (replay, output) = record_in(Locale::Spanish, Variant::Number, 5, "10\npista\n");
+        let replay = round_trip(&replay);
+
+        assert_eq!(Locale::Spanish, replay.locale);
+        assert!(String::from_utf8(output).unwrap().contains("Escribe tu número: \n"));
+        assert_eq!(Ok(()), replay.verify().unwrap());
+    }
+
+    #[test]
+    fn sessions_started_together_get_their_own_files() {
+        let dir = env::temp_dir().join(format!("guessing_game-{}-replays", std::process::id()));
+        let (first, _) = record(Variant::Number, 1, "10\n");
+        let (mut second, _) = record(Variant::Number, 2, "20\n");
+        second.started = first.started;
+
+        let first_path = first.save_new(&dir).unwrap();
+        let second_path = second.save_new(&dir).unwrap();
+
+        assert_ne!(first_path, second_path);
+        assert_eq!(first, Replay::load(&first_path).unwrap());
+        assert_eq!(second, Replay::load(&second_path).unwrap());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn last_line_without_a_newline_is_kept() {
+        let (replay, _) = record(Variant::Number, 3, "10\n20");
+        let replay = round_trip(&replay);
+
+        assert_eq!(Some("20"), replay.events.iter().rev().find(|e| e.direction == Direction::In).map(|e| e.text.as_str()));
+        assert_eq!(Ok(()), replay.verify().unwrap());
+    }
+
+    #[test]
+    fn detects_a_different_output() {
+        let (mut replay, _) = record(Variant::Number, 3, "10\n");
+        let changed = replay
+            .events
+            .iter_mut()
+            .find(|event| event.text.starts_with("You guessed"))
+            .unwrap();
+        changed.text = "You guessed: ten\n".to_string();
+
+        let mismatch = replay.verify().unwrap().unwrap_err();
+        assert_eq!(4, mismatch.line);
+        assert_eq!(Some("You guessed: ten".to_string()), mismatch.recorded);
+        assert_eq!(Some("You guessed: 10 (1/10)".to_string()), mismatch.replayed);
+    }
+
+    #[test]
+    fn detects_missing_lines() {
+        let (mut replay, _) = record(Variant::Code, 3, "1234\n");
+        replay.events.pop();
+
+        let mismatch = replay.verify().unwrap().unwrap_err();
+        assert_eq!(None, mismatch.recorded);
+        assert!(mismatch.replayed.unwrap().starts_with("Bye!"));
+    }
+
+    #[test]
+    fn rejects_invalid_files() {
+        assert!(Replay::parse("hello\n").is_err());
+        assert!(Replay::parse(MAGIC).is_err());
+
+        let header = format!("{}\nvariant number\ndifficulty easy\nlocale en\nseed 1\nstarted 0\n", MAGIC);
+        assert!(Replay::parse(&header).is_ok());
+        assert_eq!(
+            Err("line 7: expected \"in\" or \"out\"".to_string()),
+            Replay::parse(&(header.clone() + "sideways 1 x\n"))
+        );
+        assert_eq!(
+            Err("line 7: missing milliseconds".to_string()),
+            Replay::parse(&(header.clone() + "in soon x\n"))
+        );
+        assert!(Replay::parse(&header.replace("seed 1", "seed one")).is_err());
+        assert!(Replay::parse(&header.replace("variant number", "variant chess")).is_err());
+        assert!(Replay::parse(&header.replace("locale en", "locale tlh")).is_err());
+        assert_eq!(
+            Err("line 4: expected \"seed <value>\"".to_string()),
+            Replay::parse(&header.replace("locale en", "language en"))
+        );
+    }
+
+    #[test]
+    fn loads_replays_without_a_locale() {
+        let (replay, _) = record(Variant::Number, 7, "10\n20\nquit\n");
+        let mut file = Vec::new();
+        replay.write(&mut file).unwrap();
+        let file = String::from_utf8(file).unwrap().replace("locale en\n", "");
+        assert!(file.starts_with(&format!("{}\nvariant number\ndifficulty easy\nseed 7\n", MAGIC)));
+
+        let old = Replay::parse(&file).unwrap();
+        assert_eq!(Locale::English, old.locale);
+        assert_eq!(replay.events, old.events);
+        assert_eq!(Ok(()), old.verify().unwrap());
+    }
+}
@@ -0,0 +1,234 @@
+// Reverse mode: the human thinks of a number and the program guesses it,
+// always in the middle of the numbers that are still possible. If the
+// answers leave no possible number, the human was cheating.
+use std::io::{self, BufRead, Write};
+
+use crate::game::Outcome;
+use crate::i18n::Catalog;
+use crate::solver::Interval;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Answer {
+    Higher,
+    Lower,
+    Correct,
+}
+
+impl Answer {
+    // English, Spanish or symbols, whatever the language of the messages
+    pub fn parse(text: &str) -> Option<Answer> {
+        match text.trim().to_lowercase().as_str() {
+            "h" | "higher" | "mayor" | "+" => Some(Answer::Higher),
+            "l" | "lower" | "menor" | "-" => Some(Answer::Lower),
+            "c" | "correct" | "correcto" | "=" => Some(Answer::Correct),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    Continue,
+    Guessed,
+    // The answers contradict each other
+    Cheated,
+}
+
+pub struct Guesser {
+    interval: Interval,
+    attempts: u32,
+}
+
+impl Guesser {
+    pub fn new(low: u32, high: u32) -> Guesser {
+        Guesser {
+            interval: Interval { low, high },
+            attempts: 1,
+        }
+    }
+
+    pub fn guess(&self) -> u32 {
+        self.interval.middle()
+    }
+
+    pub fn attempts(&self) -> u32 {
+        self.attempts
+    }
+
+    // What the answers said: higher than the first, lower than the second
+    pub fn bounds(&self) -> (u32, u32) {
+        (self.interval.low - 1, self.interval.high + 1)
+    }
+
+    pub fn answer(&mut self, answer: Answer) -> Verdict {
+        // "higher" means my guess was too small
+        let outcome = match answer {
+            Answer::Higher => Outcome::TooSmall,
+            Answer::Lower => Outcome::TooBig,
+            Answer::Correct => return Verdict::Guessed,
+        };
+        self.interval.update(self.guess(), outcome);
+
+        if self.interval.is_empty() {
+            Verdict::Cheated
+        } else {
+            self.attempts += 1;
+            Verdict::Continue
+        }
+    }
+}
+
+// Plays until the number is guessed, a contradiction is found or the input
+// runs out (then it's Continue)
+pub fn play<R: BufRead, W: Write>(
+    low: u32,
+    high: u32,
+    catalog: &Catalog,
+    mut input: R,
+    mut output: W,
+) -> io::Result<Verdict> {
+    writeln!(output, "{}", catalog.format("reverse.rules", &[("low", &low), ("high", &high)]))?;
+    writeln!(output, "{}", catalog.text("reverse.answers"))?;
+
+    let mut guesser = Guesser::new(low, high);
+
+    loop {
+        let guess = guesser.guess();
+        let attempt = guesser.attempts();
+        writeln!(output, "{}", catalog.format("reverse.guess", &[("guess", &guess), ("attempt", &attempt)]))?;
+
+        let answer = loop {
+            let mut line = String::new();
+            if input.read_line(&mut line)? == 0 {
+                return Ok(Verdict::Continue);
+            }
+
+            match Answer::parse(&line) {
+                Some(answer) => break answer,
+                None => writeln!(output, "{}", catalog.text("reverse.invalid"))?,
+            }
+        };
+
+        match guesser.answer(answer) {
+            Verdict::Continue => {}
+            Verdict::Guessed => {
+                let attempts = catalog.plural("attempts", u64::from(guesser.attempts()));
+                writeln!(output, "{}", catalog.format("reverse.guessed", &[("attempts", &attempts)]))?;
+                return Ok(Verdict::Guessed);
+            }
+            Verdict::Cheated => {
+                let (above, below) = guesser.bounds();
+                writeln!(output, "{}", catalog.format("reverse.cheated", &[("above", &above), ("below", &below)]))?;
+                return Ok(Verdict::Cheated);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::i18n::Locale;
+    use std::cmp::Ordering;
+    use std::io::Cursor;
+
+    fn honest(secret: u32, guess: u32) -> Answer {
+        match secret.cmp(&guess) {
+            Ordering::Greater => Answer::Higher,
+            Ordering::Less => Answer::Lower,
+            Ordering::Equal => Answer::Correct,
+        }
+    }
+
+    fn play_script(script: &str) -> (Verdict, String) {
+        let mut output = Vec::new();
+        let catalog = Catalog::new(Locale::English);
+        let verdict = play(1, 100, &catalog, Cursor::new(script), &mut output).unwrap();
+
+        (verdict, String::from_utf8(output).unwrap())
+    }
+
+    #[test]
+    fn parses_answers() {
+        assert_eq!(Some(Answer::Higher), Answer::parse("H\n"));
+        assert_eq!(Some(Answer::Lower), Answer::parse(" lower "));
+        assert_eq!(Some(Answer::Correct), Answer::parse("c"));
+        assert_eq!(Some(Answer::Higher), Answer::parse("Mayor"));
+        assert_eq!(Some(Answer::Lower), Answer::parse("-"));
+        assert_eq!(None, Answer::parse("maybe"));
+    }
+
+    #[test]
+    fn guesses_every_number_with_honest_answers() {
+        for secret in 1..=1000 {
+            let mut guesser = Guesser::new(1, 1000);
+
+            loop {
+                match guesser.answer(honest(secret, guesser.guess())) {
+                    Verdict::Continue => {}
+                    Verdict::Guessed => break,
+                    Verdict::Cheated => panic!("honest answers for {} look like cheating", secret),
+                }
+            }
+
+            assert!(guesser.attempts() <= 10);
+        }
+    }
+
+    #[test]
+    fn detects_contradictions() {
+        let mut guesser = Guesser::new(1, 100);
+
+        assert_eq!(50, guesser.guess());
+        assert_eq!(Verdict::Continue, guesser.answer(Answer::Higher));
+        assert_eq!(75, guesser.guess());
+        assert_eq!(Verdict::Continue, guesser.answer(Answer::Lower));
+
+        // Only 51..=74 are left; keep saying "lower" until nothing is
+        let mut verdict = Verdict::Continue;
+        while verdict == Verdict::Continue {
+            verdict = guesser.answer(Answer::Lower);
+        }
+        assert_eq!(Verdict::Cheated, verdict);
+    }
+
+    #[test]
+    fn lower_than_the_minimum_is_cheating() {
+        let mut guesser = Guesser::new(1, 3);
+
+        assert_eq!(Verdict::Continue, guesser.answer(Answer::Lower));
+        assert_eq!(1, guesser.guess());
+        assert_eq!(Verdict::Cheated, guesser.answer(Answer::Lower));
+        assert_eq!((0, 1), guesser.bounds());
+    }
+
+    #[test]
+    fn plays_a_scripted_session() {
+        let (verdict, output) = play_script("higher\nwhat?\nl\ncorrect\n");
+
+        assert_eq!(Verdict::Guessed, verdict);
+        let expected = [
+            "Think of a number between 1 and 100 and I'll guess it.",
+            "Answer \"higher\", \"lower\" or \"correct\" (h/l/c).",
+            "My guess is 50 (attempt 1)",
+            "My guess is 75 (attempt 2)",
+            "Please answer higher, lower or correct",
+            "My guess is 62 (attempt 3)",
+            "I got it in 3 attempts!",
+        ];
+        assert_eq!(expected.join("\n") + "\n", output);
+    }
+
+    #[test]
+    fn reports_a_cheater() {
+        let (verdict, output) = play_script("h\nl\nh\nl\nh\nl\nh\nl\n");
+
+        assert_eq!(Verdict::Cheated, verdict);
+        assert!(output.ends_with("You're cheating! No number is higher than 65 and lower than 66\n"));
+    }
+
+    #[test]
+    fn input_running_out_stops() {
+        assert_eq!(Verdict::Continue, play_script("h\n").0);
+    }
+}
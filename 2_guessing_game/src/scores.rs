@@ -0,0 +1,267 @@
+// High scores: every finished game is appended as one line to a local
+// file, and the best wins of each difficulty are shown at the end.
+//
+// File format, one game per line, fields separated by tabs:
+//
+//   name  difficulty  won  attempts  seconds  score  unix-timestamp
+//
+// Lines that can't be read (a corrupted or hand-edited file) are skipped,
+// and a missing file is just an empty table.
+use std::env;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::difficulty::Difficulty;
+
+pub const TOP: usize = 10;
+
+const FILE_NAME: &str = ".guessing_game_scores";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Record {
+    pub name: String,
+    pub difficulty: Difficulty,
+    pub won: bool,
+    pub attempts: u32,
+    pub duration: Duration,
+    pub score: u32,
+    // Seconds since the unix epoch
+    pub date: u64,
+}
+
+impl Record {
+    pub fn new(name: &str, difficulty: Difficulty, won: bool, attempts: u32, duration: Duration, score: u32) -> Record {
+        let date = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|since| since.as_secs())
+            .unwrap_or(0);
+
+        Record {
+            name: clean_name(name),
+            difficulty,
+            won,
+            attempts,
+            duration,
+            score,
+            date,
+        }
+    }
+
+    fn to_line(&self) -> String {
+        format!(
+            "{}\t{}\t{}\t{}\t{}\t{}\t{}",
+            self.name,
+            self.difficulty.name(),
+            self.won,
+            self.attempts,
+            self.duration.as_secs(),
+            self.score,
+            self.date
+        )
+    }
+
+    fn from_line(line: &str) -> Option<Record> {
+        let fields: Vec<&str> = line.split('\t').collect();
+        if fields.len() != 7 || fields[0].is_empty() {
+            return None;
+        }
+
+        Some(Record {
+            name: fields[0].to_string(),
+            difficulty: Difficulty::parse(fields[1])?,
+            won: fields[2].parse().ok()?,
+            attempts: fields[3].parse().ok()?,
+            duration: Duration::from_secs(fields[4].parse().ok()?),
+            score: fields[5].parse().ok()?,
+            date: fields[6].parse().ok()?,
+        })
+    }
+}
+
+// Tabs and new lines would break the file format
+fn clean_name(name: &str) -> String {
+    let name: String = name
+        .trim()
+        .chars()
+        .map(|c| if c.is_control() { ' ' } else { c })
+        .collect();
+
+    if name.is_empty() {
+        String::from("anonymous")
+    } else {
+        name
+    }
+}
+
+pub struct HighScores {
+    path: PathBuf,
+    records: Vec<Record>,
+    // Lines of the file that couldn't be read
+    pub skipped: usize,
+}
+
+impl HighScores {
+    // $GUESSING_GAME_SCORES, or a hidden file in the home directory
+    pub fn default_path() -> PathBuf {
+        if let Some(path) = env::var_os("GUESSING_GAME_SCORES") {
+            return PathBuf::from(path);
+        }
+
+        match env::var_os("HOME") {
+            Some(home) => Path::new(&home).join(FILE_NAME),
+            None => PathBuf::from(FILE_NAME),
+        }
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> HighScores {
+        let path = path.as_ref().to_path_buf();
+        let contents = fs::read(&path).unwrap_or_default();
+        let contents = String::from_utf8_lossy(&contents);
+
+        let mut records = Vec::new();
+        let mut skipped = 0;
+
+        for line in contents.lines().filter(|line| !line.trim().is_empty()) {
+            match Record::from_line(line) {
+                Some(record) => records.push(record),
+                None => skipped += 1,
+            }
+        }
+
+        HighScores { path, records, skipped }
+    }
+
+    // Appends the game to the file; the table is updated even if that fails
+    pub fn add(&mut self, record: Record) -> io::Result<()> {
+        let line = record.to_line();
+        self.records.push(record);
+
+        // A corrupted file may not end with a new line
+        let needs_newline = fs::read(&self.path)
+            .map(|contents| !contents.is_empty() && !contents.ends_with(b"\n"))
+            .unwrap_or(false);
+
+        let mut file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+        if needs_newline {
+            writeln!(file)?;
+        }
+        writeln!(file, "{}", line)
+    }
+
+    // Best wins first: higher score, then fewer attempts, then faster
+    pub fn top(&self, difficulty: Difficulty) -> Vec<&Record> {
+        let mut wins: Vec<&Record> = self
+            .records
+            .iter()
+            .filter(|record| record.won && record.difficulty == difficulty)
+            .collect();
+
+        wins.sort_by(|a, b| {
+            b.score
+                .cmp(&a.score)
+                .then(a.attempts.cmp(&b.attempts))
+                .then(a.duration.cmp(&b.duration))
+        });
+        wins.truncate(TOP);
+        wins
+    }
+}
+
+// Unix timestamp to YYYY-MM-DD (UTC)
+pub fn format_date(timestamp: u64) -> String {
+    // Howard Hinnant's days-to-civil algorithm
+    let days = (timestamp / 86_400) as i64 + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days - era * 146_097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_path(name: &str) -> PathBuf {
+        env::temp_dir().join(format!("guessing_game-{}-{}", std::process::id(), name))
+    }
+
+    fn win(name: &str, score: u32, attempts: u32) -> Record {
+        Record::new(name, Difficulty::Normal, true, attempts, Duration::from_secs(20), score)
+    }
+
+    #[test]
+    fn missing_file_is_an_empty_table() {
+        let scores = HighScores::load(temp_path("missing"));
+
+        assert!(scores.top(Difficulty::Normal).is_empty());
+        assert_eq!(0, scores.skipped);
+    }
+
+    #[test]
+    fn records_survive_a_reload() {
+        let path = temp_path("reload");
+        let mut scores = HighScores::load(&path);
+        let record = win("ana\tmaría", 300, 3);
+        scores.add(record.clone()).unwrap();
+
+        let reloaded = HighScores::load(&path);
+        assert_eq!(vec![&record], reloaded.top(Difficulty::Normal));
+        assert_eq!("ana maría", record.name);
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn corrupted_lines_are_skipped() {
+        let path = temp_path("corrupted");
+        let good = win("bob", 250, 4).to_line();
+        fs::write(&path, format!("garbage\n{}\nbob\tnormal\ttrue\t\u{0}\n\u{fffd}\t{}", good, good)).unwrap();
+
+        let mut scores = HighScores::load(&path);
+        assert_eq!(1, scores.top(Difficulty::Normal).len());
+        assert_eq!(3, scores.skipped);
+
+        // The torn last line doesn't swallow the next game
+        scores.add(win("carol", 400, 2)).unwrap();
+        let reloaded = HighScores::load(&path);
+        assert_eq!("carol", reloaded.top(Difficulty::Normal)[0].name);
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn top_ten_per_difficulty() {
+        let path = temp_path("top");
+        let mut scores = HighScores::load(&path);
+
+        for score in 0..15 {
+            scores.add(win("player", score * 10, 5)).unwrap();
+        }
+        scores.add(win("fewer attempts", 140, 2)).unwrap();
+        scores.add(Record::new("loser", Difficulty::Normal, false, 7, Duration::from_secs(5), 999)).unwrap();
+        scores.add(Record::new("easy", Difficulty::Easy, true, 1, Duration::from_secs(5), 999)).unwrap();
+
+        let top = scores.top(Difficulty::Normal);
+        assert_eq!(TOP, top.len());
+        assert_eq!("fewer attempts", top[0].name);
+        assert_eq!(140, top[1].score);
+        assert!(top.iter().all(|record| record.won && record.difficulty == Difficulty::Normal));
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn formats_dates() {
+        assert_eq!("1970-01-01", format_date(0));
+        assert_eq!("2020-07-31", format_date(1_596_200_000));
+        assert_eq!("2000-02-29", format_date(951_782_400));
+    }
+}
@@ -0,0 +1,402 @@
+// Multiplayer over TCP: every connected player races to guess the same
+// secret number. Each connection has its own reader thread; all of them
+// send events through a channel to a single thread that owns the game,
+// so the game state never needs a lock.
+//
+// Protocol: one message per line, words separated by spaces.
+//
+//   client -> server
+//     NAME <name>                  must be the first message
+//     GUESS <number>
+//     QUIT
+//
+//   server -> client
+//     WELCOME <name> <round> <low> <high>
+//     JOIN <name>                  another player joined
+//     LEAVE <name>
+//     FEEDBACK <name> <number> too-small|too-big
+//     WINNER <name> <secret> <guesses>
+//     ROUND <round> <low> <high>   a new secret number was picked
+//     ERROR <message>
+//     BYE                          the server closes the connection
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::mpsc::{self, Sender};
+use std::thread;
+
+use crate::difficulty::Difficulty;
+use crate::game::Game;
+use crate::guess::Guess;
+
+enum Event {
+    Joined(usize, TcpStream),
+    Line(usize, String),
+    Left(usize),
+}
+
+struct Player {
+    // None until the NAME message
+    name: Option<String>,
+    stream: TcpStream,
+    guesses: u32,
+}
+
+pub struct Server {
+    listener: TcpListener,
+    difficulty: Difficulty,
+    seed: u64,
+}
+
+impl Server {
+    pub fn bind<A: ToSocketAddrs>(addr: A, difficulty: Difficulty, seed: u64) -> io::Result<Server> {
+        Ok(Server {
+            listener: TcpListener::bind(addr)?,
+            difficulty,
+            seed,
+        })
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    // Round N uses the secret of Game::new(difficulty, seed + N - 1).
+    // With Some(rounds) the server stops after that many winners.
+    pub fn run(self, rounds: Option<u32>) -> io::Result<()> {
+        let (tx, rx) = mpsc::channel();
+        let listener = self.listener;
+        thread::spawn(move || accept(listener, tx));
+
+        let mut room = Room {
+            difficulty: self.difficulty,
+            seed: self.seed,
+            rounds,
+            round: 1,
+            secret: Game::new(self.difficulty, self.seed).secret(),
+            players: BTreeMap::new(),
+        };
+
+        for event in rx {
+            let playing = match event {
+                Event::Joined(id, stream) => {
+                    room.players.insert(id, Player { name: None, stream, guesses: 0 });
+                    true
+                }
+                Event::Line(id, line) => room.handle(id, &line),
+                Event::Left(id) => {
+                    room.leave(id);
+                    true
+                }
+            };
+
+            if !playing {
+                break;
+            }
+        }
+
+        room.broadcast("BYE");
+        for player in room.players.values() {
+            let _ = player.stream.shutdown(Shutdown::Both);
+        }
+
+        Ok(())
+    }
+}
+
+// The game state, owned by the thread that runs the server
+struct Room {
+    difficulty: Difficulty,
+    seed: u64,
+    rounds: Option<u32>,
+    round: u32,
+    secret: u32,
+    players: BTreeMap<usize, Player>,
+}
+
+impl Room {
+    // Returns false when the last round is over
+    fn handle(&mut self, id: usize, line: &str) -> bool {
+        let mut words = line.split_whitespace();
+        let command = words.next().unwrap_or("");
+        let argument = words.next().unwrap_or("");
+        let name = match self.players.get(&id) {
+            Some(player) => player.name.clone(),
+            None => return true,
+        };
+
+        match (command, name) {
+            ("NAME", None) => self.join(id, argument),
+            ("NAME", Some(_)) => self.send(id, "ERROR you already have a name"),
+            (_, None) => self.send(id, "ERROR send NAME <name> first"),
+            ("GUESS", Some(name)) => return self.guess(id, name, argument),
+            ("QUIT", Some(_)) => {
+                self.send(id, "BYE");
+                self.leave(id);
+            }
+            _ => self.send(id, "ERROR unknown command"),
+        }
+
+        true
+    }
+
+    fn join(&mut self, id: usize, name: &str) {
+        let name = self.unique_name(name, id);
+        if let Some(player) = self.players.get_mut(&id) {
+            player.name = Some(name.clone());
+        }
+
+        let (low, high) = self.difficulty.range();
+        self.send(id, &format!("WELCOME {} {} {} {}", name, self.round, low, high));
+        self.broadcast(&format!("JOIN {}", name));
+    }
+
+    fn leave(&mut self, id: usize) {
+        if let Some(player) = self.players.remove(&id) {
+            let _ = player.stream.shutdown(Shutdown::Both);
+
+            if let Some(name) = player.name {
+                self.broadcast(&format!("LEAVE {}", name));
+            }
+        }
+    }
+
+    fn guess(&mut self, id: usize, name: String, argument: &str) -> bool {
+        let (low, high) = self.difficulty.range();
+        let guess = match Guess::parse(argument, low, high) {
+            Ok(guess) => guess.value(),
+            Err(e) => {
+                self.send(id, &format!("ERROR {}", e));
+                return true;
+            }
+        };
+
+        let guesses = match self.players.get_mut(&id) {
+            Some(player) => {
+                player.guesses += 1;
+                player.guesses
+            }
+            None => return true,
+        };
+
+        let feedback = match guess.cmp(&self.secret) {
+            Ordering::Less => "too-small",
+            Ordering::Greater => "too-big",
+            Ordering::Equal => {
+                self.broadcast(&format!("WINNER {} {} {}", name, self.secret, guesses));
+                return self.next_round();
+            }
+        };
+        self.broadcast(&format!("FEEDBACK {} {} {}", name, guess, feedback));
+
+        true
+    }
+
+    fn next_round(&mut self) -> bool {
+        if self.rounds.is_some_and(|rounds| self.round >= rounds) {
+            return false;
+        }
+
+        self.round += 1;
+        let seed = self.seed.wrapping_add(u64::from(self.round - 1));
+        self.secret = Game::new(self.difficulty, seed).secret();
+        for player in self.players.values_mut() {
+            player.guesses = 0;
+        }
+
+        let (low, high) = self.difficulty.range();
+        self.broadcast(&format!("ROUND {} {} {}", self.round, low, high));
+        true
+    }
+
+    // Names are one word and can't be repeated
+    fn unique_name(&self, name: &str, id: usize) -> String {
+        let name = if name.is_empty() {
+            format!("player-{}", id)
+        } else {
+            name.to_string()
+        };
+        let taken = self.players.values().any(|player| player.name.as_ref() == Some(&name));
+
+        if taken {
+            format!("{}-{}", name, id)
+        } else {
+            name
+        }
+    }
+
+    fn send(&mut self, id: usize, message: &str) {
+        if let Some(player) = self.players.get_mut(&id) {
+            let _ = writeln!(player.stream, "{}", message);
+        }
+    }
+
+    // Only to players who already have a name
+    fn broadcast(&mut self, message: &str) {
+        for player in self.players.values_mut().filter(|player| player.name.is_some()) {
+            let _ = writeln!(player.stream, "{}", message);
+        }
+    }
+}
+
+// Accepts connections forever, one reader thread per player
+fn accept(listener: TcpListener, tx: Sender<Event>) {
+    for (id, stream) in listener.incoming().enumerate() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(_) => continue,
+        };
+        let reader = match stream.try_clone() {
+            Ok(reader) => reader,
+            Err(_) => continue,
+        };
+
+        if tx.send(Event::Joined(id, stream)).is_err() {
+            return;
+        }
+
+        let tx = tx.clone();
+        thread::spawn(move || {
+            for line in BufReader::new(reader).lines() {
+                let line = match line {
+                    Ok(line) => line,
+                    Err(_) => break,
+                };
+                if tx.send(Event::Line(id, line)).is_err() {
+                    return;
+                }
+            }
+            let _ = tx.send(Event::Left(id));
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    struct Connection {
+        reader: BufReader<TcpStream>,
+        writer: TcpStream,
+    }
+
+    impl Connection {
+        fn open(addr: SocketAddr) -> Connection {
+            let stream = TcpStream::connect(addr).unwrap();
+            stream.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+
+            Connection {
+                reader: BufReader::new(stream.try_clone().unwrap()),
+                writer: stream,
+            }
+        }
+
+        fn send(&mut self, line: &str) {
+            writeln!(self.writer, "{}", line).unwrap();
+        }
+
+        fn receive(&mut self) -> String {
+            let mut line = String::new();
+            self.reader.read_line(&mut line).unwrap();
+            line.trim_end().to_string()
+        }
+    }
+
+    fn start(seed: u64, rounds: u32) -> (SocketAddr, thread::JoinHandle<io::Result<()>>) {
+        let server = Server::bind("127.0.0.1:0", Difficulty::Normal, seed).unwrap();
+        let addr = server.local_addr().unwrap();
+
+        (addr, thread::spawn(move || server.run(Some(rounds))))
+    }
+
+    fn wrong_guess(secret: u32) -> (u32, &'static str) {
+        if secret > 1 {
+            (secret - 1, "too-small")
+        } else {
+            (secret + 1, "too-big")
+        }
+    }
+
+    #[test]
+    fn players_race_for_the_same_number() {
+        let secret = Game::new(Difficulty::Normal, 5).secret();
+        let (addr, server) = start(5, 1);
+
+        let mut alice = Connection::open(addr);
+        alice.send("NAME alice");
+        assert_eq!("WELCOME alice 1 1 100", alice.receive());
+        assert_eq!("JOIN alice", alice.receive());
+
+        let mut bob = Connection::open(addr);
+        bob.send("NAME bob");
+        assert_eq!("WELCOME bob 1 1 100", bob.receive());
+        assert_eq!("JOIN bob", bob.receive());
+        assert_eq!("JOIN bob", alice.receive());
+
+        let (guess, feedback) = wrong_guess(secret);
+        alice.send(&format!("GUESS {}", guess));
+        let expected = format!("FEEDBACK alice {} {}", guess, feedback);
+        assert_eq!(expected, alice.receive());
+        assert_eq!(expected, bob.receive());
+
+        bob.send(&format!("GUESS {}", secret));
+        let expected = format!("WINNER bob {} 1", secret);
+        assert_eq!(expected, alice.receive());
+        assert_eq!(expected, bob.receive());
+        assert_eq!("BYE", alice.receive());
+        assert_eq!("BYE", bob.receive());
+
+        server.join().unwrap().unwrap();
+    }
+
+    #[test]
+    fn new_round_after_a_winner() {
+        let first = Game::new(Difficulty::Normal, 9).secret();
+        let second = Game::new(Difficulty::Normal, 10).secret();
+        let (addr, server) = start(9, 2);
+
+        let mut carol = Connection::open(addr);
+        carol.send("NAME carol");
+        carol.receive();
+        carol.receive();
+
+        carol.send(&format!("GUESS {}", first));
+        assert_eq!(format!("WINNER carol {} 1", first), carol.receive());
+        assert_eq!("ROUND 2 1 100", carol.receive());
+
+        carol.send(&format!("GUESS {}", second));
+        assert_eq!(format!("WINNER carol {} 1", second), carol.receive());
+        assert_eq!("BYE", carol.receive());
+
+        server.join().unwrap().unwrap();
+    }
+
+    #[test]
+    fn rejects_invalid_messages() {
+        let (addr, _server) = start(1, 1);
+
+        let mut dave = Connection::open(addr);
+        dave.send("GUESS 10");
+        assert_eq!("ERROR send NAME <name> first", dave.receive());
+
+        dave.send("NAME dave");
+        dave.receive();
+        dave.receive();
+        dave.send("GUESS 500");
+        assert_eq!("ERROR 500 is out of range, the number is between 1 and 100", dave.receive());
+        dave.send("GUESS ten");
+        assert_eq!("ERROR \"ten\" is not a number", dave.receive());
+        dave.send("DANCE");
+        assert_eq!("ERROR unknown command", dave.receive());
+
+        let mut other = Connection::open(addr);
+        other.send("NAME dave");
+        assert!(other.receive().starts_with("WELCOME dave-"));
+        assert!(other.receive().starts_with("JOIN dave-"));
+
+        other.send("QUIT");
+        assert_eq!("BYE", other.receive());
+    }
+}
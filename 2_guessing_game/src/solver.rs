@@ -0,0 +1,290 @@
+// The computer plays its own game: a Strategy picks each guess from the
+// feedback of the previous ones, and run() plays N games per strategy to
+// compare them. New strategies only need to implement the trait and be
+// added to strategies().
+use std::fmt;
+use rand::{Rng, SeedableRng};
+use rand::prng::ChaChaRng;
+
+use crate::difficulty::Difficulty;
+use crate::game::{Game, Outcome, State};
+use crate::i18n::Catalog;
+
+pub trait Strategy {
+    fn name(&self) -> &'static str;
+
+    // Called before every game with the inclusive range of the secret
+    fn start(&mut self, low: u32, high: u32);
+
+    fn next_guess(&mut self) -> u32;
+
+    fn feedback(&mut self, guess: u32, outcome: Outcome);
+}
+
+// Keeps the interval where the secret can still be
+#[derive(Debug, Default)]
+pub struct Interval {
+    pub low: u32,
+    pub high: u32,
+}
+
+impl Interval {
+    pub fn update(&mut self, guess: u32, outcome: Outcome) {
+        match outcome {
+            Outcome::TooSmall => self.low = guess + 1,
+            Outcome::TooBig => self.high = guess.saturating_sub(1),
+            Outcome::Win => {}
+        }
+    }
+
+    // Only possible when the feedback contradicts itself
+    pub fn is_empty(&self) -> bool {
+        self.low > self.high
+    }
+
+    pub fn middle(&self) -> u32 {
+        self.low + (self.high - self.low) / 2
+    }
+}
+
+// Always the middle of the interval: never more than log2(range) attempts
+#[derive(Default)]
+pub struct BinarySearch {
+    interval: Interval,
+}
+
+impl Strategy for BinarySearch {
+    fn name(&self) -> &'static str {
+        "binary-search"
+    }
+
+    fn start(&mut self, low: u32, high: u32) {
+        self.interval = Interval { low, high };
+    }
+
+    fn next_guess(&mut self) -> u32 {
+        self.interval.middle()
+    }
+
+    fn feedback(&mut self, guess: u32, outcome: Outcome) {
+        self.interval.update(guess, outcome);
+    }
+}
+
+// Any number of the interval, with its own seeded generator
+pub struct RandomGuess {
+    interval: Interval,
+    rng: ChaChaRng,
+}
+
+impl RandomGuess {
+    pub fn new(seed: u64) -> RandomGuess {
+        RandomGuess {
+            interval: Interval::default(),
+            rng: ChaChaRng::seed_from_u64(seed),
+        }
+    }
+}
+
+impl Strategy for RandomGuess {
+    fn name(&self) -> &'static str {
+        "random"
+    }
+
+    fn start(&mut self, low: u32, high: u32) {
+        self.interval = Interval { low, high };
+    }
+
+    fn next_guess(&mut self) -> u32 {
+        self.rng.gen_range(self.interval.low, self.interval.high + 1)
+    }
+
+    fn feedback(&mut self, guess: u32, outcome: Outcome) {
+        self.interval.update(guess, outcome);
+    }
+}
+
+// A player who always thinks the number is small: guesses at a
+// quarter of the interval instead of the middle
+#[derive(Default)]
+pub struct Biased {
+    interval: Interval,
+}
+
+impl Strategy for Biased {
+    fn name(&self) -> &'static str {
+        "biased-low"
+    }
+
+    fn start(&mut self, low: u32, high: u32) {
+        self.interval = Interval { low, high };
+    }
+
+    fn next_guess(&mut self) -> u32 {
+        self.interval.low + (self.interval.high - self.interval.low) / 4
+    }
+
+    fn feedback(&mut self, guess: u32, outcome: Outcome) {
+        self.interval.update(guess, outcome);
+    }
+}
+
+pub fn strategies(seed: u64) -> Vec<Box<dyn Strategy>> {
+    vec![
+        Box::new(BinarySearch::default()),
+        Box::new(RandomGuess::new(seed)),
+        Box::new(Biased::default()),
+    ]
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Report {
+    pub strategy: &'static str,
+    pub games: u32,
+    pub wins: u32,
+    pub total_attempts: u32,
+    pub worst: u32,
+}
+
+impl Report {
+    pub fn average(&self) -> f64 {
+        if self.games == 0 {
+            0.0
+        } else {
+            f64::from(self.total_attempts) / f64::from(self.games)
+        }
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{:<14} {:>6} {:>6} {:>13.2} {:>6}",
+            self.strategy, self.games, self.wins, self.average(), self.worst
+        )
+    }
+}
+
+// Same widths as the rows of Report
+pub fn header(catalog: &Catalog) -> String {
+    format!(
+        "{:<14} {:>6} {:>6} {:>13} {:>6}",
+        catalog.text("solver.strategy"),
+        catalog.text("solver.games"),
+        catalog.text("solver.wins"),
+        catalog.text("solver.average"),
+        catalog.text("solver.worst")
+    )
+}
+
+// One game, returns (won, attempts)
+pub fn play(strategy: &mut dyn Strategy, game: &mut Game) -> (bool, u32) {
+    let (low, high) = game.difficulty().range();
+    strategy.start(low, high);
+
+    while game.state() == State::Playing {
+        let guess = strategy.next_guess();
+        let outcome = game
+            .guess(&guess.to_string())
+            .expect("strategies guess inside the range");
+        strategy.feedback(guess, outcome);
+    }
+
+    (game.state() == State::Won, game.attempts())
+}
+
+// Every strategy plays the same N secret numbers: seed, seed + 1, ...
+pub fn run(difficulty: Difficulty, games: u32, seed: u64) -> Vec<Report> {
+    strategies(seed)
+        .into_iter()
+        .map(|mut strategy| {
+            let mut report = Report {
+                strategy: strategy.name(),
+                games,
+                wins: 0,
+                total_attempts: 0,
+                worst: 0,
+            };
+
+            for i in 0..games {
+                let mut game = Game::new(difficulty, seed.wrapping_add(u64::from(i)));
+                let (won, attempts) = play(strategy.as_mut(), &mut game);
+
+                report.wins += won as u32;
+                report.total_attempts += attempts;
+                report.worst = report.worst.max(attempts);
+            }
+
+            report
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn binary_search_always_wins() {
+        for &difficulty in Difficulty::ALL.iter() {
+            let (low, high) = difficulty.range();
+
+            for secret in low..=high {
+                let mut game = Game::with_secret(difficulty, secret);
+                let (won, attempts) = play(&mut BinarySearch::default(), &mut game);
+
+                assert!(won, "{:?} lost with secret {}", difficulty, secret);
+                assert!(attempts <= difficulty.max_attempts());
+            }
+        }
+    }
+
+    #[test]
+    fn strategies_guess_inside_the_interval() {
+        for mut strategy in strategies(3) {
+            strategy.start(1, 100);
+            strategy.feedback(40, Outcome::TooSmall);
+            strategy.feedback(60, Outcome::TooBig);
+
+            for _ in 0..20 {
+                let guess = strategy.next_guess();
+                assert!((41..=59).contains(&guess), "{} guessed {}", strategy.name(), guess);
+            }
+        }
+    }
+
+    #[test]
+    fn biased_player_needs_more_attempts() {
+        let reports = run(Difficulty::Hard, 200, 11);
+        let binary = &reports[0];
+        let biased = &reports[2];
+
+        assert_eq!("binary-search", binary.strategy);
+        assert_eq!(200, binary.wins);
+        assert!(biased.average() > binary.average());
+    }
+
+    #[test]
+    fn runs_are_reproducible() {
+        assert_eq!(run(Difficulty::Normal, 30, 5), run(Difficulty::Normal, 30, 5));
+    }
+
+    #[test]
+    fn report_totals() {
+        let report = Report {
+            strategy: "test",
+            games: 4,
+            wins: 3,
+            total_attempts: 18,
+            worst: 7,
+        };
+
+        assert_eq!(4.5, report.average());
+        assert_eq!("test                4      3          4.50      7", report.to_string());
+        assert_eq!(
+            "strategy        games   wins  avg attempts  worst",
+            header(&Catalog::new(crate::i18n::Locale::English))
+        );
+    }
+}
@@ -0,0 +1,226 @@
+// Wordle-style variant: guess a five-letter word from the bundled list.
+// Every guess gets a mark per letter: in the right place, somewhere else
+// in the word, or not in the word at all.
+use rand::{Rng, SeedableRng};
+use rand::prng::ChaChaRng;
+
+use crate::game::State;
+use crate::i18n::Catalog;
+use crate::puzzle::Puzzle;
+
+pub const LENGTH: usize = 5;
+pub const MAX_ATTEMPTS: u32 = 6;
+
+// One word per line, all of them LENGTH lowercase letters
+const WORDS: &str = include_str!("words.txt");
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mark {
+    Right,
+    Present,
+    Absent,
+}
+
+impl Mark {
+    fn symbol(self) -> char {
+        match self {
+            Mark::Right => '=',
+            Mark::Present => '~',
+            Mark::Absent => '.',
+        }
+    }
+}
+
+// A letter repeated in the guess is only Present as many times as it
+// appears in the secret, after counting the ones in the right place
+pub fn marks(secret: &str, guess: &str) -> Vec<Mark> {
+    let secret: Vec<char> = secret.chars().collect();
+    let guess: Vec<char> = guess.chars().collect();
+    let mut marks = vec![Mark::Absent; guess.len()];
+    let mut unmatched: Vec<char> = Vec::new();
+
+    for (i, &letter) in secret.iter().enumerate() {
+        if guess.get(i) == Some(&letter) {
+            marks[i] = Mark::Right;
+        } else {
+            unmatched.push(letter);
+        }
+    }
+
+    for (i, letter) in guess.iter().enumerate() {
+        if marks[i] == Mark::Right {
+            continue;
+        }
+        if let Some(position) = unmatched.iter().position(|other| other == letter) {
+            unmatched.swap_remove(position);
+            marks[i] = Mark::Present;
+        }
+    }
+
+    marks
+}
+
+pub fn words() -> Vec<&'static str> {
+    WORDS.lines().collect()
+}
+
+pub struct Word {
+    secret: String,
+    attempts: u32,
+    state: State,
+}
+
+impl Word {
+    pub fn new(seed: u64) -> Word {
+        let words = words();
+        let mut rng = ChaChaRng::seed_from_u64(seed);
+
+        Word::with_secret(words[rng.gen_range(0, words.len())])
+    }
+
+    pub fn with_secret(secret: &str) -> Word {
+        Word {
+            secret: secret.to_string(),
+            attempts: 0,
+            state: State::Playing,
+        }
+    }
+}
+
+impl Puzzle for Word {
+    fn rules(&self, catalog: &Catalog) -> String {
+        let attempts = catalog.plural("attempts", u64::from(MAX_ATTEMPTS));
+        catalog.format("word.rules", &[("length", &LENGTH), ("attempts", &attempts)])
+    }
+
+    fn prompt(&self, catalog: &Catalog) -> String {
+        catalog.text("word.prompt").to_string()
+    }
+
+    fn max_attempts(&self) -> u32 {
+        MAX_ATTEMPTS
+    }
+
+    fn attempts(&self) -> u32 {
+        self.attempts
+    }
+
+    fn state(&self) -> State {
+        self.state
+    }
+
+    fn attempt(&mut self, input: &str, catalog: &Catalog) -> Result<String, String> {
+        assert_eq!(State::Playing, self.state, "the game is over");
+
+        let guess = input.trim().to_lowercase();
+        if !guess.chars().all(|c| c.is_ascii_alphabetic()) {
+            return Err(catalog.text("word.only-letters").to_string());
+        }
+        if guess.len() != LENGTH {
+            return Err(catalog.format("word.length", &[("length", &LENGTH)]));
+        }
+        if !words().contains(&guess.as_str()) {
+            return Err(catalog.format("word.not-in-list", &[("word", &guess)]));
+        }
+        self.attempts += 1;
+
+        let feedback: String = marks(&self.secret, &guess).into_iter().map(Mark::symbol).collect();
+
+        if guess == self.secret {
+            self.state = State::Won;
+            return Ok(format!("{}\n{}", feedback, catalog.text("win")));
+        }
+        if self.attempts == MAX_ATTEMPTS {
+            self.state = State::Lost;
+        }
+
+        Ok(feedback)
+    }
+
+    fn quit(&mut self) {
+        assert_eq!(State::Playing, self.state, "the game is over");
+        self.state = State::Quit;
+    }
+
+    fn reveal(&self, catalog: &Catalog) -> String {
+        catalog.format("word.reveal", &[("word", &self.secret)])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::i18n::Locale;
+
+    fn symbols(secret: &str, guess: &str) -> String {
+        marks(secret, guess).into_iter().map(Mark::symbol).collect()
+    }
+
+    #[test]
+    fn bundled_words_are_valid() {
+        let words = words();
+
+        assert!(words.len() > 100);
+        for word in words {
+            assert_eq!(LENGTH, word.len(), "{}", word);
+            assert!(word.chars().all(|c| c.is_ascii_lowercase()), "{}", word);
+        }
+    }
+
+    #[test]
+    fn marks_every_letter() {
+        assert_eq!("=====", symbols("crane", "crane"));
+        assert_eq!(".....", symbols("crane", "dusty"));
+        assert_eq!("~~=~.", symbols("crane", "react"));
+        assert_eq!("==...", symbols("crane", "crowd"));
+    }
+
+    #[test]
+    fn repeated_letters_are_marked_once() {
+        // Only one "e" in the secret, already in the right place
+        assert_eq!("..~.=", symbols("crane", "eerie"));
+        // Two "l" and two "a" in the guess, one of each in the secret
+        assert_eq!(".=~..", symbols("alert", "llama"));
+        assert_eq!("~.=..", symbols("hello", "lilac"));
+    }
+
+    #[test]
+    fn same_seed_same_word() {
+        assert_eq!(Word::new(3).secret, Word::new(3).secret);
+        assert!(words().contains(&Word::new(3).secret.as_str()));
+    }
+
+    #[test]
+    fn rejects_invalid_words() {
+        let catalog = Catalog::new(Locale::English);
+        let mut word = Word::with_secret("crane");
+
+        assert_eq!(Err("The word has 5 letters".to_string()), word.attempt("cat", &catalog));
+        assert_eq!(Err("Only letters, please".to_string()), word.attempt("cr4ne", &catalog));
+        assert_eq!(Err("\"zzzzz\" is not in the word list".to_string()), word.attempt("zzzzz", &catalog));
+        assert_eq!(0, word.attempts());
+    }
+
+    #[test]
+    fn plays_until_found() {
+        let catalog = Catalog::new(Locale::English);
+        let mut word = Word::with_secret("crane");
+
+        assert_eq!(Ok("==...".to_string()), word.attempt("CROWD\n", &catalog));
+        assert_eq!(Ok("=====\nYou win!".to_string()), word.attempt("crane", &catalog));
+        assert_eq!(State::Won, word.state());
+        assert_eq!(2, word.attempts());
+    }
+
+    #[test]
+    fn six_wrong_words_lose() {
+        let catalog = Catalog::new(Locale::English);
+        let mut word = Word::with_secret("crane");
+
+        for _ in 0..MAX_ATTEMPTS {
+            word.attempt("sport", &catalog).unwrap();
+        }
+        assert_eq!(State::Lost, word.state());
+        assert_eq!("The word was crane", word.reveal(&catalog));
+    }
+}
@@ -0,0 +1,600 @@
+/* -------------------- API por HTTP -------------------- */
+/*
+  Las tabletas del salon hablan con el restaurante por HTTP/1.1 y JSON.
+  Todo pasa por "Store", asi que cada cambio queda en la bitacora antes
+  de contestar. El dinero va en centavos y el tiempo en minutos desde que
+  se abrio, igual que en el resto de la libreria.
+
+    GET  /tables                       las mesas y quien esta sentado
+    POST /tables/{numero}/leave        el grupo de la mesa se va
+    GET  /waitlist                     la lista de espera
+    POST /waitlist                     {"name", "size", "at"} -> {"quote"}
+    POST /seatings                     {"at"}: sienta al siguiente, o null
+    GET  /reservations
+    POST /reservations                 {"name", "size", "at"} -> {"id"}
+    POST /reservations/{id}/check-in   {"at"}
+    POST /reservations/expire          {"at"}: las que ya no llegaron
+    GET  /orders
+    GET  /orders/{id}
+    POST /orders                       {"table", "server", "items": ["Flan", ...]}
+    POST /orders/{id}/kitchen          la comanda para la cocina
+    POST /orders/{id}/void             {"item"}: la posicion, desde 0
+    POST /orders/{id}/comp             {"item"}
+    POST /orders/{id}/serve
+    POST /orders/{id}/payments         {"at", "discount", "tip", "tenders": [{"cash": 500}]}
+    GET  /payments
+
+  Los errores contestan {"error": "..."} con 400 si la peticion esta mal
+  escrita, 404 si no existe lo que se pide y 409 si el restaurante no lo
+  permite en este momento (una orden que no se ha servido no se cobra).
+
+  Se atiende una conexion a la vez: el restaurante es uno solo y cada
+  peticion es corta.
+
+  Si la bitacora no se puede escribir se contesta 500 y se vuelve a abrir
+  antes de la siguiente peticion, para no contestar con un evento que no
+  quedo guardado. Si tampoco se puede abrir, el servidor deja de atender.
+*/
+
+pub mod http;
+pub mod json;
+
+use std::convert::TryFrom;
+use std::io::{self, BufReader};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+use crate::front_of_house::billing::Tender;
+use crate::front_of_house::hosting::{HostingError, Party, Reservation, Seating, Table};
+use crate::front_of_house::serving::{Order, OrderStatus, Ticket};
+use crate::menu::Menu;
+use crate::money::Money;
+use crate::simulation::TAX_RATE;
+use crate::storage::{Event, Outcome, Payment, Store, StoreError};
+use http::Request;
+use json::Json;
+
+// Un cliente que no termina de mandar su peticion no detiene al resto
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+// El codigo y el cuerpo de la respuesta
+type Reply = (u16, Json);
+
+pub struct Server {
+  listener: TcpListener,
+  store: Store,
+  menu: Menu,
+  // La bitacora fallo y no se pudo volver a abrir
+  failed: Option<io::Error>,
+}
+
+impl Server {
+  // Con el puerto 0 el sistema elige uno libre; ver "local_addr"
+  pub fn bind<A: ToSocketAddrs>(addr: A, store: Store, menu: Menu) -> io::Result<Server> {
+    Ok(Server { listener: TcpListener::bind(addr)?, store, menu, failed: None })
+  }
+
+  pub fn local_addr(&self) -> io::Result<SocketAddr> {
+    self.listener.local_addr()
+  }
+
+  pub fn store(&self) -> &Store {
+    &self.store
+  }
+
+  // Atiende hasta que la bitacora ya no se puede usar y devuelve por que;
+  // una conexion que falla no tumba al servidor
+  pub fn serve(&mut self) -> io::Error {
+    loop {
+      if let Err(e) = self.serve_one() {
+        eprintln!("Error en una conexion: {}", e);
+      }
+      if let Some(e) = self.failed.take() {
+        return e;
+      }
+    }
+  }
+
+  // Espera una conexion y contesta su peticion
+  pub fn serve_one(&mut self) -> io::Result<()> {
+    let (stream, _) = self.listener.accept()?;
+    stream.set_read_timeout(Some(TIMEOUT))?;
+
+    let (status, body) = match http::read_request(&mut BufReader::new(&stream)) {
+      Ok(request) => self.handle(&request),
+      Err(e) => error(400, e),
+    };
+    http::write_response(&mut &stream, status, &body)
+  }
+
+  pub fn handle(&mut self, request: &Request) -> (u16, Json) {
+    match self.route(request) {
+      Ok(reply) | Err(reply) => reply,
+    }
+  }
+
+  fn route(&mut self, request: &Request) -> Result<Reply, Reply> {
+    let path = request.path.split('?').next().unwrap_or_default();
+    let segments: Vec<&str> = path.split('/').filter(|segment| !segment.is_empty()).collect();
+    let body = || body(request);
+
+    let reply = match (request.method.as_str(), segments.as_slice()) {
+      ("GET", ["tables"]) => {
+        let host = self.store.restaurant().host();
+        let tables = host.tables().iter().map(|table| table_json(table, host.seating_at(table.number))).collect();
+        (200, Json::Array(tables))
+      }
+      ("POST", ["tables", table, "leave"]) => {
+        let table = id(table)?;
+        let seating = match self.execute(Event::Leave { table })? {
+          Outcome::Left(seating) => seating,
+          outcome => return Err(unexpected(outcome)),
+        };
+        (200, seating_json(&seating))
+      }
+      ("GET", ["waitlist"]) => {
+        let waitlist = self.store.restaurant().host().waitlist().map(party_json).collect();
+        (200, Json::Array(waitlist))
+      }
+      ("POST", ["waitlist"]) => {
+        let body = body()?;
+        let event = Event::Waitlist { name: text(&body, "name")?, size: number(&body, "size")?, at: number(&body, "at")? };
+        let quote = match self.execute(event)? {
+          Outcome::Quoted(quote) => quote,
+          outcome => return Err(unexpected(outcome)),
+        };
+        (201, Json::object(vec![("quote", quote.into())]))
+      }
+      ("POST", ["seatings"]) => {
+        let event = Event::Seat { at: number(&body()?, "at")? };
+        match self.execute(event)? {
+          Outcome::Seated(Some(seating)) => (201, seating_json(&seating)),
+          Outcome::Seated(None) => (200, Json::Null),
+          outcome => return Err(unexpected(outcome)),
+        }
+      }
+      ("GET", ["reservations"]) => {
+        let reservations = self.store.restaurant().host().reservations().iter().map(reservation_json).collect();
+        (200, Json::Array(reservations))
+      }
+      ("POST", ["reservations"]) => {
+        let body = body()?;
+        let event = Event::Reserve { name: text(&body, "name")?, size: number(&body, "size")?, at: number(&body, "at")? };
+        let id = match self.execute(event)? {
+          Outcome::Reserved(id) => id,
+          outcome => return Err(unexpected(outcome)),
+        };
+        (201, Json::object(vec![("id", id.into())]))
+      }
+      ("POST", ["reservations", "expire"]) => {
+        let event = Event::Expire { at: number(&body()?, "at")? };
+        let expired = match self.execute(event)? {
+          Outcome::Expired(expired) => expired,
+          outcome => return Err(unexpected(outcome)),
+        };
+        (200, Json::Array(expired.iter().map(reservation_json).collect()))
+      }
+      ("POST", ["reservations", reservation, "check-in"]) => {
+        let reservation = id(reservation)?;
+        self.execute(Event::CheckIn { reservation, at: number(&body()?, "at")? })?;
+        let host = self.store.restaurant().host();
+        let checked_in = host.reservations().iter().find(|r| r.id == reservation).map(reservation_json);
+        (200, checked_in.unwrap_or(Json::Null))
+      }
+      ("GET", ["orders"]) => (200, Json::Array(self.store.restaurant().orders().map(order_json).collect())),
+      ("GET", ["orders", order]) => (200, self.order(id(order)?)?),
+      ("POST", ["orders"]) => {
+        let body = body()?;
+        let table = number(&body, "table")?;
+        let server = text(&body, "server")?;
+        let names = body.get("items").and_then(Json::as_array).ok_or_else(|| missing("items"))?;
+
+        let mut items = Vec::with_capacity(names.len());
+        for name in names {
+          let name = name.as_str().ok_or_else(|| error(400, "los platillos van por nombre"))?;
+          let item = self.menu.get(name).ok_or_else(|| error(400, format!("el menu no tiene {}", name)))?;
+          items.push((item.name.clone(), item.category, item.price));
+        }
+
+        let order = self.store.restaurant().orders().map(Order::id).max().unwrap_or(0) + 1;
+        self.execute(Event::TakeOrder { order, table, server, items })?;
+        (201, self.order(order)?)
+      }
+      ("POST", ["orders", order, "kitchen"]) => {
+        let order = id(order)?;
+        let ticket = match self.execute(Event::SendToKitchen { order })? {
+          Outcome::Ticket(ticket) => ticket,
+          outcome => return Err(unexpected(outcome)),
+        };
+        (200, ticket_json(&ticket))
+      }
+      ("POST", ["orders", order, "void"]) => {
+        let order = id(order)?;
+        self.execute(Event::Void { order, item: number(&body()?, "item")? })?;
+        (200, self.order(order)?)
+      }
+      ("POST", ["orders", order, "comp"]) => {
+        let order = id(order)?;
+        self.execute(Event::Comp { order, item: number(&body()?, "item")? })?;
+        (200, self.order(order)?)
+      }
+      ("POST", ["orders", order, "serve"]) => {
+        let order = id(order)?;
+        self.execute(Event::Serve { order })?;
+        (200, self.order(order)?)
+      }
+      ("POST", ["orders", order, "payments"]) => {
+        let order = id(order)?;
+        let body = body()?;
+        let tenders = body.get("tenders").and_then(Json::as_array).ok_or_else(|| missing("tenders"))?;
+        let tenders = tenders.iter().map(tender).collect::<Result<_, _>>()?;
+
+        let event = Event::Pay {
+          order,
+          at: number(&body, "at")?,
+          tax_rate: TAX_RATE,
+          discount: money(&body, "discount")?,
+          tip: money(&body, "tip")?,
+          tenders,
+        };
+        let payment = match self.execute(event)? {
+          Outcome::Paid(payment) => payment,
+          outcome => return Err(unexpected(outcome)),
+        };
+        (201, payment_json(&payment))
+      }
+      ("GET", ["payments"]) => (200, Json::Array(self.store.restaurant().payments().iter().map(payment_json).collect())),
+      _ => return Err(error(404, format!("no existe {} {}", request.method, path))),
+    };
+
+    Ok(reply)
+  }
+
+  fn execute(&mut self, event: Event) -> Result<Outcome, Reply> {
+    let e = match self.store.execute(event) {
+      Ok(outcome) => return Ok(outcome),
+      Err(e) => e,
+    };
+
+    let status = match e {
+      StoreError::UnknownOrder(_)
+      | StoreError::UnknownTable(_)
+      | StoreError::Hosting(HostingError::UnknownTable(_))
+      | StoreError::Hosting(HostingError::UnknownReservation(_)) => 404,
+      StoreError::Io(_) => {
+        // El estado en memoria ya tiene el evento que no se guardo
+        if let Err(e) = self.store.reopen() {
+          self.failed = Some(e);
+        }
+        500
+      }
+      _ => 409,
+    };
+    Err(error(status, e))
+  }
+
+  fn order(&self, order: u32) -> Result<Json, Reply> {
+    let order = self.store.restaurant().order(order).ok_or_else(|| error(404, StoreError::UnknownOrder(order)))?;
+    Ok(order_json(order))
+  }
+}
+
+// Un cliente para las pruebas y para quien no quiera armar HTTP a mano
+pub fn request<A: ToSocketAddrs>(addr: A, method: &str, path: &str, body: Option<&Json>) -> io::Result<(u16, Json)> {
+  let mut stream = TcpStream::connect(addr)?;
+  stream.set_read_timeout(Some(TIMEOUT))?;
+  http::write_request(&mut stream, method, path, body)?;
+
+  let (status, body) = http::read_response(&mut BufReader::new(stream))?;
+  let body = Json::parse(&body).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+  Ok((status, body))
+}
+
+/* ---- Lo que llega ---- */
+
+fn error<M: ToString>(status: u16, message: M) -> Reply {
+  (status, Json::object(vec![("error", message.to_string().into())]))
+}
+
+// "Store" contesto algo que no va con el evento: el error es del
+// servidor, no de la peticion
+fn unexpected(outcome: Outcome) -> Reply {
+  error(500, format!("resultado inesperado: {:?}", outcome))
+}
+
+fn missing(key: &str) -> Reply {
+  error(400, format!("falta \"{}\"", key))
+}
+
+// Sin cuerpo es lo mismo que un objeto vacio
+fn body(request: &Request) -> Result<Json, Reply> {
+  if request.body.trim().is_empty() {
+    return Ok(Json::Object(Vec::new()));
+  }
+  Json::parse(&request.body).map_err(|e| error(400, e))
+}
+
+// El numero en la ruta; si no es numero la ruta no existe
+fn id(segment: &str) -> Result<u32, Reply> {
+  segment.parse().map_err(|_| error(404, format!("no existe {}", segment)))
+}
+
+fn number(body: &Json, key: &str) -> Result<u32, Reply> {
+  let value = body.get(key).ok_or_else(|| missing(key))?;
+  value
+    .as_i64()
+    .and_then(|n| u32::try_from(n).ok())
+    .ok_or_else(|| error(400, format!("\"{}\" debe ser un entero positivo", key)))
+}
+
+fn text(body: &Json, key: &str) -> Result<String, Reply> {
+  let value = body.get(key).ok_or_else(|| missing(key))?;
+  value.as_str().map(str::to_string).ok_or_else(|| error(400, format!("\"{}\" debe ser texto", key)))
+}
+
+// En centavos; si no viene es cero
+fn money(body: &Json, key: &str) -> Result<Money, Reply> {
+  match body.get(key) {
+    None | Some(Json::Null) => Ok(Money::ZERO),
+    Some(value) => value.as_i64().map(Money::from_cents).ok_or_else(|| error(400, format!("\"{}\" va en centavos", key))),
+  }
+}
+
+// {"cash": 500} o {"card": 500}
+fn tender(json: &Json) -> Result<Tender, Reply> {
+  match (json.get("cash").and_then(Json::as_i64), json.get("card").and_then(Json::as_i64)) {
+    (Some(cents), None) => Ok(Tender::Cash(Money::from_cents(cents))),
+    (None, Some(cents)) => Ok(Tender::Card(Money::from_cents(cents))),
+    _ => Err(error(400, "cada pago es {\"cash\": centavos} o {\"card\": centavos}")),
+  }
+}
+
+/* ---- Lo que sale ---- */
+
+fn cents(money: Money) -> Json {
+  Json::Number(money.cents())
+}
+
+fn table_json(table: &Table, seating: Option<&Seating>) -> Json {
+  Json::object(vec![
+    ("number", table.number.into()),
+    ("seats", table.seats.into()),
+    ("party", seating.map(|s| s.name.clone()).into()),
+    ("size", seating.map(|s| s.size).into()),
+    ("since", seating.map(|s| s.since).into()),
+  ])
+}
+
+fn seating_json(seating: &Seating) -> Json {
+  Json::object(vec![
+    ("name", seating.name.as_str().into()),
+    ("size", seating.size.into()),
+    ("tables", seating.tables.clone().into()),
+    ("since", seating.since.into()),
+    ("waited", seating.waited.into()),
+  ])
+}
+
+fn party_json(party: &Party) -> Json {
+  Json::object(vec![
+    ("name", party.name.as_str().into()),
+    ("size", party.size.into()),
+    ("arrived", party.arrived.into()),
+    ("quoted", party.quoted.into()),
+  ])
+}
+
+fn reservation_json(reservation: &Reservation) -> Json {
+  Json::object(vec![
+    ("id", reservation.id.into()),
+    ("name", reservation.name.as_str().into()),
+    ("size", reservation.size.into()),
+    ("at", reservation.at.into()),
+    ("checked_in", reservation.checked_in.into()),
+  ])
+}
+
+fn order_json(order: &Order) -> Json {
+  let status = match order.status() {
+    OrderStatus::Placed => "placed",
+    OrderStatus::Cooking => "cooking",
+    OrderStatus::Served => "served",
+    OrderStatus::Paid => "paid",
+  };
+  let items = order
+    .items()
+    .iter()
+    .enumerate()
+    .map(|(i, item)| {
+      Json::object(vec![
+        ("name", item.name.as_str().into()),
+        ("price", cents(item.price)),
+        ("comped", order.is_comped(i).into()),
+      ])
+    })
+    .collect();
+  let voided = order.voided().iter().map(|item| item.name.clone()).collect::<Vec<_>>();
+
+  Json::object(vec![
+    ("id", order.id().into()),
+    ("table", order.table().into()),
+    ("server", order.server().into()),
+    ("covers", order.covers().into()),
+    ("status", status.into()),
+    ("items", Json::Array(items)),
+    ("voided", voided.into()),
+    ("total", cents(order.total())),
+  ])
+}
+
+fn ticket_json(ticket: &Ticket) -> Json {
+  Json::object(vec![
+    ("order", ticket.order.into()),
+    ("table", ticket.table.into()),
+    ("items", ticket.items.clone().into()),
+  ])
+}
+
+fn payment_json(payment: &Payment) -> Json {
+  let tenders = payment
+    .tenders
+    .iter()
+    .map(|tender| match tender {
+      Tender::Cash(amount) => Json::object(vec![("cash", cents(*amount))]),
+      Tender::Card(amount) => Json::object(vec![("card", cents(*amount))]),
+    })
+    .collect();
+  let check = &payment.check;
+  let totals = &check.totals;
+
+  Json::object(vec![
+    ("order", check.order.into()),
+    ("table", check.table.into()),
+    ("server", check.server.as_str().into()),
+    ("at", check.paid_at.into()),
+    ("subtotal", cents(totals.subtotal)),
+    ("discount", cents(totals.discount)),
+    ("tax", cents(totals.tax)),
+    ("tip", cents(totals.tip)),
+    ("total", cents(totals.total)),
+    ("tenders", Json::Array(tenders)),
+  ])
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::simulation::SAMPLE_MENU;
+  use std::env;
+  use std::fs;
+  use std::io::{Read, Write};
+  use std::path::PathBuf;
+  use std::thread;
+
+  fn temp_path(name: &str) -> PathBuf {
+    let path = env::temp_dir().join(format!("restaurants-{}-{}", std::process::id(), name));
+    let _ = fs::remove_file(&path);
+    path
+  }
+
+  fn tables() -> Vec<Table> {
+    vec![Table::new(1, 2), Table::new(2, 4)]
+  }
+
+  // El servidor atiende "requests" peticiones en otro hilo y se devuelve
+  fn serve(path: &PathBuf, requests: usize) -> (SocketAddr, thread::JoinHandle<Server>) {
+    let store = Store::open(path, tables()).unwrap();
+    let mut server = Server::bind("127.0.0.1:0", store, Menu::parse(SAMPLE_MENU).unwrap()).unwrap();
+    let addr = server.local_addr().unwrap();
+
+    let handle = thread::spawn(move || {
+      for _ in 0..requests {
+        server.serve_one().unwrap();
+      }
+      server
+    });
+    (addr, handle)
+  }
+
+  fn json(text: &str) -> Json {
+    Json::parse(text).unwrap()
+  }
+
+  #[test]
+  fn serves_a_whole_meal() {
+    let path = temp_path("api-meal");
+    let (addr, server) = serve(&path, 9);
+    let call = |method, route, body: &str| request(addr, method, route, Some(&json(body))).unwrap();
+
+    assert_eq!((201, json(r#"{"quote":0}"#)), call("POST", "/waitlist", r#"{"name":"Ana","size":2,"at":0}"#));
+    let (status, seating) = call("POST", "/seatings", r#"{"at":1}"#);
+    assert_eq!(201, status);
+    assert_eq!(Some(&json("[1]")), seating.get("tables"));
+
+    let (status, order) = call("POST", "/orders", r#"{"table":1,"server":"Beto","items":["Flan","cafe","Papas"]}"#);
+    assert_eq!(201, status);
+    assert_eq!(Some(1), order.get("id").and_then(Json::as_i64));
+    assert_eq!(Some("placed"), order.get("status").and_then(Json::as_str));
+    assert_eq!(Some(2), order.get("covers").and_then(Json::as_i64));
+
+    let (status, order) = call("POST", "/orders/1/void", r#"{"item":2}"#);
+    assert_eq!(200, status);
+    assert_eq!(Some(&json(r#"["Papas"]"#)), order.get("voided"));
+
+    let (_, ticket) = call("POST", "/orders/1/kitchen", "{}");
+    assert_eq!(Some(&json(r#"["Flan","Cafe"]"#)), ticket.get("items"));
+    let (_, order) = call("POST", "/orders/1/serve", "{}");
+    assert_eq!(Some("served"), order.get("status").and_then(Json::as_str));
+    let (_, order) = call("POST", "/orders/1/comp", r#"{"item":0}"#);
+    assert_eq!(Some(&Json::Bool(true)), order.get("items").and_then(Json::as_array).and_then(|items| items[0].get("comped")));
+
+    let total = order.get("total").and_then(Json::as_i64).unwrap();
+    let tax = Money::from_cents(total).percent(TAX_RATE).cents();
+    let body = format!(r#"{{"at":60,"tip":100,"tenders":[{{"card":{}}}]}}"#, total + tax + 100);
+    let (status, payment) = call("POST", "/orders/1/payments", &body);
+    assert_eq!(201, status);
+    assert_eq!(Some(total + tax + 100), payment.get("total").and_then(Json::as_i64));
+
+    let (status, floor) = request(addr, "GET", "/tables", None).unwrap();
+    assert_eq!(200, status);
+    assert_eq!(Some("Ana"), floor.as_array().unwrap()[0].get("party").and_then(Json::as_str));
+    assert_eq!(Some(&Json::Null), floor.as_array().unwrap()[1].get("party"));
+
+    // Todo quedo en la bitacora
+    let server = server.join().unwrap();
+    assert_eq!(8, server.store().events());
+    drop(server);
+    let store = Store::open(&path, tables()).unwrap();
+    assert_eq!(OrderStatus::Paid, store.restaurant().order(1).unwrap().status());
+    assert_eq!("Flan", store.restaurant().checks().next().unwrap().comps[0].name);
+
+    fs::remove_file(&path).unwrap();
+  }
+
+  #[test]
+  fn answers_errors_with_a_reason() {
+    let path = temp_path("api-errors");
+    let (addr, server) = serve(&path, 8);
+    let call = |method, route, body: Option<&str>| request(addr, method, route, body.map(json).as_ref()).unwrap().0;
+
+    assert_eq!(404, call("GET", "/kitchen", None));
+    assert_eq!(404, call("GET", "/orders/7", None));
+    assert_eq!(404, call("POST", "/orders/siete/serve", None));
+    assert_eq!(400, call("POST", "/waitlist", Some(r#"{"name":"Ana","size":2}"#)));
+    assert_eq!(400, call("POST", "/orders", Some(r#"{"table":1,"server":"Beto","items":["Pizza"]}"#)));
+    assert_eq!(409, call("POST", "/tables/1/leave", None));
+
+    let (status, body) = request(addr, "POST", "/waitlist", Some(&json(r#"{"name":"Todos","size":9,"at":0}"#))).unwrap();
+    assert_eq!(409, status);
+    assert_eq!(Some("un grupo de 9 no cabe, el salon tiene 6 lugares"), body.get("error").and_then(Json::as_str));
+
+    // HTTP mal escrito
+    let mut stream = TcpStream::connect(addr).unwrap();
+    stream.write_all(b"hola\r\n\r\n").unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    assert!(response.starts_with("HTTP/1.1 400 Bad Request\r\n"), "{}", response);
+
+    assert_eq!(0, server.join().unwrap().store().events());
+    fs::remove_file(&path).unwrap();
+  }
+
+  #[test]
+  fn malformed_bodies_are_bad_requests() {
+    let mut server = {
+      let path = temp_path("api-bodies");
+      let store = Store::open(&path, tables()).unwrap();
+      fs::remove_file(&path).unwrap();
+      Server::bind("127.0.0.1:0", store, Menu::new(Vec::new())).unwrap()
+    };
+    let post = |path: &str, body: &str| Request { method: "POST".to_string(), path: path.to_string(), body: body.to_string() };
+
+    assert_eq!(400, server.handle(&post("/seatings", "{\"at\": ")).0);
+    assert_eq!(400, server.handle(&post("/seatings", r#"{"at": -5}"#)).0);
+    assert_eq!(400, server.handle(&post("/orders/1/payments", r#"{"tenders": [{"cheque": 100}]}"#)).0);
+    assert_eq!(404, server.handle(&post("/orders/1/payments", r#"{"at": 0, "tenders": []}"#)).0);
+    assert_eq!(400, server.handle(&post("/orders", r#"{"table": 1, "items": []}"#)).0);
+    assert_eq!(200, server.handle(&post("/seatings?mesa=1", r#"{"at": 0}"#)).0);
+    assert_eq!(400, server.handle(&post("/seatings", &"[".repeat(64 * 1024))).0);
+  }
+}
@@ -0,0 +1,169 @@
+/* ---- HTTP/1.1 ---- */
+/*
+  Solo lo que necesita la API: una peticion por conexion, el cuerpo se
+  lee con "Content-Length" (nada de "chunked") y la respuesta siempre
+  cierra la conexion.
+*/
+
+use std::io::{self, BufRead, Read, Write};
+
+use super::json::Json;
+
+// Mas que esto no es una orden, es un error
+const MAX_BODY: usize = 64 * 1024;
+// Lo mismo para los encabezados: sin limite, un cliente podria mandar una
+// linea sin fin y acabarse la memoria
+const MAX_LINE: usize = 8 * 1024;
+const MAX_HEADERS: usize = 100;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Request {
+  pub method: String,
+  pub path: String,
+  pub body: String,
+}
+
+pub fn read_request<R: BufRead>(reader: &mut R) -> io::Result<Request> {
+  let line = read_line(reader)?;
+  let mut parts = line.split(' ');
+  let (method, path, version) = match (parts.next(), parts.next(), parts.next(), parts.next()) {
+    (Some(method), Some(path), Some(version), None) => (method, path, version),
+    _ => return Err(invalid(format!("linea de peticion invalida: {}", line))),
+  };
+  if !version.starts_with("HTTP/1.") {
+    return Err(invalid(format!("version no soportada: {}", version)));
+  }
+
+  let body = read_body(reader)?;
+  Ok(Request { method: method.to_string(), path: path.to_string(), body })
+}
+
+pub fn write_request<W: Write>(writer: &mut W, method: &str, path: &str, body: Option<&Json>) -> io::Result<()> {
+  let body = body.map(Json::to_string).unwrap_or_default();
+
+  write!(writer, "{} {} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n", method, path)?;
+  write!(writer, "Content-Type: application/json\r\nContent-Length: {}\r\n\r\n{}", body.len(), body)?;
+  writer.flush()
+}
+
+// El codigo y el cuerpo
+pub fn read_response<R: BufRead>(reader: &mut R) -> io::Result<(u16, String)> {
+  let line = read_line(reader)?;
+  let status = line
+    .split(' ')
+    .nth(1)
+    .and_then(|code| code.parse().ok())
+    .ok_or_else(|| invalid(format!("linea de estado invalida: {}", line)))?;
+
+  Ok((status, read_body(reader)?))
+}
+
+pub fn write_response<W: Write>(writer: &mut W, status: u16, body: &Json) -> io::Result<()> {
+  let body = body.to_string();
+
+  write!(writer, "HTTP/1.1 {} {}\r\nConnection: close\r\n", status, reason(status))?;
+  write!(writer, "Content-Type: application/json\r\nContent-Length: {}\r\n\r\n{}", body.len(), body)?;
+  writer.flush()
+}
+
+fn reason(status: u16) -> &'static str {
+  match status {
+    200 => "OK",
+    201 => "Created",
+    400 => "Bad Request",
+    404 => "Not Found",
+    409 => "Conflict",
+    _ => "Internal Server Error",
+  }
+}
+
+// Los encabezados hasta la linea vacia, y luego el cuerpo
+fn read_body<R: BufRead>(reader: &mut R) -> io::Result<String> {
+  let mut length = 0;
+
+  for headers in 0.. {
+    let line = read_line(reader)?;
+    if line.is_empty() {
+      break;
+    }
+    if headers == MAX_HEADERS {
+      return Err(invalid(format!("mas de {} encabezados", MAX_HEADERS)));
+    }
+    let (name, value) = line.split_once(':').ok_or_else(|| invalid(format!("encabezado invalido: {}", line)))?;
+    if name.trim().eq_ignore_ascii_case("content-length") {
+      length = value.trim().parse().map_err(|_| invalid(format!("Content-Length invalido: {}", value.trim())))?;
+    }
+  }
+  if length > MAX_BODY {
+    return Err(invalid(format!("el cuerpo mide {} bytes, el maximo es {}", length, MAX_BODY)));
+  }
+
+  let mut body = vec![0; length];
+  reader.read_exact(&mut body)?;
+  String::from_utf8(body).map_err(|_| invalid("el cuerpo no es UTF-8".to_string()))
+}
+
+fn read_line<R: BufRead>(reader: &mut R) -> io::Result<String> {
+  let mut line = String::new();
+  let read = reader.take(MAX_LINE as u64 + 1).read_line(&mut line)?;
+  if read == 0 {
+    return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "la conexion se cerro"));
+  }
+  if read > MAX_LINE && !line.ends_with('\n') {
+    return Err(invalid(format!("una linea de mas de {} bytes", MAX_LINE)));
+  }
+
+  Ok(line.trim_end_matches(['\r', '\n']).to_string())
+}
+
+fn invalid(message: String) -> io::Error {
+  io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn requests_round_trip() {
+    let body = Json::object(vec![("mesa", 3u32.into())]);
+    let mut bytes = Vec::new();
+    write_request(&mut bytes, "POST", "/orders", Some(&body)).unwrap();
+
+    let request = read_request(&mut &bytes[..]).unwrap();
+    assert_eq!("POST", request.method);
+    assert_eq!("/orders", request.path);
+    assert_eq!(r#"{"mesa":3}"#, request.body);
+  }
+
+  #[test]
+  fn responses_round_trip() {
+    let mut bytes = Vec::new();
+    write_response(&mut bytes, 404, &Json::object(vec![("error", "no existe".into())])).unwrap();
+
+    assert!(bytes.starts_with(b"HTTP/1.1 404 Not Found\r\n"));
+    assert_eq!((404, r#"{"error":"no existe"}"#.to_string()), read_response(&mut &bytes[..]).unwrap());
+  }
+
+  #[test]
+  fn rejects_bad_requests() {
+    let kind = |text: &str| read_request(&mut text.as_bytes()).unwrap_err().kind();
+
+    assert_eq!(io::ErrorKind::InvalidData, kind("GET /tables\r\n\r\n"));
+    assert_eq!(io::ErrorKind::InvalidData, kind("GET /tables SPDY/3\r\n\r\n"));
+    assert_eq!(io::ErrorKind::InvalidData, kind("GET / HTTP/1.1\r\nsin dos puntos\r\n\r\n"));
+    assert_eq!(io::ErrorKind::InvalidData, kind("POST / HTTP/1.1\r\nContent-Length: 99999999\r\n\r\n"));
+    assert_eq!(io::ErrorKind::UnexpectedEof, kind("POST / HTTP/1.1\r\nContent-Length: 10\r\n\r\n{}"));
+    assert_eq!(io::ErrorKind::UnexpectedEof, kind(""));
+
+    let long = format!("GET /{} HTTP/1.1\r\n\r\n", "a".repeat(MAX_LINE));
+    assert_eq!(io::ErrorKind::InvalidData, kind(&long));
+    let many = format!("GET / HTTP/1.1\r\n{}\r\n", "X-Uno: 1\r\n".repeat(MAX_HEADERS + 1));
+    assert_eq!(io::ErrorKind::InvalidData, kind(&many));
+    let enough = format!("GET / HTTP/1.1\r\n{}\r\n", "X-Uno: 1\r\n".repeat(MAX_HEADERS));
+    assert!(read_request(&mut enough.as_bytes()).is_ok());
+    // Una linea que nunca termina
+    let endless = read_request(&mut io::BufReader::new(io::repeat(b'a'))).unwrap_err();
+    assert_eq!(io::ErrorKind::InvalidData, endless.kind());
+  }
+}
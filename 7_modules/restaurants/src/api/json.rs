@@ -0,0 +1,419 @@
+/* ---- JSON ---- */
+/*
+  Lo justo de JSON para la API, sin dependencias. Los numeros son enteros
+  (el dinero va en centavos y el tiempo en minutos), asi que un numero con
+  decimales o exponente es un error. Los objetos guardan sus llaves en el
+  orden en que llegaron.
+
+  Los arreglos y objetos se leen con recursion, asi que se limita que tan
+  anidados pueden venir: sin limite, un cuerpo de puros "[" acaba con la
+  pila y con el servidor.
+*/
+
+use std::error::Error;
+use std::fmt;
+
+// Arreglos y objetos uno dentro de otro; la API no necesita ni cinco
+pub const MAX_DEPTH: usize = 32;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Json {
+  Null,
+  Bool(bool),
+  Number(i64),
+  String(String),
+  Array(Vec<Json>),
+  Object(Vec<(String, Json)>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JsonError {
+  // El byte donde se encontro el problema
+  pub at: usize,
+  pub message: String,
+}
+
+impl fmt::Display for JsonError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "JSON invalido en el byte {}: {}", self.at, self.message)
+  }
+}
+
+impl Error for JsonError {}
+
+impl Json {
+  // Un objeto a partir de sus pares, para armar respuestas
+  pub fn object(fields: Vec<(&str, Json)>) -> Json {
+    Json::Object(fields.into_iter().map(|(key, value)| (key.to_string(), value)).collect())
+  }
+
+  pub fn parse(text: &str) -> Result<Json, JsonError> {
+    let mut parser = Parser { bytes: text.as_bytes(), at: 0, depth: 0 };
+    let value = parser.value()?;
+    parser.spaces();
+
+    if parser.at < parser.bytes.len() {
+      return Err(parser.error("sobra texto despues del valor"));
+    }
+    Ok(value)
+  }
+
+  pub fn get(&self, key: &str) -> Option<&Json> {
+    match self {
+      Json::Object(fields) => fields.iter().find(|(k, _)| k == key).map(|(_, value)| value),
+      _ => None,
+    }
+  }
+
+  pub fn as_i64(&self) -> Option<i64> {
+    match self {
+      Json::Number(n) => Some(*n),
+      _ => None,
+    }
+  }
+
+  pub fn as_str(&self) -> Option<&str> {
+    match self {
+      Json::String(s) => Some(s),
+      _ => None,
+    }
+  }
+
+  pub fn as_array(&self) -> Option<&[Json]> {
+    match self {
+      Json::Array(values) => Some(values),
+      _ => None,
+    }
+  }
+}
+
+impl From<&str> for Json {
+  fn from(s: &str) -> Json {
+    Json::String(s.to_string())
+  }
+}
+
+impl From<String> for Json {
+  fn from(s: String) -> Json {
+    Json::String(s)
+  }
+}
+
+impl From<u32> for Json {
+  fn from(n: u32) -> Json {
+    Json::Number(i64::from(n))
+  }
+}
+
+impl From<i64> for Json {
+  fn from(n: i64) -> Json {
+    Json::Number(n)
+  }
+}
+
+impl From<bool> for Json {
+  fn from(b: bool) -> Json {
+    Json::Bool(b)
+  }
+}
+
+impl<T: Into<Json>> From<Option<T>> for Json {
+  fn from(value: Option<T>) -> Json {
+    value.map_or(Json::Null, Into::into)
+  }
+}
+
+impl<T: Into<Json>> From<Vec<T>> for Json {
+  fn from(values: Vec<T>) -> Json {
+    Json::Array(values.into_iter().map(Into::into).collect())
+  }
+}
+
+impl fmt::Display for Json {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      Json::Null => write!(f, "null"),
+      Json::Bool(b) => write!(f, "{}", b),
+      Json::Number(n) => write!(f, "{}", n),
+      Json::String(s) => quote(f, s),
+      Json::Array(values) => {
+        write!(f, "[")?;
+        for (i, value) in values.iter().enumerate() {
+          if i > 0 {
+            write!(f, ",")?;
+          }
+          write!(f, "{}", value)?;
+        }
+        write!(f, "]")
+      }
+      Json::Object(fields) => {
+        write!(f, "{{")?;
+        for (i, (key, value)) in fields.iter().enumerate() {
+          if i > 0 {
+            write!(f, ",")?;
+          }
+          quote(f, key)?;
+          write!(f, ":{}", value)?;
+        }
+        write!(f, "}}")
+      }
+    }
+  }
+}
+
+fn quote(f: &mut fmt::Formatter, s: &str) -> fmt::Result {
+  write!(f, "\"")?;
+  for c in s.chars() {
+    match c {
+      '"' => write!(f, "\\\"")?,
+      '\\' => write!(f, "\\\\")?,
+      '\n' => write!(f, "\\n")?,
+      '\r' => write!(f, "\\r")?,
+      '\t' => write!(f, "\\t")?,
+      c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+      c => write!(f, "{}", c)?,
+    }
+  }
+  write!(f, "\"")
+}
+
+struct Parser<'a> {
+  bytes: &'a [u8],
+  at: usize,
+  depth: usize,
+}
+
+impl<'a> Parser<'a> {
+  fn error(&self, message: &str) -> JsonError {
+    JsonError { at: self.at, message: message.to_string() }
+  }
+
+  fn spaces(&mut self) {
+    while self.bytes.get(self.at).is_some_and(|b| b" \t\r\n".contains(b)) {
+      self.at += 1;
+    }
+  }
+
+  fn peek(&mut self) -> Option<u8> {
+    self.spaces();
+    self.bytes.get(self.at).copied()
+  }
+
+  fn expect(&mut self, byte: u8) -> Result<(), JsonError> {
+    if self.peek() != Some(byte) {
+      return Err(self.error(&format!("se esperaba '{}'", byte as char)));
+    }
+    self.at += 1;
+    Ok(())
+  }
+
+  fn keyword(&mut self, word: &str, value: Json) -> Result<Json, JsonError> {
+    if !self.bytes[self.at..].starts_with(word.as_bytes()) {
+      return Err(self.error("valor desconocido"));
+    }
+    self.at += word.len();
+    Ok(value)
+  }
+
+  fn value(&mut self) -> Result<Json, JsonError> {
+    match self.peek() {
+      Some(b'n') => self.keyword("null", Json::Null),
+      Some(b't') => self.keyword("true", Json::Bool(true)),
+      Some(b'f') => self.keyword("false", Json::Bool(false)),
+      Some(b'"') => Ok(Json::String(self.string()?)),
+      Some(b'[') => self.nested(Parser::array),
+      Some(b'{') => self.nested(Parser::object),
+      Some(b'-') | Some(b'0'..=b'9') => self.number(),
+      Some(_) => Err(self.error("valor desconocido")),
+      None => Err(self.error("el texto se acabo")),
+    }
+  }
+
+  fn nested(&mut self, parse: fn(&mut Parser<'a>) -> Result<Json, JsonError>) -> Result<Json, JsonError> {
+    if self.depth == MAX_DEPTH {
+      return Err(self.error(&format!("mas de {} niveles anidados", MAX_DEPTH)));
+    }
+
+    self.depth += 1;
+    let value = parse(self);
+    self.depth -= 1;
+    value
+  }
+
+  fn number(&mut self) -> Result<Json, JsonError> {
+    let start = self.at;
+    if self.bytes[self.at] == b'-' {
+      self.at += 1;
+    }
+    while self.bytes.get(self.at).is_some_and(u8::is_ascii_digit) {
+      self.at += 1;
+    }
+    if self.bytes.get(self.at).is_some_and(|b| b".eE".contains(b)) {
+      return Err(self.error("solo se aceptan numeros enteros"));
+    }
+
+    let text = std::str::from_utf8(&self.bytes[start..self.at]).unwrap_or("");
+    text.parse().map(Json::Number).map_err(|_| JsonError { at: start, message: "numero invalido".to_string() })
+  }
+
+  fn string(&mut self) -> Result<String, JsonError> {
+    self.expect(b'"')?;
+    let mut bytes = Vec::new();
+
+    loop {
+      let byte = *self.bytes.get(self.at).ok_or_else(|| self.error("falta cerrar la cadena"))?;
+      self.at += 1;
+      match byte {
+        b'"' => break,
+        b'\\' => {
+          let escape = *self.bytes.get(self.at).ok_or_else(|| self.error("falta cerrar la cadena"))?;
+          self.at += 1;
+          let c = match escape {
+            b'"' => '"',
+            b'\\' => '\\',
+            b'/' => '/',
+            b'b' => '\u{8}',
+            b'f' => '\u{c}',
+            b'n' => '\n',
+            b'r' => '\r',
+            b't' => '\t',
+            b'u' => self.unicode()?,
+            _ => return Err(self.error("escape desconocido")),
+          };
+          bytes.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+        }
+        _ if byte < 0x20 => return Err(self.error("caracter de control en la cadena")),
+        _ => bytes.push(byte),
+      }
+    }
+
+    String::from_utf8(bytes).map_err(|_| self.error("la cadena no es UTF-8"))
+  }
+
+  // "\uXXXX", con los pares sustitutos para lo que no cabe en 16 bits
+  fn unicode(&mut self) -> Result<char, JsonError> {
+    let high = self.hex()?;
+    let code = if (0xD800..0xDC00).contains(&high) {
+      if !self.bytes[self.at..].starts_with(b"\\u") {
+        return Err(self.error("falta la segunda mitad del par"));
+      }
+      self.at += 2;
+      let low = self.hex()?;
+      if !(0xDC00..0xE000).contains(&low) {
+        return Err(self.error("par sustituto invalido"));
+      }
+      0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00)
+    } else {
+      high
+    };
+
+    char::from_u32(code).ok_or_else(|| self.error("caracter invalido"))
+  }
+
+  fn hex(&mut self) -> Result<u32, JsonError> {
+    let digits = self.bytes.get(self.at..self.at + 4).ok_or_else(|| self.error("escape incompleto"))?;
+    let digits = std::str::from_utf8(digits).map_err(|_| self.error("escape invalido"))?;
+    let code = u32::from_str_radix(digits, 16).map_err(|_| self.error("escape invalido"))?;
+    self.at += 4;
+    Ok(code)
+  }
+
+  fn array(&mut self) -> Result<Json, JsonError> {
+    self.expect(b'[')?;
+    let mut values = Vec::new();
+    if self.peek() == Some(b']') {
+      self.at += 1;
+      return Ok(Json::Array(values));
+    }
+
+    loop {
+      values.push(self.value()?);
+      match self.peek() {
+        Some(b',') => self.at += 1,
+        Some(b']') => {
+          self.at += 1;
+          return Ok(Json::Array(values));
+        }
+        _ => return Err(self.error("se esperaba ',' o ']'")),
+      }
+    }
+  }
+
+  fn object(&mut self) -> Result<Json, JsonError> {
+    self.expect(b'{')?;
+    let mut fields = Vec::new();
+    if self.peek() == Some(b'}') {
+      self.at += 1;
+      return Ok(Json::Object(fields));
+    }
+
+    loop {
+      self.spaces();
+      let key = self.string()?;
+      self.expect(b':')?;
+      fields.push((key, self.value()?));
+      match self.peek() {
+        Some(b',') => self.at += 1,
+        Some(b'}') => {
+          self.at += 1;
+          return Ok(Json::Object(fields));
+        }
+        _ => return Err(self.error("se esperaba ',' o '}'")),
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn parses_nested_values() {
+    let json = Json::parse(r#" {"mesa": 3, "platillos": ["Flan", "Café"], "nota": null, "ok": true} "#).unwrap();
+
+    assert_eq!(Some(3), json.get("mesa").and_then(Json::as_i64));
+    assert_eq!(Some("Café"), json.get("platillos").and_then(Json::as_array).and_then(|a| a[1].as_str()));
+    assert_eq!(Some(&Json::Null), json.get("nota"));
+    assert_eq!(Some(&Json::Bool(true)), json.get("ok"));
+    assert_eq!(None, json.get("falta"));
+    assert_eq!(Json::Number(-12), Json::parse("-12").unwrap());
+    assert_eq!(Json::String("😀".to_string()), Json::parse(r#""😀""#).unwrap());
+  }
+
+  #[test]
+  fn writes_what_it_reads() {
+    let json = Json::object(vec![
+      ("nombre", "Ana \"la\" \\ de\nla 3".into()),
+      ("lista", vec![1u32, 2].into()),
+      ("vacio", Json::Object(Vec::new())),
+      ("nada", Option::<u32>::None.into()),
+    ]);
+    let text = json.to_string();
+
+    assert_eq!(r#"{"nombre":"Ana \"la\" \\ de\nla 3","lista":[1,2],"vacio":{},"nada":null}"#, text);
+    assert_eq!(json, Json::parse(&text).unwrap());
+  }
+
+  #[test]
+  fn reports_where_it_broke() {
+    assert_eq!(5, Json::parse("[1, 2").unwrap_err().at);
+    assert_eq!(7, Json::parse(r#"{"a": 1.5}"#).unwrap_err().at);
+    assert_eq!(5, Json::parse("true false").unwrap_err().at);
+    assert!(Json::parse(r#""abierta"#).is_err());
+    assert!(Json::parse(r#"{"a" 1}"#).is_err());
+    assert!(Json::parse("").is_err());
+    assert!(Json::parse("nulo").is_err());
+  }
+
+  #[test]
+  fn limits_nesting() {
+    let deep = |depth: usize| format!("{}{}", "[".repeat(depth), "]".repeat(depth));
+
+    assert!(Json::parse(&deep(MAX_DEPTH)).is_ok());
+    assert_eq!(MAX_DEPTH, Json::parse(&deep(MAX_DEPTH + 1)).unwrap_err().at);
+    assert!(Json::parse(&r#"{"a":"#.repeat(MAX_DEPTH + 1)).is_err());
+    // Lo que cabe en un cuerpo de la API no tumba al parser
+    assert!(Json::parse(&"[".repeat(64 * 1024)).is_err());
+  }
+}
@@ -0,0 +1,399 @@
+/* -------------------- Cocina -------------------- */
+/*
+  Cada platillo de un "Ticket" va a la estacion que lo prepara. Cada
+  estacion hace un platillo a la vez, en el orden de su fila, y cuando
+  termina todos los platillos de una orden la orden esta lista.
+
+  Como en "7_modules/src/modules.rs", "fix_incorrect_order" vuelve a
+  cocinar la orden con "cook_order"; lo que se vuelve a hacer pasa antes
+  que lo demas en la fila, pero no interrumpe el platillo que ya se esta
+  preparando.
+
+  La cocina tiene su propio reloj simulado ("Clock"): nada avanza hasta
+  que se llama a "advance".
+
+  Cocinar gasta del inventario ("back_of_house/inventory.rs"); un ticket
+  para el que no alcanza no entra a la cocina.
+*/
+
+pub mod inventory;
+
+use std::collections::{HashMap, VecDeque};
+use std::error::Error;
+use std::fmt;
+
+use crate::clock::Clock;
+use crate::front_of_house::serving::Ticket;
+use inventory::{Alert, Inventory, InventoryError};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Station {
+  Grill,
+  Fryer,
+  Salad,
+}
+
+impl Station {
+  pub const ALL: [Station; 3] = [Station::Grill, Station::Fryer, Station::Salad];
+}
+
+impl fmt::Display for Station {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let name = match self {
+      Station::Grill => "parrilla",
+      Station::Fryer => "freidora",
+      Station::Salad => "ensaladas",
+    };
+    write!(f, "{}", name)
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KitchenError {
+  EmptyTicket,
+  UnknownItem(String),
+  Inventory(InventoryError),
+}
+
+impl fmt::Display for KitchenError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      KitchenError::EmptyTicket => write!(f, "el ticket no tiene platillos"),
+      KitchenError::UnknownItem(name) => write!(f, "ninguna estacion prepara {}", name),
+      KitchenError::Inventory(e) => write!(f, "{}", e),
+    }
+  }
+}
+
+impl Error for KitchenError {}
+
+// Un platillo en la fila de una estacion
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Job {
+  pub order: u32,
+  pub table: u32,
+  pub item: String,
+  pub prep: u32,
+  pub refire: bool,
+}
+
+// Una orden que ya salio completa de la cocina
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Ready {
+  pub order: u32,
+  pub table: u32,
+  pub at: u32,
+}
+
+#[derive(Default)]
+struct Line {
+  // El platillo que se esta preparando y cuando termina
+  cooking: Option<(Job, u32)>,
+  queue: VecDeque<Job>,
+}
+
+impl Line {
+  // Los que se vuelven a hacer van despues de los otros que se vuelven a
+  // hacer, pero antes que todo lo demas
+  fn push(&mut self, job: Job, now: u32) {
+    if job.refire {
+      let position = self.queue.iter().take_while(|queued| queued.refire).count();
+      self.queue.insert(position, job);
+    } else {
+      self.queue.push_back(job);
+    }
+    self.start_next(now);
+  }
+
+  fn start_next(&mut self, now: u32) {
+    if self.cooking.is_none() {
+      if let Some(job) = self.queue.pop_front() {
+        let done = now + job.prep;
+        self.cooking = Some((job, done));
+      }
+    }
+  }
+
+  // Cada platillo con el minuto en que termina, si nada mas llega
+  fn schedule(&self, now: u32) -> Vec<(&Job, u32)> {
+    let mut schedule = Vec::new();
+    let mut free = now;
+
+    if let Some((job, done)) = &self.cooking {
+      schedule.push((job, *done));
+      free = *done;
+    }
+    for job in &self.queue {
+      free += job.prep;
+      schedule.push((job, free));
+    }
+
+    schedule
+  }
+}
+
+pub struct Kitchen {
+  clock: Clock,
+  routes: HashMap<String, (Station, u32)>,
+  lines: HashMap<Station, Line>,
+  // Platillos que le faltan a cada orden
+  pending: HashMap<u32, usize>,
+  inventory: Inventory,
+  // Los avisos del inventario que nadie ha leido
+  alerts: Vec<Alert>,
+}
+
+impl Default for Kitchen {
+  fn default() -> Kitchen {
+    Kitchen::new()
+  }
+}
+
+impl Kitchen {
+  pub fn new() -> Kitchen {
+    Kitchen {
+      clock: Clock::new(),
+      routes: HashMap::new(),
+      lines: Station::ALL.iter().map(|&station| (station, Line::default())).collect(),
+      pending: HashMap::new(),
+      inventory: Inventory::new(),
+      alerts: Vec::new(),
+    }
+  }
+
+  pub fn inventory(&self) -> &Inventory {
+    &self.inventory
+  }
+
+  pub fn inventory_mut(&mut self) -> &mut Inventory {
+    &mut self.inventory
+  }
+
+  // Los avisos desde la ultima vez que se pidieron
+  pub fn take_alerts(&mut self) -> Vec<Alert> {
+    self.alerts.drain(..).collect()
+  }
+
+  pub fn now(&self) -> u32 {
+    self.clock.now()
+  }
+
+  // Que estacion prepara el platillo y en cuantos minutos
+  pub fn route(&mut self, item: &str, station: Station, prep: u32) {
+    self.routes.insert(item.to_string(), (station, prep));
+  }
+
+  pub fn cook_order(&mut self, ticket: &Ticket) -> Result<u32, KitchenError> {
+    self.receive(ticket, false)
+  }
+
+  // La orden salio mal: se vuelve a hacer completa y con prioridad
+  pub fn fix_incorrect_order(&mut self, ticket: &Ticket) -> Result<u32, KitchenError> {
+    self.receive(ticket, true)
+  }
+
+  // La fila de la estacion, empezando por lo que se esta preparando
+  pub fn queue(&self, station: Station) -> Vec<&Job> {
+    self.lines[&station].schedule(self.now()).into_iter().map(|(job, _)| job).collect()
+  }
+
+  // Minutos hasta que la estacion se desocupa
+  pub fn backlog(&self, station: Station) -> u32 {
+    let now = self.now();
+    self.lines[&station].schedule(now).last().map_or(0, |&(_, done)| done - now)
+  }
+
+  // Cuando termina el siguiente platillo, None si la cocina esta parada
+  pub fn next_done(&self) -> Option<u32> {
+    self.lines.values().filter_map(|line| line.cooking.as_ref().map(|&(_, done)| done)).min()
+  }
+
+  // Cuando estara lista la orden si no llega nada con prioridad
+  pub fn estimate(&self, order: u32) -> Option<u32> {
+    self
+      .lines
+      .values()
+      .flat_map(|line| line.schedule(self.now()))
+      .filter(|(job, _)| job.order == order)
+      .map(|(_, done)| done)
+      .max()
+  }
+
+  // Avanza el reloj y devuelve las ordenes que se completaron, en el
+  // orden en que quedaron listas
+  pub fn advance(&mut self, minutes: u32) -> Vec<Ready> {
+    let until = self.clock.advance(minutes);
+    let mut finished = Vec::new();
+
+    for &station in Station::ALL.iter() {
+      let line = self.lines.get_mut(&station).expect("every station has a line");
+
+      while let Some((_, done)) = &line.cooking {
+        let done = *done;
+        if done > until {
+          break;
+        }
+        if let Some((job, _)) = line.cooking.take() {
+          finished.push((done, job));
+        }
+        line.start_next(done);
+      }
+    }
+
+    finished.sort_by_key(|&(done, ref job)| (done, job.order));
+
+    let mut ready = Vec::new();
+    for (done, job) in finished {
+      let left = self.pending.entry(job.order).or_insert(1);
+      *left -= 1;
+      if *left == 0 {
+        self.pending.remove(&job.order);
+        ready.push(Ready { order: job.order, table: job.table, at: done });
+      }
+    }
+
+    ready
+  }
+
+  fn receive(&mut self, ticket: &Ticket, refire: bool) -> Result<u32, KitchenError> {
+    if ticket.items.is_empty() {
+      return Err(KitchenError::EmptyTicket);
+    }
+
+    // Nada entra a la cocina si falta alguna ruta
+    let mut jobs = Vec::new();
+    for item in &ticket.items {
+      let &(station, prep) = self
+        .routes
+        .get(item)
+        .ok_or_else(|| KitchenError::UnknownItem(item.clone()))?;
+      let job = Job { order: ticket.order, table: ticket.table, item: item.clone(), prep, refire };
+      jobs.push((station, job));
+    }
+
+    let alerts = self.inventory.consume(&ticket.items).map_err(KitchenError::Inventory)?;
+    self.alerts.extend(alerts);
+
+    let now = self.now();
+    *self.pending.entry(ticket.order).or_insert(0) += jobs.len();
+    for (station, job) in jobs {
+      self.lines.get_mut(&station).expect("every station has a line").push(job, now);
+    }
+
+    Ok(self.estimate(ticket.order).unwrap_or(now))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::money::Money;
+  use inventory::Ingredient;
+
+  fn kitchen() -> Kitchen {
+    let mut kitchen = Kitchen::new();
+    kitchen.route("Hamburguesa", Station::Grill, 10);
+    kitchen.route("Papas", Station::Fryer, 4);
+    kitchen.route("Ensalada", Station::Salad, 3);
+    kitchen
+  }
+
+  fn ticket(order: u32, items: &[&str]) -> Ticket {
+    Ticket { order, table: order * 10, items: items.iter().map(|item| item.to_string()).collect() }
+  }
+
+  #[test]
+  fn routes_items_to_their_stations() {
+    let mut kitchen = kitchen();
+    assert_eq!(Ok(10), kitchen.cook_order(&ticket(1, &["Hamburguesa", "Papas", "Ensalada"])));
+
+    assert_eq!(1, kitchen.queue(Station::Grill).len());
+    assert_eq!("Papas", kitchen.queue(Station::Fryer)[0].item);
+    assert_eq!(3, kitchen.backlog(Station::Salad));
+  }
+
+  #[test]
+  fn rejects_unknown_items() {
+    let mut kitchen = kitchen();
+    assert_eq!(
+      Err(KitchenError::UnknownItem("Pizza".to_string())),
+      kitchen.cook_order(&ticket(1, &["Papas", "Pizza"]))
+    );
+    assert_eq!(Err(KitchenError::EmptyTicket), kitchen.cook_order(&ticket(1, &[])));
+    // Ni las papas entraron
+    assert!(kitchen.queue(Station::Fryer).is_empty());
+  }
+
+  #[test]
+  fn stations_work_one_item_at_a_time() {
+    let mut kitchen = kitchen();
+    kitchen.cook_order(&ticket(1, &["Hamburguesa"])).unwrap();
+    assert_eq!(Ok(20), kitchen.cook_order(&ticket(2, &["Hamburguesa", "Ensalada"])));
+    assert_eq!(Ok(30), kitchen.cook_order(&ticket(3, &["Papas", "Papas", "Hamburguesa"])));
+    assert_eq!(30, kitchen.backlog(Station::Grill));
+    assert_eq!(8, kitchen.backlog(Station::Fryer));
+
+    assert!(kitchen.advance(9).is_empty());
+    assert_eq!(vec![Ready { order: 1, table: 10, at: 10 }], kitchen.advance(1));
+    assert_eq!(vec![Ready { order: 2, table: 20, at: 20 }], kitchen.advance(15));
+    assert_eq!(vec![Ready { order: 3, table: 30, at: 30 }], kitchen.advance(100));
+    assert_eq!(125, kitchen.now());
+    assert_eq!(0, kitchen.backlog(Station::Grill));
+  }
+
+  #[test]
+  fn refires_go_first() {
+    let mut kitchen = kitchen();
+    kitchen.cook_order(&ticket(1, &["Hamburguesa"])).unwrap();
+    kitchen.cook_order(&ticket(2, &["Hamburguesa"])).unwrap();
+    kitchen.cook_order(&ticket(3, &["Hamburguesa"])).unwrap();
+    kitchen.advance(2);
+
+    // La 1 ya esta en la parrilla y no se interrumpe
+    assert_eq!(Ok(20), kitchen.fix_incorrect_order(&ticket(4, &["Hamburguesa"])));
+    assert_eq!(Ok(30), kitchen.fix_incorrect_order(&ticket(5, &["Hamburguesa"])));
+
+    let orders: Vec<u32> = kitchen.queue(Station::Grill).iter().map(|job| job.order).collect();
+    assert_eq!(vec![1, 4, 5, 2, 3], orders);
+    assert_eq!(Some(50), kitchen.estimate(3));
+
+    let ready: Vec<u32> = kitchen.advance(48).iter().map(|ready| ready.order).collect();
+    assert_eq!(vec![1, 4, 5, 2, 3], ready);
+  }
+
+  #[test]
+  fn an_order_is_ready_when_every_station_is_done() {
+    let mut kitchen = kitchen();
+    kitchen.cook_order(&ticket(1, &["Papas", "Ensalada"])).unwrap();
+    kitchen.cook_order(&ticket(2, &["Ensalada"])).unwrap();
+
+    assert_eq!(Some(3), kitchen.next_done());
+
+    assert_eq!(vec![Ready { order: 1, table: 10, at: 4 }, Ready { order: 2, table: 20, at: 6 }], kitchen.advance(6));
+    assert_eq!(None, kitchen.estimate(1));
+    assert_eq!(None, kitchen.next_done());
+  }
+
+  #[test]
+  fn cooking_uses_the_inventory() {
+    let mut kitchen = kitchen();
+    let inventory = kitchen.inventory_mut();
+    inventory.add(Ingredient::new("papa", 500, Money::from_cents(2_00), 1000, 200));
+    inventory.recipe("Papas", &[("papa", 200)]).unwrap();
+
+    kitchen.cook_order(&ticket(1, &["Papas"])).unwrap();
+    assert!(kitchen.take_alerts().is_empty());
+
+    kitchen.cook_order(&ticket(2, &["Papas", "Hamburguesa"])).unwrap();
+    assert_eq!(
+      vec![Alert::LowStock { ingredient: "papa".to_string(), left: 100 }, Alert::EightySixed("Papas".to_string())],
+      kitchen.take_alerts()
+    );
+    assert!(kitchen.take_alerts().is_empty());
+
+    let error = kitchen.cook_order(&ticket(3, &["Hamburguesa", "Papas"])).unwrap_err();
+    assert_eq!("no alcanza papa para Papas", error.to_string());
+    // La hamburguesa tampoco entro
+    assert_eq!(1, kitchen.queue(Station::Grill).len());
+  }
+}
@@ -0,0 +1,346 @@
+/* -------------------- Inventario -------------------- */
+/*
+  Cada platillo tiene su receta: cuanto usa de cada ingrediente, en la
+  unidad del ingrediente (gramos, piezas, mililitros...). Cocinar un
+  ticket descuenta todo o nada: si falta algo para un platillo no se
+  descuenta ninguno.
+
+  Un platillo sin receta no gasta nada del inventario (el cafe de la
+  maquina, por ejemplo). Un platillo con receta que ya no alcanza queda
+  "86" (fuera del menu) hasta que se surte de nuevo.
+*/
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+
+use crate::menu::Menu;
+use crate::money::Money;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ingredient {
+  pub name: String,
+  pub stock: u32,
+  // Lo que cuestan "per" unidades
+  pub cost: Money,
+  pub per: u32,
+  // Se avisa cuando quedan "threshold" unidades o menos
+  pub threshold: u32,
+}
+
+impl Ingredient {
+  pub fn new(name: &str, stock: u32, cost: Money, per: u32, threshold: u32) -> Ingredient {
+    Ingredient { name: name.to_string(), stock, cost, per, threshold }
+  }
+
+  // El costo de "quantity" unidades, redondeado al centavo
+  pub fn cost_of(&self, quantity: u32) -> Money {
+    let exact = i128::from(self.cost.cents()) * i128::from(quantity);
+    let per = i128::from(self.per.max(1));
+
+    Money::from_cents(((2 * exact + per) / (2 * per)) as i64)
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Alert {
+  // El ingrediente bajo de su minimo
+  LowStock { ingredient: String, left: u32 },
+  // El platillo ya no se puede hacer
+  EightySixed(String),
+}
+
+impl fmt::Display for Alert {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      Alert::LowStock { ingredient, left } => write!(f, "quedan {} de {}", left, ingredient),
+      Alert::EightySixed(dish) => write!(f, "86 {}", dish),
+    }
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InventoryError {
+  UnknownIngredient(String),
+  OutOfStock { dish: String, ingredient: String },
+}
+
+impl fmt::Display for InventoryError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      InventoryError::UnknownIngredient(name) => write!(f, "no existe el ingrediente {}", name),
+      InventoryError::OutOfStock { dish, ingredient } => {
+        write!(f, "no alcanza {} para {}", ingredient, dish)
+      }
+    }
+  }
+}
+
+impl Error for InventoryError {}
+
+// Un renglon del reporte de costo de alimentos
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FoodCost {
+  pub dish: String,
+  pub cost: Money,
+  pub price: Money,
+  // En puntos base, costo / precio
+  pub percent: u32,
+}
+
+impl fmt::Display for FoodCost {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    // El porcentaje con un decimal
+    let tenths = (self.percent + 5) / 10;
+    write!(
+      f,
+      "{:<20} {:>9} {:>9} {:>5}.{}%",
+      self.dish,
+      self.cost.to_string(),
+      self.price.to_string(),
+      tenths / 10,
+      tenths % 10
+    )
+  }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Inventory {
+  ingredients: Vec<Ingredient>,
+  recipes: HashMap<String, Vec<(String, u32)>>,
+}
+
+impl Inventory {
+  pub fn new() -> Inventory {
+    Inventory::default()
+  }
+
+  pub fn add(&mut self, ingredient: Ingredient) {
+    match self.ingredients.iter_mut().find(|i| i.name == ingredient.name) {
+      Some(existing) => *existing = ingredient,
+      None => self.ingredients.push(ingredient),
+    }
+  }
+
+  pub fn ingredient(&self, name: &str) -> Option<&Ingredient> {
+    self.ingredients.iter().find(|ingredient| ingredient.name == name)
+  }
+
+  pub fn recipe(&mut self, dish: &str, quantities: &[(&str, u32)]) -> Result<(), InventoryError> {
+    if let Some(&(name, _)) = quantities.iter().find(|(name, _)| self.ingredient(name).is_none()) {
+      return Err(InventoryError::UnknownIngredient(name.to_string()));
+    }
+
+    let recipe = quantities.iter().map(|&(name, quantity)| (name.to_string(), quantity)).collect();
+    self.recipes.insert(dish.to_string(), recipe);
+    Ok(())
+  }
+
+  pub fn restock(&mut self, name: &str, quantity: u32) -> Result<(), InventoryError> {
+    let ingredient = self
+      .ingredients
+      .iter_mut()
+      .find(|ingredient| ingredient.name == name)
+      .ok_or_else(|| InventoryError::UnknownIngredient(name.to_string()))?;
+
+    ingredient.stock += quantity;
+    Ok(())
+  }
+
+  pub fn can_make(&self, dish: &str) -> bool {
+    self.check(&[dish]).is_ok()
+  }
+
+  // Si alcanza para todos juntos, como en un mismo ticket
+  pub fn can_make_all<S: AsRef<str>>(&self, dishes: &[S]) -> bool {
+    let dishes: Vec<&str> = dishes.iter().map(AsRef::as_ref).collect();
+    self.check(&dishes).is_ok()
+  }
+
+  // Los platillos con receta que ya no alcanzan, en orden alfabetico
+  pub fn eighty_sixed(&self) -> Vec<&str> {
+    let mut dishes: Vec<&str> = self
+      .recipes
+      .keys()
+      .map(String::as_str)
+      .filter(|dish| !self.can_make(dish))
+      .collect();
+    dishes.sort_unstable();
+    dishes
+  }
+
+  // Descuenta lo de todos los platillos y avisa lo que cambio: los
+  // ingredientes que bajaron de su minimo y los platillos que se acabaron
+  pub fn consume<S: AsRef<str>>(&mut self, dishes: &[S]) -> Result<Vec<Alert>, InventoryError> {
+    let dishes: Vec<&str> = dishes.iter().map(AsRef::as_ref).collect();
+    let needed = self.check(&dishes)?;
+    let before = self.eighty_sixed().into_iter().map(str::to_string).collect::<Vec<_>>();
+
+    let mut alerts = Vec::new();
+    for ingredient in &mut self.ingredients {
+      let quantity = match needed.get(&ingredient.name) {
+        Some(&quantity) if quantity > 0 => quantity,
+        _ => continue,
+      };
+
+      let was_low = ingredient.stock <= ingredient.threshold;
+      ingredient.stock -= quantity;
+      if !was_low && ingredient.stock <= ingredient.threshold {
+        alerts.push(Alert::LowStock { ingredient: ingredient.name.clone(), left: ingredient.stock });
+      }
+    }
+
+    for dish in self.eighty_sixed() {
+      if !before.iter().any(|before| before == dish) {
+        alerts.push(Alert::EightySixed(dish.to_string()));
+      }
+    }
+
+    Ok(alerts)
+  }
+
+  // El costo de la receta contra el precio del menu, de cada platillo del
+  // menu que tiene receta
+  pub fn food_costs(&self, menu: &Menu) -> Vec<FoodCost> {
+    menu
+      .items()
+      .iter()
+      .filter_map(|item| {
+        let recipe = self.recipes.get(&item.name)?;
+        let cost: Money = recipe
+          .iter()
+          .filter_map(|(name, quantity)| self.ingredient(name).map(|ingredient| ingredient.cost_of(*quantity)))
+          .sum();
+        let percent = match item.price.cents() {
+          price if price > 0 => ((cost.cents() * 10_000 * 2 + price) / (2 * price)) as u32,
+          _ => 0,
+        };
+
+        Some(FoodCost { dish: item.name.clone(), cost, price: item.price, percent })
+      })
+      .collect()
+  }
+
+  // Lo que usan todos los platillos juntos, o el primero que ya no alcanza
+  fn check(&self, dishes: &[&str]) -> Result<HashMap<String, u32>, InventoryError> {
+    let mut needed: HashMap<String, u32> = HashMap::new();
+
+    for dish in dishes {
+      for (name, quantity) in self.recipes.get(*dish).into_iter().flatten() {
+        let total = needed.entry(name.clone()).or_insert(0);
+        *total += quantity;
+
+        let stock = self.ingredient(name).map_or(0, |ingredient| ingredient.stock);
+        if *total > stock {
+          return Err(InventoryError::OutOfStock { dish: dish.to_string(), ingredient: name.clone() });
+        }
+      }
+    }
+
+    Ok(needed)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::menu::MenuItem;
+
+  fn money(cents: i64) -> Money {
+    Money::from_cents(cents)
+  }
+
+  // Carne a $12 el kilo, pan a $0.40 la pieza, queso a $10 el kilo
+  fn inventory() -> Inventory {
+    let mut inventory = Inventory::new();
+    inventory.add(Ingredient::new("carne", 600, money(12_00), 1000, 250));
+    inventory.add(Ingredient::new("pan", 10, money(40), 1, 2));
+    inventory.add(Ingredient::new("queso", 100, money(10_00), 1000, 30));
+
+    inventory.recipe("Hamburguesa", &[("carne", 150), ("pan", 1), ("queso", 20)]).unwrap();
+    inventory.recipe("Sandwich", &[("pan", 2), ("queso", 25)]).unwrap();
+    inventory
+  }
+
+  #[test]
+  fn consumes_whole_tickets() {
+    let mut inventory = inventory();
+
+    assert_eq!(Ok(Vec::new()), inventory.consume(&["Hamburguesa", "Cafe"]));
+    assert_eq!(450, inventory.ingredient("carne").unwrap().stock);
+    assert_eq!(9, inventory.ingredient("pan").unwrap().stock);
+    assert_eq!(80, inventory.ingredient("queso").unwrap().stock);
+
+    // Tres sandwiches llevan 75 de queso, cuatro 100
+    assert_eq!(
+      Err(InventoryError::OutOfStock { dish: "Sandwich".to_string(), ingredient: "queso".to_string() }),
+      inventory.consume(&["Sandwich", "Sandwich", "Sandwich", "Sandwich"])
+    );
+    assert_eq!(80, inventory.ingredient("queso").unwrap().stock);
+  }
+
+  #[test]
+  fn alerts_once_when_low() {
+    let mut inventory = inventory();
+    inventory.consume(&["Hamburguesa", "Hamburguesa"]).unwrap();
+
+    let alerts = inventory.consume(&["Hamburguesa"]).unwrap();
+    assert_eq!(vec![Alert::LowStock { ingredient: "carne".to_string(), left: 150 }], alerts);
+    assert_eq!("quedan 150 de carne", alerts[0].to_string());
+
+    // La carne ya estaba baja; el queso baja de 30 y ya no alcanza ni
+    // para hamburguesas ni para sandwiches
+    let alerts = inventory.consume(&["Hamburguesa"]).unwrap();
+    assert_eq!(
+      vec![
+        Alert::LowStock { ingredient: "queso".to_string(), left: 20 },
+        Alert::EightySixed("Hamburguesa".to_string()),
+        Alert::EightySixed("Sandwich".to_string())
+      ],
+      alerts
+    );
+  }
+
+  #[test]
+  fn eighty_sixes_and_restocks() {
+    let mut inventory = inventory();
+    assert!(inventory.eighty_sixed().is_empty());
+
+    let alerts = inventory.consume(&["Sandwich"; 4]).unwrap();
+    assert!(alerts.contains(&Alert::EightySixed("Hamburguesa".to_string())));
+    assert!(alerts.contains(&Alert::EightySixed("Sandwich".to_string())));
+    assert_eq!(vec!["Hamburguesa", "Sandwich"], inventory.eighty_sixed());
+    assert!(inventory.can_make("Cafe"));
+
+    inventory.restock("pan", 2).unwrap();
+    inventory.restock("queso", 1000).unwrap();
+    assert!(inventory.eighty_sixed().is_empty());
+    assert_eq!(Err(InventoryError::UnknownIngredient("sal".to_string())), inventory.restock("sal", 1));
+    assert_eq!(
+      Err(InventoryError::UnknownIngredient("papa".to_string())),
+      inventory.recipe("Papas", &[("pan", 1), ("papa", 200)])
+    );
+  }
+
+  #[test]
+  fn food_cost_per_dish() {
+    let inventory = inventory();
+    let menu = Menu::new(vec![MenuItem::new("Hamburguesa", 10_50), MenuItem::new("Sandwich", 6_00), MenuItem::new("Cafe", 2_50)]);
+
+    // 150 g de carne $1.80, pan $0.40, 20 g de queso $0.20
+    let costs = inventory.food_costs(&menu);
+    assert_eq!(2, costs.len());
+    assert_eq!(FoodCost { dish: "Hamburguesa".to_string(), cost: money(2_40), price: money(10_50), percent: 2286 }, costs[0]);
+    assert_eq!(money(1_05), costs[1].cost);
+    assert_eq!(1750, costs[1].percent);
+    assert_eq!("Hamburguesa              $2.40    $10.50    22.9%", costs[0].to_string());
+  }
+
+  #[test]
+  fn rounds_ingredient_costs() {
+    let salt = Ingredient::new("sal", 1000, money(25), 1000, 0);
+    assert_eq!(money(0), salt.cost_of(19));
+    assert_eq!(money(1), salt.cost_of(20));
+    assert_eq!(money(25), salt.cost_of(1000));
+  }
+}
@@ -0,0 +1,25 @@
+/* -------------------- Reloj simulado -------------------- */
+/*
+  Minutos desde que abre el restaurante. Nadie lo mueve mas que el que
+  lo tiene, asi una prueba sabe exactamente que hora es.
+*/
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Clock {
+  now: u32,
+}
+
+impl Clock {
+  pub fn new() -> Clock {
+    Clock { now: 0 }
+  }
+
+  pub fn now(&self) -> u32 {
+    self.now
+  }
+
+  pub fn advance(&mut self, minutes: u32) -> u32 {
+    self.now += minutes;
+    self.now
+  }
+}
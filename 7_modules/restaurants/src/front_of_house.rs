@@ -0,0 +1,8 @@
+/*
+  Como en "7_modules/src/files_modules.rs", cada modulo hijo vive en su
+  propio archivo dentro de "src/front_of_house/".
+*/
+
+pub mod billing;
+pub mod hosting;
+pub mod serving;
@@ -0,0 +1,447 @@
+/* -------------------- La cuenta -------------------- */
+/*
+  Como se calcula, en este orden:
+
+    subtotal   = la suma de los platillos
+    descuentos = cupones y descuentos sobre el subtotal (nunca mas que el)
+    impuesto   = la tasa sobre (subtotal - descuentos)
+    propina    = sobre el subtotal, antes de descuentos e impuestos
+    total      = subtotal - descuentos + impuesto + propina
+
+  Cada cantidad se redondea al centavo una vez y el total es la suma de
+  esas cantidades ya redondeadas, asi el ticket impreso siempre cuadra.
+  Al dividir la cuenta, cada parte de cada cantidad se reparte con
+  "Money::allocate", y las cuentas de todos suman el total exacto.
+*/
+
+use std::error::Error;
+use std::fmt;
+
+use crate::money::Money;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Discount {
+  // En puntos base
+  Percent(u32),
+  Amount(Money),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Coupon {
+  pub code: String,
+  pub discount: Discount,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tip {
+  // En puntos base
+  Percent(u32),
+  Amount(Money),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tender {
+  Cash(Money),
+  Card(Money),
+}
+
+impl Tender {
+  pub fn amount(self) -> Money {
+    match self {
+      Tender::Cash(amount) | Tender::Card(amount) => amount,
+    }
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BillError {
+  CouponUsed(String),
+  NotPositive(Money),
+  // Un descuento o una propina negativos cambiarian el total al reves
+  Negative(Money),
+  // Solo el efectivo puede pasarse del saldo, y se devuelve cambio
+  Overpaid { balance: Money, tendered: Money },
+  AlreadyPaid,
+  NoGuests,
+  // La linea de la cuenta no tiene quien la pague
+  Unassigned(usize),
+  UnknownGuest(usize),
+}
+
+impl fmt::Display for BillError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      BillError::CouponUsed(code) => write!(f, "el cupon {} ya se uso en esta cuenta", code),
+      BillError::NotPositive(amount) => write!(f, "no se puede pagar {}", amount),
+      BillError::Negative(amount) => write!(f, "{} es negativo", amount),
+      BillError::Overpaid { balance, tendered } => {
+        write!(f, "con tarjeta no se puede pagar {} de una cuenta de {}", tendered, balance)
+      }
+      BillError::AlreadyPaid => write!(f, "la cuenta ya esta pagada"),
+      BillError::NoGuests => write!(f, "la cuenta se divide entre nadie"),
+      BillError::Unassigned(line) => write!(f, "nadie paga la linea {}", line + 1),
+      BillError::UnknownGuest(guest) => write!(f, "no existe el comensal {}", guest + 1),
+    }
+  }
+}
+
+impl Error for BillError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bill {
+  lines: Vec<(String, Money)>,
+  // En puntos base
+  tax_rate: u32,
+  discounts: Vec<Discount>,
+  coupons: Vec<String>,
+  tip: Option<Tip>,
+  tenders: Vec<Tender>,
+}
+
+// Una parte de la cuenta: la de todos o la de un comensal
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Totals {
+  pub subtotal: Money,
+  pub discount: Money,
+  pub tax: Money,
+  pub tip: Money,
+  pub total: Money,
+}
+
+impl Bill {
+  pub fn new(lines: Vec<(String, Money)>, tax_rate: u32) -> Bill {
+    Bill {
+      lines,
+      tax_rate,
+      discounts: Vec::new(),
+      coupons: Vec::new(),
+      tip: None,
+      tenders: Vec::new(),
+    }
+  }
+
+  pub fn lines(&self) -> &[(String, Money)] {
+    &self.lines
+  }
+
+  pub fn discount(&mut self, discount: Discount) -> Result<(), BillError> {
+    if let Discount::Amount(amount) = discount {
+      not_negative(amount)?;
+    }
+
+    self.discounts.push(discount);
+    Ok(())
+  }
+
+  // Cada cupon una vez por cuenta
+  pub fn redeem(&mut self, coupon: &Coupon) -> Result<(), BillError> {
+    if self.coupons.contains(&coupon.code) {
+      return Err(BillError::CouponUsed(coupon.code.clone()));
+    }
+    if let Discount::Amount(amount) = coupon.discount {
+      not_negative(amount)?;
+    }
+
+    self.coupons.push(coupon.code.clone());
+    self.discounts.push(coupon.discount);
+    Ok(())
+  }
+
+  pub fn tip(&mut self, tip: Tip) -> Result<(), BillError> {
+    if let Tip::Amount(amount) = tip {
+      not_negative(amount)?;
+    }
+
+    self.tip = Some(tip);
+    Ok(())
+  }
+
+  pub fn totals(&self) -> Totals {
+    let subtotal: Money = self.lines.iter().map(|&(_, price)| price).sum();
+
+    let discount: Money = self
+      .discounts
+      .iter()
+      .map(|&discount| match discount {
+        Discount::Percent(rate) => subtotal.percent(rate),
+        Discount::Amount(amount) => amount,
+      })
+      .sum();
+    let discount = discount.min(subtotal);
+
+    let tax = (subtotal - discount).percent(self.tax_rate);
+    let tip = match self.tip {
+      Some(Tip::Percent(rate)) => subtotal.percent(rate),
+      Some(Tip::Amount(amount)) => amount,
+      None => Money::ZERO,
+    };
+
+    Totals { subtotal, discount, tax, tip, total: subtotal - discount + tax + tip }
+  }
+
+  pub fn paid(&self) -> Money {
+    self.tenders.iter().map(|tender| tender.amount()).sum()
+  }
+
+  // Lo que falta por pagar, nunca negativo
+  pub fn balance(&self) -> Money {
+    (self.totals().total - self.paid()).max(Money::ZERO)
+  }
+
+  pub fn is_settled(&self) -> bool {
+    self.balance() == Money::ZERO
+  }
+
+  // Registra un pago y devuelve el cambio. El efectivo que sobra se
+  // devuelve, la tarjeta se cobra justo.
+  pub fn pay(&mut self, tender: Tender) -> Result<Money, BillError> {
+    let balance = self.balance();
+    let tendered = tender.amount();
+
+    if balance == Money::ZERO {
+      return Err(BillError::AlreadyPaid);
+    }
+    if tendered <= Money::ZERO {
+      return Err(BillError::NotPositive(tendered));
+    }
+
+    match tender {
+      Tender::Cash(_) if tendered > balance => {
+        self.tenders.push(Tender::Cash(balance));
+        Ok(tendered - balance)
+      }
+      Tender::Card(_) if tendered > balance => Err(BillError::Overpaid { balance, tendered }),
+      _ => {
+        self.tenders.push(tender);
+        Ok(Money::ZERO)
+      }
+    }
+  }
+
+  // Partes iguales de todo
+  pub fn split_evenly(&self, guests: usize) -> Result<Vec<Totals>, BillError> {
+    self.split_by_weight(&vec![1; guests])
+  }
+
+  // "owners[line]" son los comensales que comparten esa linea; el precio
+  // se reparte entre ellos y cada quien paga descuento, impuesto y
+  // propina en proporcion a lo que consumio
+  pub fn split_by_items(&self, guests: usize, owners: &[Vec<usize>]) -> Result<Vec<Totals>, BillError> {
+    if guests == 0 {
+      return Err(BillError::NoGuests);
+    }
+
+    let mut subtotals = vec![Money::ZERO; guests];
+    for (line, &(_, price)) in self.lines.iter().enumerate() {
+      let sharing = match owners.get(line) {
+        Some(sharing) if !sharing.is_empty() => sharing,
+        _ => return Err(BillError::Unassigned(line)),
+      };
+      if let Some(&guest) = sharing.iter().find(|&&guest| guest >= guests) {
+        return Err(BillError::UnknownGuest(guest));
+      }
+
+      for (&guest, part) in sharing.iter().zip(price.split(sharing.len())) {
+        subtotals[guest] += part;
+      }
+    }
+
+    let weights: Vec<i64> = subtotals.iter().map(|subtotal| subtotal.cents()).collect();
+    Ok(self.shares(subtotals, &weights))
+  }
+
+  fn split_by_weight(&self, weights: &[i64]) -> Result<Vec<Totals>, BillError> {
+    if weights.is_empty() {
+      return Err(BillError::NoGuests);
+    }
+
+    let subtotals = self.totals().subtotal.allocate(weights);
+    Ok(self.shares(subtotals, weights))
+  }
+
+  fn shares(&self, subtotals: Vec<Money>, weights: &[i64]) -> Vec<Totals> {
+    let totals = self.totals();
+    let discounts = totals.discount.allocate(weights);
+    let tips = totals.tip.allocate(weights);
+
+    // El impuesto sigue a lo que cada quien paga despues del descuento
+    let taxable: Vec<i64> = subtotals.iter().zip(&discounts).map(|(&s, &d)| (s - d).cents()).collect();
+    let taxes = totals.tax.allocate(&taxable);
+
+    subtotals
+      .into_iter()
+      .enumerate()
+      .map(|(i, subtotal)| {
+        let (discount, tax, tip) = (discounts[i], taxes[i], tips[i]);
+        Totals { subtotal, discount, tax, tip, total: subtotal - discount + tax + tip }
+      })
+      .collect()
+  }
+}
+
+fn not_negative(amount: Money) -> Result<(), BillError> {
+  if amount < Money::ZERO {
+    return Err(BillError::Negative(amount));
+  }
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn money(cents: i64) -> Money {
+    Money::from_cents(cents)
+  }
+
+  fn bill(prices: &[i64], tax_rate: u32) -> Bill {
+    let lines = prices.iter().enumerate().map(|(i, &price)| (format!("Platillo {}", i + 1), money(price))).collect();
+    Bill::new(lines, tax_rate)
+  }
+
+  fn sum(shares: &[Totals]) -> Totals {
+    shares.iter().fold(Totals::default(), |sum, share| Totals {
+      subtotal: sum.subtotal + share.subtotal,
+      discount: sum.discount + share.discount,
+      tax: sum.tax + share.tax,
+      tip: sum.tip + share.tip,
+      total: sum.total + share.total,
+    })
+  }
+
+  #[test]
+  fn computes_the_total() {
+    let mut bill = bill(&[12_50, 3_99, 8_00], 1600);
+    bill.discount(Discount::Percent(1000)).unwrap();
+    bill.tip(Tip::Percent(1500)).unwrap();
+
+    // Subtotal 24.49, descuento 2.449 -> 2.45, impuesto 16% de 22.04 =
+    // 3.5264 -> 3.53, propina 15% de 24.49 = 3.6735 -> 3.67
+    let totals = bill.totals();
+    assert_eq!(money(24_49), totals.subtotal);
+    assert_eq!(money(2_45), totals.discount);
+    assert_eq!(money(3_53), totals.tax);
+    assert_eq!(money(3_67), totals.tip);
+    assert_eq!(money(29_24), totals.total);
+  }
+
+  #[test]
+  fn discounts_never_go_below_zero() {
+    let mut bill = bill(&[5_00], 1000);
+    bill.redeem(&Coupon { code: "PRIMERA".to_string(), discount: Discount::Amount(money(4_00)) }).unwrap();
+    bill.discount(Discount::Amount(money(3_00))).unwrap();
+
+    assert_eq!(money(5_00), bill.totals().discount);
+    assert_eq!(Money::ZERO, bill.totals().total);
+    assert!(bill.is_settled());
+  }
+
+  #[test]
+  fn coupons_only_once() {
+    let coupon = Coupon { code: "2X1".to_string(), discount: Discount::Percent(5000) };
+    let mut bill = bill(&[10_00], 0);
+
+    bill.redeem(&coupon).unwrap();
+    assert_eq!(Err(BillError::CouponUsed("2X1".to_string())), bill.redeem(&coupon));
+    assert_eq!(money(5_00), bill.totals().total);
+  }
+
+  #[test]
+  fn negative_discounts_and_tips_are_rejected() {
+    let mut bill = bill(&[10_00], 0);
+
+    assert_eq!(Err(BillError::Negative(money(-1_00))), bill.discount(Discount::Amount(money(-1_00))));
+    assert_eq!(Err(BillError::Negative(money(-50))), bill.tip(Tip::Amount(money(-50))));
+    let coupon = Coupon { code: "MAS".to_string(), discount: Discount::Amount(money(-2_00)) };
+    assert_eq!(Err(BillError::Negative(money(-2_00))), bill.redeem(&coupon));
+    assert_eq!(money(10_00), bill.totals().total);
+
+    // El cupon rechazado se puede volver a intentar bien
+    bill.redeem(&Coupon { discount: Discount::Amount(money(2_00)), ..coupon }).unwrap();
+    bill.tip(Tip::Amount(Money::ZERO)).unwrap();
+    assert_eq!(money(8_00), bill.totals().total);
+    assert_eq!("-$1.00 es negativo", BillError::Negative(money(-1_00)).to_string());
+  }
+
+  #[test]
+  fn several_tenders() {
+    let mut bill = bill(&[20_00, 15_00], 1600);
+    assert_eq!(money(40_60), bill.totals().total);
+
+    assert_eq!(Ok(Money::ZERO), bill.pay(Tender::Card(money(20_00))));
+    assert_eq!(money(20_60), bill.balance());
+    assert_eq!(
+      Err(BillError::Overpaid { balance: money(20_60), tendered: money(25_00) }),
+      bill.pay(Tender::Card(money(25_00)))
+    );
+    assert_eq!(Err(BillError::NotPositive(Money::ZERO)), bill.pay(Tender::Cash(Money::ZERO)));
+
+    assert_eq!(Ok(money(4_40)), bill.pay(Tender::Cash(money(25_00))));
+    assert!(bill.is_settled());
+    assert_eq!(money(40_60), bill.paid());
+    assert_eq!(Err(BillError::AlreadyPaid), bill.pay(Tender::Cash(money(1))));
+  }
+
+  #[test]
+  fn splits_evenly() {
+    let mut bill = bill(&[10_00], 1600);
+    bill.tip(Tip::Amount(money(1_00))).unwrap();
+
+    let shares = bill.split_evenly(3).unwrap();
+    let totals: Vec<i64> = shares.iter().map(|share| share.total.cents()).collect();
+    assert_eq!(vec![4_22, 4_19, 4_19], totals);
+    assert_eq!(bill.totals(), sum(&shares));
+    assert_eq!(Err(BillError::NoGuests), bill.split_evenly(0));
+  }
+
+  #[test]
+  fn splits_by_items() {
+    // Cada quien su plato y el postre a medias
+    let mut bill = bill(&[12_00, 8_00, 5_01], 1000);
+    bill.discount(Discount::Amount(money(2_00))).unwrap();
+    let shares = bill.split_by_items(2, &[vec![0], vec![1], vec![0, 1]]).unwrap();
+
+    assert_eq!(money(14_51), shares[0].subtotal);
+    assert_eq!(money(10_50), shares[1].subtotal);
+    assert_eq!(bill.totals(), sum(&shares));
+
+    assert_eq!(Err(BillError::Unassigned(2)), bill.split_by_items(2, &[vec![0], vec![1]]));
+    assert_eq!(Err(BillError::Unassigned(1)), bill.split_by_items(2, &[vec![0], vec![], vec![1]]));
+    assert_eq!(Err(BillError::UnknownGuest(2)), bill.split_by_items(2, &[vec![0], vec![2], vec![1]]));
+  }
+
+  #[test]
+  fn every_bill_reconciles_to_the_cent() {
+    let rates = [0, 1, 825, 1000, 1600, 3333];
+    let discounts = [None, Some(Discount::Percent(1500)), Some(Discount::Amount(money(3_33)))];
+    let tips = [None, Some(Tip::Percent(1800)), Some(Tip::Amount(money(1)))];
+
+    for first in (0..=3_000).step_by(7) {
+      for &rate in rates.iter() {
+        for &discount in discounts.iter() {
+          for &tip in tips.iter() {
+            let mut bill = bill(&[first, 1_01, 33], rate);
+            if let Some(discount) = discount {
+              bill.discount(discount).unwrap();
+            }
+            if let Some(tip) = tip {
+              bill.tip(tip).unwrap();
+            }
+
+            let totals = bill.totals();
+            let printed = totals.subtotal - totals.discount + totals.tax + totals.tip;
+            assert_eq!(printed, totals.total);
+
+            for guests in 1..=7 {
+              assert_eq!(totals, sum(&bill.split_evenly(guests).unwrap()), "{} at {}", first, rate);
+            }
+            let owners = [vec![0], vec![1, 2], vec![0, 1, 2]];
+            let shares = bill.split_by_items(3, &owners).unwrap();
+            assert_eq!(totals, sum(&shares), "{} at {} by items", first, rate);
+            assert!(shares.iter().all(|share| share.total >= Money::ZERO));
+          }
+        }
+      }
+    }
+  }
+}
@@ -0,0 +1,469 @@
+/* -------------------- Recepcion -------------------- */
+/*
+  El anfitrion ("Host") conoce las mesas, la lista de espera y las
+  reservaciones. El tiempo se cuenta en minutos desde que abre el
+  restaurante, asi el que llama decide que hora es y las pruebas no
+  dependen del reloj.
+
+  Las mesas se dan en el orden en que estan en el salon: dos mesas
+  seguidas en la lista estan juntas y se pueden unir para un grupo grande.
+*/
+
+use std::collections::VecDeque;
+use std::error::Error;
+use std::fmt;
+
+// Lo que se calcula que dura un grupo en la mesa
+pub const TURN_MINUTES: u32 = 45;
+// Una reservacion se puede sentar desde EARLY minutos antes de su hora y
+// se pierde GRACE minutos despues si nadie llega
+pub const EARLY_MINUTES: u32 = 15;
+pub const GRACE_MINUTES: u32 = 15;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Table {
+  pub number: u32,
+  pub seats: u32,
+}
+
+impl Table {
+  pub fn new(number: u32, seats: u32) -> Table {
+    Table { number, seats }
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Party {
+  pub name: String,
+  pub size: u32,
+  pub arrived: u32,
+  // La espera que se le dijo al llegar, en minutos
+  pub quoted: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reservation {
+  pub id: u32,
+  pub name: String,
+  pub size: u32,
+  pub at: u32,
+  pub checked_in: bool,
+}
+
+impl Reservation {
+  // Los minutos en los que se puede sentar
+  pub fn window(&self) -> (u32, u32) {
+    (self.at.saturating_sub(EARLY_MINUTES), self.at + GRACE_MINUTES)
+  }
+}
+
+// Un grupo sentado en una o varias mesas juntas
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Seating {
+  pub name: String,
+  pub size: u32,
+  pub tables: Vec<u32>,
+  pub since: u32,
+  // Cuanto espero desde que llego, cero para las reservaciones
+  pub waited: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HostingError {
+  EmptyParty,
+  TooLarge { size: u32, seats: u32 },
+  UnknownReservation(u32),
+  Expired(u32),
+  UnknownTable(u32),
+  TableFree(u32),
+}
+
+impl fmt::Display for HostingError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      HostingError::EmptyParty => write!(f, "el grupo no tiene personas"),
+      HostingError::TooLarge { size, seats } => {
+        write!(f, "un grupo de {} no cabe, el salon tiene {} lugares", size, seats)
+      }
+      HostingError::UnknownReservation(id) => write!(f, "no existe la reservacion {}", id),
+      HostingError::Expired(id) => write!(f, "la reservacion {} ya expiro", id),
+      HostingError::UnknownTable(number) => write!(f, "no existe la mesa {}", number),
+      HostingError::TableFree(number) => write!(f, "la mesa {} no esta ocupada", number),
+    }
+  }
+}
+
+impl Error for HostingError {}
+
+pub struct Host {
+  tables: Vec<Table>,
+  // Misma posicion que "tables"
+  seated: Vec<Option<usize>>,
+  seatings: Vec<Option<Seating>>,
+  waitlist: VecDeque<Party>,
+  reservations: Vec<Reservation>,
+  next_reservation: u32,
+}
+
+impl Host {
+  pub fn new(tables: Vec<Table>) -> Host {
+    let seated = vec![None; tables.len()];
+
+    Host {
+      tables,
+      seated,
+      seatings: Vec::new(),
+      waitlist: VecDeque::new(),
+      reservations: Vec::new(),
+      next_reservation: 1,
+    }
+  }
+
+  pub fn tables(&self) -> &[Table] {
+    &self.tables
+  }
+
+  pub fn waitlist(&self) -> impl Iterator<Item = &Party> {
+    self.waitlist.iter()
+  }
+
+  pub fn reservations(&self) -> &[Reservation] {
+    &self.reservations
+  }
+
+  pub fn seatings(&self) -> impl Iterator<Item = &Seating> {
+    self.seatings.iter().flatten()
+  }
+
+  // Quien esta en la mesa, None si esta libre
+  pub fn seating_at(&self, table: u32) -> Option<&Seating> {
+    let index = self.tables.iter().position(|t| t.number == table)?;
+    self.seated[index].and_then(|seating| self.seatings[seating].as_ref())
+  }
+
+  // Agrega al grupo al final de la fila y devuelve la espera estimada
+  pub fn add_to_waitlist(&mut self, name: &str, size: u32, now: u32) -> Result<u32, HostingError> {
+    self.check_size(size)?;
+
+    self.waitlist.push_back(Party { name: name.to_string(), size, arrived: now, quoted: 0 });
+
+    let quoted = self.quotes(now).last().copied().unwrap_or(0);
+    if let Some(party) = self.waitlist.back_mut() {
+      party.quoted = quoted;
+    }
+
+    Ok(quoted)
+  }
+
+  // El grupo se cansa de esperar y se va
+  pub fn leave_waitlist(&mut self, name: &str) -> Option<Party> {
+    let position = self.waitlist.iter().position(|party| party.name == name)?;
+    self.waitlist.remove(position)
+  }
+
+  // La espera estimada de cada grupo que falta por sentar, en el orden
+  // en que se van a sentar: primero las reservaciones que ya llegaron y
+  // despues la fila.
+  //
+  // Cada mesa ocupada se libera TURN_MINUTES despues de que se sentaron;
+  // cada grupo toma las mesas que se liberen antes.
+  pub fn quotes(&self, now: u32) -> Vec<u32> {
+    let mut free_at: Vec<u32> = self
+      .seated
+      .iter()
+      .map(|seating| match seating.and_then(|s| self.seatings[s].as_ref()) {
+        Some(seating) => now.max(seating.since + TURN_MINUTES),
+        None => now,
+      })
+      .collect();
+
+    self
+      .pending()
+      .map(|size| {
+        let best = blocks(&self.tables, size)
+          .into_iter()
+          .min_by_key(|&(start, end, seats)| (free_at[start..end].iter().max().copied(), seats, end - start));
+
+        match best {
+          Some((start, end, _)) => {
+            let seated = free_at[start..end].iter().copied().max().unwrap_or(now);
+            for free in &mut free_at[start..end] {
+              *free = seated + TURN_MINUTES;
+            }
+            seated - now
+          }
+          None => 0,
+        }
+      })
+      .collect()
+  }
+
+  pub fn reserve(&mut self, name: &str, size: u32, at: u32) -> Result<u32, HostingError> {
+    self.check_size(size)?;
+
+    let id = self.next_reservation;
+    self.next_reservation += 1;
+    self.reservations.push(Reservation { id, name: name.to_string(), size, at, checked_in: false });
+
+    Ok(id)
+  }
+
+  // La reservacion llego; pasa antes que la fila
+  pub fn check_in(&mut self, id: u32, now: u32) -> Result<(), HostingError> {
+    let reservation = self
+      .reservations
+      .iter_mut()
+      .find(|r| r.id == id)
+      .ok_or(HostingError::UnknownReservation(id))?;
+
+    if now > reservation.window().1 {
+      return Err(HostingError::Expired(id));
+    }
+
+    reservation.checked_in = true;
+    Ok(())
+  }
+
+  // Quita las reservaciones cuya ventana ya paso sin que llegaran
+  pub fn expire(&mut self, now: u32) -> Vec<Reservation> {
+    let (expired, kept) = self
+      .reservations
+      .drain(..)
+      .partition(|r| !r.checked_in && now > r.window().1);
+
+    self.reservations = kept;
+    expired
+  }
+
+  // Sienta al primero que quepa en las mesas libres: las reservaciones
+  // que ya llegaron y estan en su ventana, despues la fila. Un grupo
+  // chico puede pasar antes que uno grande que todavia no cabe.
+  pub fn seat_at_table(&mut self, now: u32) -> Option<Seating> {
+    let reservation = self.reservations.iter().enumerate().find_map(|(i, r)| {
+      let open = r.checked_in && now >= r.window().0;
+      self.free_block(r.size).filter(|_| open).map(|block| (i, block))
+    });
+
+    let (name, size, waited, (start, end)) = match reservation {
+      Some((i, block)) => {
+        let reservation = self.reservations.remove(i);
+        (reservation.name, reservation.size, 0, block)
+      }
+      None => {
+        let (i, block) = self
+          .waitlist
+          .iter()
+          .enumerate()
+          .find_map(|(i, party)| self.free_block(party.size).map(|block| (i, block)))?;
+        let party = self.waitlist.remove(i)?;
+        (party.name, party.size, now.saturating_sub(party.arrived), block)
+      }
+    };
+
+    let seating = Seating {
+      name,
+      size,
+      tables: self.tables[start..end].iter().map(|t| t.number).collect(),
+      since: now,
+      waited,
+    };
+
+    // Se reutiliza el primer lugar vacio para que la lista no crezca
+    let slot = match self.seatings.iter().position(Option::is_none) {
+      Some(slot) => slot,
+      None => {
+        self.seatings.push(None);
+        self.seatings.len() - 1
+      }
+    };
+    self.seatings[slot] = Some(seating.clone());
+    for seated in &mut self.seated[start..end] {
+      *seated = Some(slot);
+    }
+
+    Some(seating)
+  }
+
+  // El grupo de esa mesa se va y libera todas sus mesas
+  pub fn leave(&mut self, table: u32) -> Result<Seating, HostingError> {
+    let index = self
+      .tables
+      .iter()
+      .position(|t| t.number == table)
+      .ok_or(HostingError::UnknownTable(table))?;
+    let slot = self.seated[index].ok_or(HostingError::TableFree(table))?;
+
+    for seated in &mut self.seated {
+      if *seated == Some(slot) {
+        *seated = None;
+      }
+    }
+
+    self.seatings[slot].take().ok_or(HostingError::TableFree(table))
+  }
+
+  fn check_size(&self, size: u32) -> Result<(), HostingError> {
+    let seats = self.tables.iter().map(|t| t.seats).sum();
+
+    match size {
+      0 => Err(HostingError::EmptyParty),
+      _ if size > seats => Err(HostingError::TooLarge { size, seats }),
+      _ => Ok(()),
+    }
+  }
+
+  fn pending(&self) -> impl Iterator<Item = u32> + '_ {
+    let reservations = self.reservations.iter().filter(|r| r.checked_in).map(|r| r.size);
+    reservations.chain(self.waitlist.iter().map(|party| party.size))
+  }
+
+  // Las mesas libres y juntas que desperdician menos lugares
+  fn free_block(&self, size: u32) -> Option<(usize, usize)> {
+    blocks(&self.tables, size)
+      .into_iter()
+      .filter(|&(start, end, _)| self.seated[start..end].iter().all(Option::is_none))
+      .min_by_key(|&(start, end, seats)| (seats, end - start))
+      .map(|(start, end, _)| (start, end))
+  }
+}
+
+// Cada grupo de mesas seguidas (inicio, fin, lugares) donde cabe el
+// grupo sin que sobre una mesa en la orilla
+fn blocks(tables: &[Table], size: u32) -> Vec<(usize, usize, u32)> {
+  let mut blocks = Vec::new();
+
+  for start in 0..tables.len() {
+    let mut seats = 0;
+    for end in start + 1..=tables.len() {
+      seats += tables[end - 1].seats;
+      if seats >= size {
+        blocks.push((start, end, seats));
+        break;
+      }
+    }
+  }
+
+  blocks
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  // 1 y 2 para dos, 3 y 4 para cuatro, en ese orden en el salon
+  fn host() -> Host {
+    Host::new(vec![Table::new(1, 2), Table::new(2, 2), Table::new(3, 4), Table::new(4, 4)])
+  }
+
+  #[test]
+  fn seats_the_smallest_table_that_fits() {
+    let mut host = host();
+    assert_eq!(Ok(0), host.add_to_waitlist("Ana", 2, 0));
+    assert_eq!(Ok(0), host.add_to_waitlist("Luis", 3, 0));
+
+    let ana = host.seat_at_table(5).unwrap();
+    assert_eq!(vec![1], ana.tables);
+    assert_eq!(5, ana.waited);
+
+    let luis = host.seat_at_table(5).unwrap();
+    assert_eq!(vec![3], luis.tables);
+    assert_eq!(None, host.seat_at_table(5));
+  }
+
+  #[test]
+  fn combines_adjacent_tables() {
+    let mut host = Host::new(vec![Table::new(1, 4), Table::new(2, 2), Table::new(3, 4)]);
+    host.add_to_waitlist("Grande", 8, 0).unwrap();
+
+    // 1+2 son 6 lugares y no alcanza; 1+2+3 si
+    assert_eq!(vec![1, 2, 3], host.seat_at_table(0).unwrap().tables);
+    assert!(host.seating_at(2).is_some());
+
+    let seating = host.leave(3).unwrap();
+    assert_eq!("Grande", seating.name);
+    assert!(host.seatings().next().is_none());
+    assert_eq!(Err(HostingError::TableFree(1)), host.leave(1));
+  }
+
+  #[test]
+  fn rejects_parties_that_never_fit() {
+    let mut host = host();
+    assert_eq!(Err(HostingError::EmptyParty), host.add_to_waitlist("Nadie", 0, 0));
+    assert_eq!(Err(HostingError::TooLarge { size: 13, seats: 12 }), host.add_to_waitlist("Boda", 13, 0));
+    assert_eq!(Ok(0), host.add_to_waitlist("Todos", 12, 0));
+  }
+
+  #[test]
+  fn quotes_wait_times() {
+    let mut host = host();
+    for name in ["A", "B", "C", "D"].iter() {
+      host.add_to_waitlist(name, 2, 0).unwrap();
+    }
+    while host.seat_at_table(0).is_some() {}
+
+    // Todas las mesas estan ocupadas desde el minuto 0
+    assert_eq!(Ok(35), host.add_to_waitlist("E", 2, 10));
+    assert_eq!(Ok(35), host.add_to_waitlist("F", 4, 10));
+    assert_eq!(Ok(35), host.add_to_waitlist("G", 2, 10));
+    // Sobra la mesa 4, aunque sea para cuatro
+    assert_eq!(Ok(35), host.add_to_waitlist("H", 2, 10));
+    // Ya todas estan prometidas hasta el minuto 45 + 45
+    assert_eq!(Ok(80), host.add_to_waitlist("I", 2, 10));
+    assert_eq!(vec![35, 35, 35, 35, 80], host.quotes(10));
+    assert_eq!(35, host.waitlist().next().unwrap().quoted);
+
+    host.leave(1).unwrap();
+    assert_eq!("E", host.seat_at_table(20).unwrap().name);
+  }
+
+  #[test]
+  fn smaller_parties_go_ahead() {
+    let mut host = Host::new(vec![Table::new(1, 2), Table::new(2, 4)]);
+    host.add_to_waitlist("Primero", 4, 0).unwrap();
+    host.seat_at_table(0).unwrap();
+
+    host.add_to_waitlist("Grande", 4, 1).unwrap();
+    host.add_to_waitlist("Pareja", 2, 2).unwrap();
+    assert_eq!("Pareja", host.seat_at_table(3).unwrap().name);
+    assert_eq!("Grande", host.waitlist().next().unwrap().name);
+
+    assert_eq!(4, host.leave_waitlist("Grande").unwrap().size);
+    assert_eq!(None, host.leave_waitlist("Grande"));
+    assert!(host.waitlist().next().is_none());
+  }
+
+  #[test]
+  fn reservations_go_first_inside_their_window() {
+    let mut host = host();
+    let id = host.reserve("Reyes", 4, 60).unwrap();
+    host.add_to_waitlist("Fila", 4, 40).unwrap();
+    host.add_to_waitlist("Otra", 4, 40).unwrap();
+    host.check_in(id, 40).unwrap();
+
+    // Todavia no abre su ventana (45..=75)
+    assert_eq!("Fila", host.seat_at_table(40).unwrap().name);
+
+    let seating = host.seat_at_table(45).unwrap();
+    assert_eq!("Reyes", seating.name);
+    assert_eq!(0, seating.waited);
+    assert!(host.reservations().is_empty());
+  }
+
+  #[test]
+  fn no_shows_expire() {
+    let mut host = host();
+    let late = host.reserve("Tarde", 2, 60).unwrap();
+    let early = host.reserve("Temprano", 2, 60).unwrap();
+    host.check_in(early, 50).unwrap();
+
+    assert!(host.expire(75).is_empty());
+    let expired = host.expire(76);
+    assert_eq!(1, expired.len());
+    assert_eq!(late, expired[0].id);
+    assert_eq!(1, host.reservations().len());
+
+    assert_eq!(Err(HostingError::UnknownReservation(late)), host.check_in(late, 76));
+    let other = host.reserve("Otro", 2, 100).unwrap();
+    assert_eq!(Err(HostingError::Expired(other)), host.check_in(other, 116));
+  }
+}
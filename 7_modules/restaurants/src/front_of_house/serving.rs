@@ -0,0 +1,440 @@
+/* -------------------- Servicio -------------------- */
+/*
+  Cada orden pasa por los mismos estados y siempre en el mismo orden:
+
+    Placed -> Cooking -> Served -> Paid
+
+  Los campos de "Order" son privados, asi que la unica forma de cambiar
+  su estado es con las funciones de este modulo, que rechazan cualquier
+  salto (cobrar algo que no se ha servido, servir dos veces...).
+
+  Una orden servida se cobra con su cuenta ("billing::Bill") y solo pasa
+  a pagada cuando la cuenta no debe nada. Al cobrarla queda un "Check":
+  quien la atendio, cuantos comieron, que se cobro y que no. Con esos se
+  hace el cierre del dia (ver "reporting.rs").
+
+  Un platillo se puede anular ("void") mientras no se haya servido: sale
+  de la orden como si no se hubiera pedido. Despues solo se puede regalar
+  ("comp"): sigue en la orden pero no se cobra.
+*/
+
+use std::error::Error;
+use std::fmt;
+
+use crate::front_of_house::billing::{Bill, Totals};
+use crate::front_of_house::hosting::Table;
+use crate::menu::MenuItem;
+use crate::money::Money;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OrderStatus {
+  Placed,
+  Cooking,
+  Served,
+  Paid,
+}
+
+impl OrderStatus {
+  // El estado que sigue, None cuando la orden ya esta pagada
+  pub fn next(self) -> Option<OrderStatus> {
+    match self {
+      OrderStatus::Placed => Some(OrderStatus::Cooking),
+      OrderStatus::Cooking => Some(OrderStatus::Served),
+      OrderStatus::Served => Some(OrderStatus::Paid),
+      OrderStatus::Paid => None,
+    }
+  }
+}
+
+impl fmt::Display for OrderStatus {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let name = match self {
+      OrderStatus::Placed => "tomada",
+      OrderStatus::Cooking => "en cocina",
+      OrderStatus::Served => "servida",
+      OrderStatus::Paid => "pagada",
+    };
+    write!(f, "{}", name)
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OrderError {
+  InvalidTransition { from: OrderStatus, to: OrderStatus },
+  EmptyOrder,
+  Unpaid(Money),
+  UnknownItem(usize),
+  // Ya no se puede anular o regalar nada
+  Closed(OrderStatus),
+  // La cuenta no tiene los platillos de la orden
+  WrongBill(u32),
+}
+
+impl fmt::Display for OrderError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      OrderError::InvalidTransition { from, to } => {
+        write!(f, "una orden {} no puede pasar a {}", from, to)
+      }
+      OrderError::EmptyOrder => write!(f, "la orden no tiene platillos"),
+      OrderError::Unpaid(balance) => write!(f, "la cuenta todavia debe {}", balance),
+      OrderError::UnknownItem(item) => write!(f, "la orden no tiene platillo {}", item + 1),
+      OrderError::Closed(status) => write!(f, "una orden {} ya no se puede cambiar", status),
+      OrderError::WrongBill(order) => write!(f, "la cuenta no es de la orden {}", order),
+    }
+  }
+}
+
+impl Error for OrderError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Order {
+  id: u32,
+  table: u32,
+  server: String,
+  covers: u32,
+  items: Vec<MenuItem>,
+  // Misma posicion que "items"
+  comped: Vec<bool>,
+  voided: Vec<MenuItem>,
+  status: OrderStatus,
+}
+
+impl Order {
+  pub fn id(&self) -> u32 {
+    self.id
+  }
+
+  pub fn table(&self) -> u32 {
+    self.table
+  }
+
+  pub fn server(&self) -> &str {
+    &self.server
+  }
+
+  pub fn covers(&self) -> u32 {
+    self.covers
+  }
+
+  pub fn items(&self) -> &[MenuItem] {
+    &self.items
+  }
+
+  pub fn is_comped(&self, item: usize) -> bool {
+    self.comped.get(item).copied().unwrap_or(false)
+  }
+
+  pub fn voided(&self) -> &[MenuItem] {
+    &self.voided
+  }
+
+  // Quien atiende la mesa y cuantos comen, para el cierre del dia
+  pub fn assign(&mut self, server: &str, covers: u32) {
+    self.server = server.to_string();
+    self.covers = covers;
+  }
+
+  pub fn status(&self) -> OrderStatus {
+    self.status
+  }
+
+  // Sin lo que se regalo
+  pub fn total(&self) -> Money {
+    self.charged().map(|item| item.price).sum()
+  }
+
+  // Una cuenta nueva con un renglon por platillo; la tasa en puntos base.
+  // Lo regalado aparece sin costo.
+  pub fn bill(&self, tax_rate: u32) -> Bill {
+    Bill::new(self.lines(), tax_rate)
+  }
+
+  fn lines(&self) -> Vec<(String, Money)> {
+    self
+      .items
+      .iter()
+      .zip(&self.comped)
+      .map(|(item, &comped)| {
+        if comped {
+          (format!("{} (cortesia)", item.name), Money::ZERO)
+        } else {
+          (item.name.clone(), item.price)
+        }
+      })
+      .collect()
+  }
+
+  // Quita el platillo antes de que se sirva; no puede quedar vacia
+  pub fn void(&mut self, item: usize) -> Result<MenuItem, OrderError> {
+    if self.status != OrderStatus::Placed && self.status != OrderStatus::Cooking {
+      return Err(OrderError::Closed(self.status));
+    }
+    if item >= self.items.len() {
+      return Err(OrderError::UnknownItem(item));
+    }
+    if self.items.len() == 1 {
+      return Err(OrderError::EmptyOrder);
+    }
+
+    self.comped.remove(item);
+    let voided = self.items.remove(item);
+    self.voided.push(voided.clone());
+    Ok(voided)
+  }
+
+  // Lo deja en la orden pero no se cobra
+  pub fn comp(&mut self, item: usize) -> Result<(), OrderError> {
+    if self.status == OrderStatus::Paid {
+      return Err(OrderError::Closed(self.status));
+    }
+    let comped = self.comped.get_mut(item).ok_or(OrderError::UnknownItem(item))?;
+
+    *comped = true;
+    Ok(())
+  }
+
+  fn charged(&self) -> impl Iterator<Item = &MenuItem> {
+    self.items.iter().zip(&self.comped).filter(|(_, &comped)| !comped).map(|(item, _)| item)
+  }
+
+  // Solo se avanza un estado a la vez
+  fn advance(&mut self, to: OrderStatus) -> Result<(), OrderError> {
+    if self.status.next() != Some(to) {
+      return Err(OrderError::InvalidTransition { from: self.status, to });
+    }
+
+    self.status = to;
+    Ok(())
+  }
+}
+
+// Lo que la cocina recibe: que preparar y para que mesa
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ticket {
+  pub order: u32,
+  pub table: u32,
+  pub items: Vec<String>,
+}
+
+// Lo que queda de una orden pagada
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Check {
+  pub order: u32,
+  pub table: u32,
+  pub server: String,
+  pub covers: u32,
+  // El minuto en que se cobro
+  pub paid_at: u32,
+  // Lo que se cobro, lo que se regalo y lo que se anulo
+  pub items: Vec<MenuItem>,
+  pub comps: Vec<MenuItem>,
+  pub voids: Vec<MenuItem>,
+  pub totals: Totals,
+  pub paid: Money,
+}
+
+pub fn take_order(id: u32, table: &Table, items: Vec<MenuItem>) -> Result<Order, OrderError> {
+  if items.is_empty() {
+    return Err(OrderError::EmptyOrder);
+  }
+
+  Ok(Order {
+    id,
+    table: table.number,
+    server: String::new(),
+    covers: 0,
+    comped: vec![false; items.len()],
+    items,
+    voided: Vec::new(),
+    status: OrderStatus::Placed,
+  })
+}
+
+pub fn send_to_kitchen(order: &mut Order) -> Result<Ticket, OrderError> {
+  order.advance(OrderStatus::Cooking)?;
+
+  Ok(Ticket {
+    order: order.id,
+    table: order.table,
+    items: order.items.iter().map(|item| item.name.clone()).collect(),
+  })
+}
+
+pub fn serve_order(order: &mut Order) -> Result<(), OrderError> {
+  order.advance(OrderStatus::Served)
+}
+
+// Cierra la orden si su cuenta ya se pago; "now" es el minuto del cobro.
+// La cuenta tiene que ser la de "order.bill(..)", con los mismos renglones.
+pub fn take_payment(order: &mut Order, bill: &Bill, now: u32) -> Result<Check, OrderError> {
+  if bill.lines() != order.lines().as_slice() {
+    return Err(OrderError::WrongBill(order.id));
+  }
+  if order.status == OrderStatus::Served && !bill.is_settled() {
+    return Err(OrderError::Unpaid(bill.balance()));
+  }
+  order.advance(OrderStatus::Paid)?;
+
+  let comps = order.items.iter().zip(&order.comped).filter(|(_, &comped)| comped).map(|(item, _)| item.clone());
+  Ok(Check {
+    order: order.id,
+    table: order.table,
+    server: order.server.clone(),
+    covers: order.covers,
+    paid_at: now,
+    items: order.charged().cloned().collect(),
+    comps: comps.collect(),
+    voids: order.voided.clone(),
+    totals: bill.totals(),
+    paid: bill.paid(),
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::front_of_house::billing::Tender;
+
+  fn order() -> Order {
+    let items = vec![MenuItem::new("Tacos", 8_50), MenuItem::new("Agua", 2_00)];
+    take_order(1, &Table::new(4, 2), items).unwrap()
+  }
+
+  // La cuenta sin impuesto, pagada en efectivo
+  fn paid(order: &Order) -> Bill {
+    let mut bill = order.bill(0);
+    bill.pay(Tender::Cash(order.total())).unwrap();
+    bill
+  }
+
+  fn invalid(from: OrderStatus, to: OrderStatus) -> Result<(), OrderError> {
+    Err(OrderError::InvalidTransition { from, to })
+  }
+
+  #[test]
+  fn full_lifecycle() {
+    let mut order = order();
+    assert_eq!(OrderStatus::Placed, order.status());
+    assert_eq!(4, order.table());
+
+    let ticket = send_to_kitchen(&mut order).unwrap();
+    assert_eq!(Ticket { order: 1, table: 4, items: vec!["Tacos".to_string(), "Agua".to_string()] }, ticket);
+    assert_eq!(OrderStatus::Cooking, order.status());
+
+    serve_order(&mut order).unwrap();
+    assert_eq!(OrderStatus::Served, order.status());
+
+    let mut bill = order.bill(1600);
+    assert_eq!(Err(OrderError::Unpaid(Money::from_cents(12_18))), take_payment(&mut order, &bill, 40));
+    assert_eq!(OrderStatus::Served, order.status());
+
+    bill.pay(Tender::Card(Money::from_cents(12_18))).unwrap();
+    let check = take_payment(&mut order, &bill, 40).unwrap();
+    assert_eq!(Money::from_cents(12_18), check.paid);
+    assert_eq!(40, check.paid_at);
+    assert_eq!(OrderStatus::Paid, order.status());
+  }
+
+  #[test]
+  fn empty_orders_are_rejected() {
+    assert_eq!(Err(OrderError::EmptyOrder), take_order(1, &Table::new(1, 2), Vec::new()));
+  }
+
+  #[test]
+  fn cannot_skip_states() {
+    let mut order = order();
+    let bill = paid(&order);
+
+    assert_eq!(invalid(OrderStatus::Placed, OrderStatus::Served), serve_order(&mut order));
+    assert_eq!(
+      Err(OrderError::InvalidTransition { from: OrderStatus::Placed, to: OrderStatus::Paid }),
+      take_payment(&mut order, &bill, 0)
+    );
+
+    send_to_kitchen(&mut order).unwrap();
+    assert_eq!(
+      Err(OrderError::InvalidTransition { from: OrderStatus::Cooking, to: OrderStatus::Paid }),
+      take_payment(&mut order, &bill, 0)
+    );
+    // Un error no cambia el estado
+    assert_eq!(OrderStatus::Cooking, order.status());
+  }
+
+  #[test]
+  fn cannot_go_back_or_repeat() {
+    let mut order = order();
+    send_to_kitchen(&mut order).unwrap();
+    assert_eq!(
+      Err(OrderError::InvalidTransition { from: OrderStatus::Cooking, to: OrderStatus::Cooking }),
+      send_to_kitchen(&mut order)
+    );
+
+    serve_order(&mut order).unwrap();
+    assert_eq!(invalid(OrderStatus::Served, OrderStatus::Served), serve_order(&mut order));
+    assert!(send_to_kitchen(&mut order).is_err());
+
+    let bill = paid(&order);
+    take_payment(&mut order, &bill, 0).unwrap();
+    assert_eq!(
+      Err(OrderError::InvalidTransition { from: OrderStatus::Paid, to: OrderStatus::Paid }),
+      take_payment(&mut order, &bill, 0)
+    );
+    assert_eq!(invalid(OrderStatus::Paid, OrderStatus::Served), serve_order(&mut order));
+  }
+
+  #[test]
+  fn voids_before_serving_and_comps_after() {
+    let mut order = order();
+    order.assign("Luis", 2);
+    assert_eq!(Err(OrderError::UnknownItem(2)), order.void(2).map(|_| ()));
+
+    send_to_kitchen(&mut order).unwrap();
+    assert_eq!("Agua", order.void(1).unwrap().name);
+    assert_eq!(Err(OrderError::EmptyOrder), order.void(0).map(|_| ()));
+    assert_eq!(Money::from_cents(8_50), order.total());
+
+    serve_order(&mut order).unwrap();
+    assert_eq!(Err(OrderError::Closed(OrderStatus::Served)), order.void(0).map(|_| ()));
+    order.comp(0).unwrap();
+    assert!(order.is_comped(0));
+    assert_eq!(Money::ZERO, order.total());
+    assert_eq!(vec![("Tacos (cortesia)".to_string(), Money::ZERO)], order.bill(1600).lines());
+
+    // Nada que cobrar: la cuenta ya esta saldada
+    let bill = order.bill(1600);
+    let check = take_payment(&mut order, &bill, 55).unwrap();
+    assert_eq!(("Luis", 2), (check.server.as_str(), check.covers));
+    assert!(check.items.is_empty());
+    assert_eq!(vec!["Tacos"], check.comps.iter().map(|item| item.name.as_str()).collect::<Vec<_>>());
+    assert_eq!(vec!["Agua"], check.voids.iter().map(|item| item.name.as_str()).collect::<Vec<_>>());
+    assert_eq!(Err(OrderError::Closed(OrderStatus::Paid)), order.comp(0));
+  }
+
+  #[test]
+  fn only_takes_the_bill_of_the_order() {
+    let mut order = order();
+    send_to_kitchen(&mut order).unwrap();
+    serve_order(&mut order).unwrap();
+
+    let other = take_order(2, &Table::new(1, 2), vec![MenuItem::new("Sopa", 6_00)]).unwrap();
+    assert_eq!(Err(OrderError::WrongBill(1)), take_payment(&mut order, &paid(&other), 40));
+
+    // La cuenta de antes de regalar un platillo ya no es la de la orden
+    let before = paid(&order);
+    order.comp(1).unwrap();
+    assert_eq!(Err(OrderError::WrongBill(1)), take_payment(&mut order, &before, 40));
+    assert_eq!(OrderStatus::Served, order.status());
+
+    let bill = paid(&order);
+    assert_eq!(Money::from_cents(8_50), take_payment(&mut order, &bill, 40).unwrap().paid);
+  }
+
+  #[test]
+  fn error_messages() {
+    let error = OrderError::InvalidTransition { from: OrderStatus::Placed, to: OrderStatus::Paid };
+    assert_eq!("una orden tomada no puede pasar a pagada", error.to_string());
+    assert_eq!("la cuenta todavia debe $3.50", OrderError::Unpaid(Money::from_cents(3_50)).to_string());
+    assert_eq!("la cuenta no es de la orden 4", OrderError::WrongBill(4).to_string());
+  }
+}
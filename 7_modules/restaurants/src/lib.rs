@@ -6,23 +6,36 @@
 */
 
 
-mod front_of_house {
-  // publicos
-  pub mod hosting {
-    pub fn add_to_waitlist() {}
-
-    fn seat_at_table() {}
-  }
-
-  // Privados
-  mod serving {
-    fn take_order() {}
-
-    fn serve_order() {}
+/*
+  Los modulos viven en sus propios archivos (ver "files_modules.rs"):
+
+    src/front_of_house.rs           -> pub mod billing; hosting; serving;
+    src/front_of_house/billing.rs   -> la cuenta: impuestos, propinas y pagos
+    src/front_of_house/hosting.rs   -> mesas, lista de espera y reservaciones
+    src/front_of_house/serving.rs   -> ordenes y sus estados
+    src/api.rs                      -> la API por HTTP para las tabletas del salon
+    src/api/http.rs                 -> peticiones y respuestas HTTP/1.1
+    src/api/json.rs                 -> leer y escribir JSON
+    src/back_of_house.rs            -> la cocina y sus estaciones
+    src/back_of_house/inventory.rs  -> ingredientes, recetas y costo de alimentos
+    src/clock.rs                    -> el reloj simulado de la cocina
+    src/menu.rs                     -> el menu: precios, modificadores, alergenos
+                                       y horarios, se carga de "menu.txt"
+    src/money.rs                    -> dinero en centavos enteros
+    src/reporting.rs                -> el cierre del dia: ventas, comensales, cortesias
+    src/simulation.rs               -> una noche completa, con "cargo run"
+    src/storage.rs                  -> la bitacora en disco de mesas, ordenes y pagos
+*/
 
-    fn take_payment() {}
-  }
-}
+pub mod api;
+pub mod back_of_house;
+pub mod clock;
+pub mod front_of_house;
+pub mod menu;
+pub mod money;
+pub mod reporting;
+pub mod simulation;
+pub mod storage;
 
 /* 
     
@@ -42,17 +55,37 @@ mod front_of_house {
 
 /* -------------------- Paths para referirnos a un elemento en el arbol del modulo -------------------- */
 
-pub fn eat_at_restaurant() {
+// Sigue una orden de principio a fin, devuelve lo cobrado
+pub fn eat_at_restaurant() -> Result<money::Money, front_of_house::serving::OrderError> {
+  // Todo bien, todo es publico
+  let mut host = front_of_house::hosting::Host::new(vec![front_of_house::hosting::Table::new(1, 2)]);
+  host.add_to_waitlist("Ana", 2, 0).expect("cabe en la mesa 1");
+  let seating = host.seat_at_table(0).expect("la mesa 1 esta libre");
+
+  let table = host.tables()[0];
+  let items = vec![menu::MenuItem::new("Tacos", 8_50)];
+
   // Absolute path
-  create::front_of_house::serving::take_order();
+  let mut order = crate::front_of_house::serving::take_order(1, &table, items)?;
 
   // Relative path
-  front_of_house::serving::take_order();
+  let ticket = front_of_house::serving::send_to_kitchen(&mut order)?;
 
-  // Tanto "serving" como "take_order" son privados por defecto
-  // por lo cual el compilador arrojaria un error.
+  let mut kitchen = back_of_house::Kitchen::new();
+  kitchen.route("Tacos", back_of_house::Station::Grill, 8);
+  let ready = kitchen.cook_order(&ticket).expect("la parrilla hace tacos");
+  kitchen.advance(ready);
 
+  front_of_house::serving::serve_order(&mut order)?;
 
-  // Todo bien, todo es publico
-  front_of_house::hosting::add_to_waitlist();
-}
\ No newline at end of file
+  // "serving" y sus funciones ahora son "pub"; sin eso el compilador
+  // arrojaria un error desde aqui, porque son privados por defecto.
+  let mut bill = order.bill(1600);
+  bill.tip(front_of_house::billing::Tip::Percent(1500)).expect("un porcentaje nunca es negativo");
+  let balance = bill.balance();
+  bill.pay(front_of_house::billing::Tender::Card(balance)).expect("se paga justo");
+  let check = front_of_house::serving::take_payment(&mut order, &bill, kitchen.now() + 30)?;
+
+  host.leave(seating.tables[0]).expect("la mesa 1 esta ocupada");
+  Ok(check.paid)
+}
@@ -0,0 +1,150 @@
+/*
+  Simula una noche en el restaurante con todo lo que hay en la libreria.
+
+    cargo run -- [--seed <numero>] [--minutes <minutos>] [--every <minutos>]
+                 [--menu <archivo>]
+
+  Sin "--seed" se usa una al azar y se muestra, para repetir la noche.
+  Al final se imprime el cierre del dia; con "--csv <archivo>" tambien se
+  guarda como CSV.
+
+  Con "--serve" en vez de simular atiende la API para las tabletas; lo que
+  pase se guarda en la bitacora ("restaurante.log" si no se da otra):
+
+    cargo run -- --serve 127.0.0.1:8080 [--log <archivo>] [--menu <archivo>]
+
+  Y con "--close" se hace el cierre del dia de esa bitacora:
+
+    cargo run -- --close [--log <archivo>] [--csv <archivo>]
+*/
+
+use std::env;
+use std::fs;
+use std::process;
+
+use rand::Rng;
+
+use restaurants::api::Server;
+use restaurants::menu::Menu;
+use restaurants::reporting::DayReport;
+use restaurants::simulation::{self, Config, Simulation};
+use restaurants::storage::Store;
+
+fn main() {
+  if let Some(addr) = option("--serve") {
+    serve(&addr);
+    return;
+  }
+  if flag("--close") {
+    let store = open_log();
+    close(&DayReport::new(store.restaurant().checks(), simulation::OPENS_AT));
+    return;
+  }
+
+  let seed = match option("--seed") {
+    Some(seed) => number(&seed, "--seed"),
+    None => rand::thread_rng().gen(),
+  };
+
+  let mut config = Config::new(seed);
+  if let Some(minutes) = option("--minutes") {
+    config.minutes = number(&minutes, "--minutes");
+  }
+  if let Some(every) = option("--every") {
+    config.arrival_every = number(&every, "--every");
+    // NaN tambien se lee como numero
+    if !(config.arrival_every.is_finite() && config.arrival_every >= simulation::MIN_ARRIVAL_EVERY) {
+      eprintln!("--every necesita un numero de al menos {}, no {}", simulation::MIN_ARRIVAL_EVERY, every);
+      process::exit(2);
+    }
+  }
+
+  let opens_at = config.opens_at;
+  let simulation = match option("--menu") {
+    Some(path) => {
+      let menu = load_menu(&path);
+      let kitchen = simulation::kitchen(&menu);
+      Simulation::new(config, menu, kitchen, simulation::floor())
+    }
+    None => simulation::sample(config),
+  };
+
+  let report = simulation.run();
+  println!("Semilla: {}", seed);
+  println!("{}", report);
+  println!();
+  close(&DayReport::new(&report.checks, opens_at));
+}
+
+fn serve(addr: &str) {
+  let menu = match option("--menu") {
+    Some(path) => load_menu(&path),
+    None => Menu::parse(simulation::SAMPLE_MENU).expect("menu.txt is valid"),
+  };
+  let store = open_log();
+
+  let mut server = Server::bind(addr, store, menu).unwrap_or_else(|e| {
+    eprintln!("No se pudo escuchar en {}: {}", addr, e);
+    process::exit(1);
+  });
+  println!("Atendiendo en http://{} ({} eventos en la bitacora)", addr, server.store().events());
+  let e = server.serve();
+  eprintln!("La bitacora ya no se puede usar: {}", e);
+  process::exit(1);
+}
+
+fn open_log() -> Store {
+  let log = option("--log").unwrap_or_else(|| "restaurante.log".to_string());
+  let store = Store::open(&log, simulation::floor()).unwrap_or_else(|e| {
+    eprintln!("No se pudo abrir la bitacora {}: {}", log, e);
+    process::exit(1);
+  });
+  if store.truncated() > 0 {
+    eprintln!("Se descartaron {} bytes de un registro incompleto", store.truncated());
+  }
+
+  store
+}
+
+// Imprime el cierre y, si se pidio, lo guarda como CSV
+fn close(report: &DayReport) {
+  println!("{}", report);
+
+  if let Some(path) = option("--csv") {
+    if let Err(e) = fs::write(&path, report.to_csv()) {
+      eprintln!("No se pudo escribir {}: {}", path, e);
+      process::exit(1);
+    }
+  }
+}
+
+fn load_menu(path: &str) -> Menu {
+  Menu::load(path).unwrap_or_else(|e| {
+    eprintln!("No se pudo leer el menu {}: {}", path, e);
+    process::exit(2);
+  })
+}
+
+// El valor de "--nombre valor" en la linea de comandos
+fn option(name: &str) -> Option<String> {
+  let mut args = env::args().skip(1);
+
+  while let Some(arg) = args.next() {
+    if arg == name {
+      return args.next();
+    }
+  }
+
+  None
+}
+
+fn flag(name: &str) -> bool {
+  env::args().skip(1).any(|arg| arg == name)
+}
+
+fn number<T: std::str::FromStr>(text: &str, name: &str) -> T {
+  text.parse().unwrap_or_else(|_| {
+    eprintln!("{} necesita un numero, no {}", name, text);
+    process::exit(2);
+  })
+}
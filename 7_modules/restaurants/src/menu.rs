@@ -0,0 +1,483 @@
+/* -------------------- Menu -------------------- */
+/*
+  Lo que el restaurante vende. Los precios son "Money", centavos enteros,
+  para que las cuentas no pierdan decimales.
+
+  El menu se escribe en un archivo de texto parecido a TOML, una seccion
+  por platillo:
+
+    # comentario
+    [Hamburguesa]
+    category = main
+    price = 10.50
+    allergens = gluten, dairy
+    available = lunch, dinner         (o "all day", o "7:00-11:30")
+    add = Tocino, 1.50
+    add = Queso extra, 1.00, dairy
+    remove = Pan, -1.00, gluten
+
+  Un "add" agrega un ingrediente y sus alergenos; un "remove" quita uno, y
+  los alergenos que lleva dejan de estar en el platillo. Las horas son
+  minutos desde la medianoche.
+*/
+
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::money::Money;
+
+pub const BREAKFAST: (u32, u32) = (7 * 60, 11 * 60 + 30);
+pub const LUNCH: (u32, u32) = (12 * 60, 17 * 60);
+pub const DINNER: (u32, u32) = (18 * 60, 23 * 60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Category {
+  Starter,
+  Main,
+  Side,
+  Dessert,
+  Drink,
+}
+
+impl Category {
+  pub const ALL: [Category; 5] = [Category::Starter, Category::Main, Category::Side, Category::Dessert, Category::Drink];
+
+  pub fn parse(name: &str) -> Option<Category> {
+    Category::ALL.iter().copied().find(|category| category.name() == name.trim())
+  }
+
+  pub fn name(self) -> &'static str {
+    match self {
+      Category::Starter => "starter",
+      Category::Main => "main",
+      Category::Side => "side",
+      Category::Dessert => "dessert",
+      Category::Drink => "drink",
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Allergen {
+  Gluten,
+  Dairy,
+  Egg,
+  Peanut,
+  TreeNut,
+  Soy,
+  Fish,
+  Shellfish,
+  Sesame,
+}
+
+impl Allergen {
+  pub const ALL: [Allergen; 9] = [
+    Allergen::Gluten,
+    Allergen::Dairy,
+    Allergen::Egg,
+    Allergen::Peanut,
+    Allergen::TreeNut,
+    Allergen::Soy,
+    Allergen::Fish,
+    Allergen::Shellfish,
+    Allergen::Sesame,
+  ];
+
+  pub fn parse(name: &str) -> Option<Allergen> {
+    Allergen::ALL.iter().copied().find(|allergen| allergen.name() == name.trim())
+  }
+
+  pub fn name(self) -> &'static str {
+    match self {
+      Allergen::Gluten => "gluten",
+      Allergen::Dairy => "dairy",
+      Allergen::Egg => "egg",
+      Allergen::Peanut => "peanut",
+      Allergen::TreeNut => "tree nut",
+      Allergen::Soy => "soy",
+      Allergen::Fish => "fish",
+      Allergen::Shellfish => "shellfish",
+      Allergen::Sesame => "sesame",
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Change {
+  Add,
+  Remove,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Modifier {
+  pub name: String,
+  pub change: Change,
+  // Lo que cambia el precio, negativo si es mas barato
+  pub delta: Money,
+  pub allergens: Vec<Allergen>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MenuItem {
+  pub name: String,
+  pub category: Category,
+  pub price: Money,
+  pub allergens: Vec<Allergen>,
+  // Los que se pueden pedir; en un platillo ya personalizado, los que
+  // se pidieron
+  pub modifiers: Vec<Modifier>,
+  // Horarios (desde, hasta) en minutos del dia; vacio es todo el dia
+  pub available: Vec<(u32, u32)>,
+}
+
+impl MenuItem {
+  // Un plato fuerte sin alergenos ni modificadores, todo el dia
+  pub fn new(name: &str, cents: i64) -> MenuItem {
+    MenuItem {
+      name: name.to_string(),
+      category: Category::Main,
+      price: Money::from_cents(cents),
+      allergens: Vec::new(),
+      modifiers: Vec::new(),
+      available: Vec::new(),
+    }
+  }
+
+  // Un horario que termina antes de empezar cruza la medianoche
+  pub fn is_available(&self, minute: u32) -> bool {
+    self.available.is_empty()
+      || self.available.iter().any(|&(from, to)| {
+        if from <= to {
+          (from..to).contains(&minute)
+        } else {
+          minute >= from || minute < to
+        }
+      })
+  }
+
+  pub fn modifier(&self, name: &str) -> Option<&Modifier> {
+    self.modifiers.iter().find(|modifier| modifier.name.eq_ignore_ascii_case(name.trim()))
+  }
+
+  // El platillo como se pidio: con el precio y los alergenos de los
+  // modificadores elegidos
+  pub fn customize(&self, choices: &[&str]) -> Result<MenuItem, MenuError> {
+    let mut item = MenuItem { modifiers: Vec::new(), ..self.clone() };
+
+    for choice in choices {
+      let modifier = self
+        .modifier(choice)
+        .ok_or_else(|| MenuError::UnknownModifier { item: self.name.clone(), modifier: choice.to_string() })?;
+
+      item.price += modifier.delta;
+      match modifier.change {
+        Change::Add => item.allergens.extend(&modifier.allergens),
+        Change::Remove => item.allergens.retain(|allergen| !modifier.allergens.contains(allergen)),
+      }
+      item.modifiers.push(modifier.clone());
+    }
+    item.allergens.sort_by_key(|allergen| Allergen::ALL.iter().position(|a| a == allergen));
+    item.allergens.dedup();
+
+    Ok(item)
+  }
+
+  // Lo que hay que quitarle para que no tenga el alergeno: None si no se
+  // puede, una lista vacia si ya es seguro
+  pub fn without(&self, allergen: Allergen) -> Option<Vec<&Modifier>> {
+    if !self.allergens.contains(&allergen) {
+      return Some(Vec::new());
+    }
+
+    self
+      .modifiers
+      .iter()
+      .find(|modifier| modifier.change == Change::Remove && modifier.allergens.contains(&allergen))
+      .map(|modifier| vec![modifier])
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MenuError {
+  Parse { line: usize, message: String },
+  UnknownModifier { item: String, modifier: String },
+}
+
+impl fmt::Display for MenuError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      MenuError::Parse { line, message } => write!(f, "linea {}: {}", line, message),
+      MenuError::UnknownModifier { item, modifier } => write!(f, "{} no se puede pedir con {}", item, modifier),
+    }
+  }
+}
+
+impl Error for MenuError {}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Menu {
+  items: Vec<MenuItem>,
+}
+
+impl Menu {
+  pub fn new(items: Vec<MenuItem>) -> Menu {
+    Menu { items }
+  }
+
+  pub fn parse(text: &str) -> Result<Menu, MenuError> {
+    let mut items: Vec<MenuItem> = Vec::new();
+    // La linea de cada seccion, para avisar si le falta el precio
+    let mut headers = Vec::new();
+    let mut priced = Vec::new();
+
+    for (i, line) in text.lines().enumerate() {
+      let number = i + 1;
+      let error = |message: String| MenuError::Parse { line: number, message };
+      let line = line.trim();
+      if line.is_empty() || line.starts_with('#') {
+        continue;
+      }
+
+      if line.starts_with('[') {
+        let name = line
+          .strip_prefix('[')
+          .and_then(|rest| rest.strip_suffix(']'))
+          .map(str::trim)
+          .filter(|name| !name.is_empty())
+          .ok_or_else(|| error(format!("seccion mal escrita: {}", line)))?;
+        if items.iter().any(|item| item.name == name) {
+          return Err(error(format!("{} ya esta en el menu", name)));
+        }
+
+        items.push(MenuItem::new(name, 0));
+        headers.push(number);
+        priced.push(false);
+        continue;
+      }
+
+      let (key, value) = match line.find('=') {
+        Some(equals) => (line[..equals].trim(), line[equals + 1..].trim()),
+        None => return Err(error(format!("falta \"=\" en: {}", line))),
+      };
+      let item = items
+        .last_mut()
+        .ok_or_else(|| error("falta la seccion [platillo]".to_string()))?;
+
+      match key {
+        "category" => {
+          item.category = Category::parse(value).ok_or_else(|| error(format!("categoria desconocida: {}", value)))?;
+        }
+        "price" => {
+          item.price = price(value).ok_or_else(|| error(format!("precio invalido: {}", value)))?;
+          if let Some(priced) = priced.last_mut() {
+            *priced = true;
+          }
+        }
+        "allergens" => item.allergens = allergens(value.split(',')).map_err(error)?,
+        "available" => item.available = hours(value).map_err(error)?,
+        "add" | "remove" => {
+          let change = if key == "add" { Change::Add } else { Change::Remove };
+          item.modifiers.push(modifier(change, value).map_err(error)?);
+        }
+        _ => return Err(error(format!("campo desconocido: {}", key))),
+      }
+    }
+
+    if let Some(i) = priced.iter().position(|&priced| !priced) {
+      let message = format!("{} no tiene precio", items[i].name);
+      return Err(MenuError::Parse { line: headers[i], message });
+    }
+
+    Ok(Menu { items })
+  }
+
+  pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Menu> {
+    let text = fs::read_to_string(path)?;
+    Menu::parse(&text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+  }
+
+  pub fn items(&self) -> &[MenuItem] {
+    &self.items
+  }
+
+  pub fn get(&self, name: &str) -> Option<&MenuItem> {
+    self.items.iter().find(|item| item.name.eq_ignore_ascii_case(name.trim()))
+  }
+
+  pub fn category(&self, category: Category) -> impl Iterator<Item = &MenuItem> {
+    self.items.iter().filter(move |item| item.category == category)
+  }
+
+  pub fn available_at(&self, minute: u32) -> impl Iterator<Item = &MenuItem> {
+    self.items.iter().filter(move |item| item.is_available(minute))
+  }
+
+  // Lo que puede comer alguien con esa alergia, con lo que hay que
+  // quitarle a cada platillo
+  pub fn safe_for(&self, allergen: Allergen) -> Vec<(&MenuItem, Vec<&Modifier>)> {
+    self
+      .items
+      .iter()
+      .filter_map(|item| item.without(allergen).map(|removals| (item, removals)))
+      .collect()
+  }
+}
+
+fn price(text: &str) -> Option<Money> {
+  Money::parse(text).filter(|&price| price >= Money::ZERO)
+}
+
+fn allergens<'a, I: Iterator<Item = &'a str>>(names: I) -> Result<Vec<Allergen>, String> {
+  names
+    .map(str::trim)
+    .filter(|name| !name.is_empty())
+    .map(|name| Allergen::parse(name).ok_or_else(|| format!("alergeno desconocido: {}", name)))
+    .collect()
+}
+
+// "Nombre, precio[, alergenos...]"
+fn modifier(change: Change, text: &str) -> Result<Modifier, String> {
+  let mut parts = text.split(',').map(str::trim);
+  let name = parts.next().filter(|name| !name.is_empty()).ok_or("falta el nombre del modificador")?;
+  let delta = parts.next().ok_or_else(|| format!("{} no tiene precio", name))?;
+  let delta = Money::parse(delta).ok_or_else(|| format!("precio invalido: {}", delta))?;
+
+  Ok(Modifier { name: name.to_string(), change, delta, allergens: allergens(parts)? })
+}
+
+// "all day", "breakfast", "lunch", "dinner" o "7:00-11:30", separados
+// por comas
+fn hours(text: &str) -> Result<Vec<(u32, u32)>, String> {
+  let mut windows = Vec::new();
+
+  for part in text.split(',').map(str::trim) {
+    match part {
+      "all day" => return Ok(Vec::new()),
+      "breakfast" => windows.push(BREAKFAST),
+      "lunch" => windows.push(LUNCH),
+      "dinner" => windows.push(DINNER),
+      _ => {
+        let window = part
+          .split_once('-')
+          .and_then(|(from, to)| Some((minute(from)?, minute(to)?)))
+          .ok_or_else(|| format!("horario invalido: {}", part))?;
+        windows.push(window);
+      }
+    }
+  }
+
+  Ok(windows)
+}
+
+// "7:05" -> 425
+fn minute(text: &str) -> Option<u32> {
+  let (hour, minute) = text.trim().split_once(':')?;
+  let (hour, minute): (u32, u32) = (hour.parse().ok()?, minute.parse().ok()?);
+
+  if hour < 24 && minute < 60 {
+    Some(hour * 60 + minute)
+  } else {
+    None
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const MENU: &str = include_str!("../menu.txt");
+
+  fn menu() -> Menu {
+    Menu::parse(MENU).unwrap()
+  }
+
+  fn names<'a, I: Iterator<Item = &'a MenuItem>>(items: I) -> Vec<&'a str> {
+    items.map(|item| item.name.as_str()).collect()
+  }
+
+  fn error(text: &str) -> String {
+    Menu::parse(text).unwrap_err().to_string()
+  }
+
+  #[test]
+  fn loads_the_sample_menu() {
+    let menu = menu();
+    let burger = menu.get("hamburguesa").unwrap();
+
+    assert_eq!(Category::Main, burger.category);
+    assert_eq!(Money::from_cents(10_50), burger.price);
+    assert_eq!(vec![Allergen::Gluten, Allergen::Dairy, Allergen::Sesame], burger.allergens);
+    assert_eq!(vec![LUNCH, DINNER], burger.available);
+    assert_eq!(Some(Change::Remove), burger.modifier("Pan").map(|m| m.change));
+    assert_eq!(vec!["Papas", "Aros de cebolla"], names(menu.category(Category::Side)));
+  }
+
+  #[test]
+  fn customizes_items() {
+    let menu = menu();
+    let burger = menu.get("Hamburguesa").unwrap();
+
+    let ordered = burger.customize(&["tocino", "Pan"]).unwrap();
+    assert_eq!(Money::from_cents(11_00), ordered.price);
+    assert_eq!(vec![Allergen::Dairy], ordered.allergens);
+    assert_eq!(2, ordered.modifiers.len());
+
+    let cheesy = burger.customize(&["Queso extra"]).unwrap();
+    assert_eq!(vec![Allergen::Gluten, Allergen::Dairy, Allergen::Sesame], cheesy.allergens);
+    assert_eq!(
+      Err(MenuError::UnknownModifier { item: "Hamburguesa".to_string(), modifier: "Mole".to_string() }),
+      burger.customize(&["Mole"])
+    );
+  }
+
+  #[test]
+  fn availability_by_time_of_day() {
+    let menu = menu();
+
+    assert_eq!(vec!["Chilaquiles", "Papas", "Cafe"], names(menu.available_at(8 * 60)));
+    assert!(names(menu.available_at(13 * 60)).contains(&"Hamburguesa"));
+    assert!(!names(menu.available_at(17 * 60 + 30)).contains(&"Hamburguesa"));
+    // El cafe de noche cruza la medianoche
+    assert!(names(menu.available_at(60)).contains(&"Cafe"));
+    assert!(!names(menu.available_at(4 * 60)).contains(&"Cafe"));
+  }
+
+  #[test]
+  fn safe_for_allergies() {
+    let menu = menu();
+
+    let safe: Vec<(&str, Vec<&str>)> = menu
+      .safe_for(Allergen::Gluten)
+      .into_iter()
+      .map(|(item, removals)| (item.name.as_str(), removals.iter().map(|m| m.name.as_str()).collect()))
+      .collect();
+    assert_eq!(
+      vec![
+        ("Hamburguesa", vec!["Pan"]),
+        ("Ensalada", vec!["Crutones"]),
+        ("Chilaquiles", vec![]),
+        ("Papas", vec![]),
+        ("Flan", vec![]),
+        ("Cafe", vec![])
+      ],
+      safe
+    );
+    assert_eq!(vec!["Hamburguesa", "Ensalada", "Papas", "Cafe"], names(menu.safe_for(Allergen::Egg).into_iter().map(|(item, _)| item)));
+  }
+
+  #[test]
+  fn reports_the_bad_line() {
+    assert_eq!("linea 1: falta la seccion [platillo]", error("price = 1"));
+    assert_eq!("linea 3: precio invalido: uno", error("[Agua]\n\nprice = uno"));
+    assert_eq!("linea 2: categoria desconocida: soup", error("[Sopa]\ncategory = soup"));
+    assert_eq!("linea 2: alergeno desconocido: mole", error("[Sopa]\nallergens = gluten, mole"));
+    assert_eq!("linea 2: horario invalido: 25:00-26:00", error("[Sopa]\navailable = 25:00-26:00"));
+    assert_eq!("linea 2: campo desconocido: color", error("[Sopa]\ncolor = rojo"));
+    assert_eq!("linea 1: Sopa no tiene precio", error("[Sopa]\ncategory = starter\n[Agua]\nprice = 1"));
+    assert_eq!("linea 3: Sopa ya esta en el menu", error("[Sopa]\nprice = 1\n[Sopa]"));
+    assert_eq!("linea 2: Queso no tiene precio", error("[Sopa]\nadd = Queso"));
+  }
+}
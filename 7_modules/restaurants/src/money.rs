@@ -0,0 +1,250 @@
+/* -------------------- Dinero -------------------- */
+/*
+  Todo se cuenta en centavos enteros. Un porcentaje se redondea una sola
+  vez al centavo mas cercano (los medios centavos hacia arriba), y cuando
+  una cantidad se reparte ("split", "allocate") las partes siempre suman
+  exactamente la cantidad original: los centavos que sobran se le dan a
+  las primeras partes.
+
+  Las tasas se dan en puntos base: 1600 es 16%, 825 es 8.25%.
+*/
+
+use std::fmt;
+use std::iter::Sum;
+use std::ops::{Add, AddAssign, Neg, Sub, SubAssign};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Money(i64);
+
+impl Money {
+  pub const ZERO: Money = Money(0);
+
+  pub fn from_cents(cents: i64) -> Money {
+    Money(cents)
+  }
+
+  pub fn cents(self) -> i64 {
+    self.0
+  }
+
+  // "10", "10.5", "$10.50" o "-1.25"; None con mas de dos decimales
+  pub fn parse(text: &str) -> Option<Money> {
+    let text = text.trim();
+    let (sign, text) = match text.strip_prefix('-') {
+      Some(rest) => (-1, rest),
+      None => (1, text),
+    };
+    let text = text.strip_prefix('$').unwrap_or(text);
+
+    let (whole, fraction) = match text.find('.') {
+      Some(dot) => (&text[..dot], &text[dot + 1..]),
+      None => (text, "00"),
+    };
+    let digits = |part: &str| !part.is_empty() && part.chars().all(|c| c.is_ascii_digit());
+    if !digits(whole) || !digits(fraction) || fraction.len() > 2 {
+      return None;
+    }
+
+    let whole: i64 = whole.parse().ok()?;
+    let fraction: i64 = format!("{:0<2}", fraction).parse().ok()?;
+    Some(Money(sign * (whole.checked_mul(100)? + fraction)))
+  }
+
+  // El porcentaje en puntos base, redondeado al centavo
+  pub fn percent(self, basis_points: u32) -> Money {
+    let exact = i128::from(self.0) * i128::from(basis_points);
+    let half = if exact < 0 { -5_000 } else { 5_000 };
+
+    Money(((exact + half) / 10_000) as i64)
+  }
+
+  // "parts" partes iguales; las primeras llevan un centavo mas si no
+  // alcanza para todas
+  pub fn split(self, parts: usize) -> Vec<Money> {
+    self.allocate(&vec![1; parts])
+  }
+
+  // Partes proporcionales a "weights" por el metodo del mayor residuo.
+  // Si todos los pesos son cero se reparte en partes iguales.
+  pub fn allocate(self, weights: &[i64]) -> Vec<Money> {
+    if weights.is_empty() {
+      return Vec::new();
+    }
+    if self.0 < 0 {
+      return (-self).allocate(weights).into_iter().map(Neg::neg).collect();
+    }
+
+    let total: i128 = weights.iter().map(|&weight| i128::from(weight.max(0))).sum();
+    if total == 0 {
+      return self.split(weights.len());
+    }
+
+    let amount = i128::from(self.0);
+    let mut parts = Vec::with_capacity(weights.len());
+    let mut remainders = Vec::with_capacity(weights.len());
+    for (i, &weight) in weights.iter().enumerate() {
+      let exact = amount * i128::from(weight.max(0));
+      parts.push((exact / total) as i64);
+      remainders.push((exact % total, i));
+    }
+
+    // Los centavos sueltos van a los residuos mas grandes, y entre
+    // iguales a las primeras partes
+    let left = self.0 - parts.iter().sum::<i64>();
+    remainders.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+    for &(_, i) in remainders.iter().take(left as usize) {
+      parts[i] += 1;
+    }
+
+    parts.into_iter().map(Money).collect()
+  }
+}
+
+impl fmt::Display for Money {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let sign = if self.0 < 0 { "-" } else { "" };
+    let cents = self.0.unsigned_abs();
+    write!(f, "{}${}.{:02}", sign, cents / 100, cents % 100)
+  }
+}
+
+impl Add for Money {
+  type Output = Money;
+
+  fn add(self, other: Money) -> Money {
+    Money(self.0 + other.0)
+  }
+}
+
+impl Sub for Money {
+  type Output = Money;
+
+  fn sub(self, other: Money) -> Money {
+    Money(self.0 - other.0)
+  }
+}
+
+impl Neg for Money {
+  type Output = Money;
+
+  fn neg(self) -> Money {
+    Money(-self.0)
+  }
+}
+
+impl AddAssign for Money {
+  fn add_assign(&mut self, other: Money) {
+    self.0 += other.0;
+  }
+}
+
+impl SubAssign for Money {
+  fn sub_assign(&mut self, other: Money) {
+    self.0 -= other.0;
+  }
+}
+
+impl Sum for Money {
+  fn sum<I: Iterator<Item = Money>>(iter: I) -> Money {
+    iter.fold(Money::ZERO, Add::add)
+  }
+}
+
+impl<'a> Sum<&'a Money> for Money {
+  fn sum<I: Iterator<Item = &'a Money>>(iter: I) -> Money {
+    iter.copied().sum()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn cents(parts: Vec<Money>) -> Vec<i64> {
+    parts.into_iter().map(Money::cents).collect()
+  }
+
+  #[test]
+  fn displays_dollars_and_cents() {
+    assert_eq!("$10.50", Money::from_cents(10_50).to_string());
+    assert_eq!("$0.07", Money::from_cents(7).to_string());
+    assert_eq!("-$1.00", Money::from_cents(-1_00).to_string());
+  }
+
+  #[test]
+  fn parses_amounts() {
+    assert_eq!(Some(Money::from_cents(10_50)), Money::parse("10.50"));
+    assert_eq!(Some(Money::from_cents(10_50)), Money::parse(" $10.5 "));
+    assert_eq!(Some(Money::from_cents(7_00)), Money::parse("7"));
+    assert_eq!(Some(Money::from_cents(-1_25)), Money::parse("-1.25"));
+    assert_eq!(None, Money::parse("1.255"));
+    assert_eq!(None, Money::parse("1."));
+    assert_eq!(None, Money::parse("diez"));
+    assert_eq!(None, Money::parse(""));
+  }
+
+  #[test]
+  fn rounds_half_cents_up() {
+    // 8.25% de $1.00 son 8.25 centavos
+    assert_eq!(Money::from_cents(8), Money::from_cents(1_00).percent(825));
+    // 16% de $0.03 son 0.48 centavos; de $0.04, 0.64
+    assert_eq!(Money::ZERO, Money::from_cents(3).percent(1600));
+    assert_eq!(Money::from_cents(1), Money::from_cents(4).percent(1600));
+    // 10% de $0.05 es justo medio centavo
+    assert_eq!(Money::from_cents(1), Money::from_cents(5).percent(1000));
+    assert_eq!(Money::from_cents(-1), Money::from_cents(-5).percent(1000));
+  }
+
+  #[test]
+  fn percent_is_never_more_than_half_a_cent_off() {
+    for amount in 0..=5_000 {
+      for &rate in [1, 500, 825, 1000, 1250, 1600, 1800, 2000, 3333].iter() {
+        let exact = amount * i64::from(rate);
+        let rounded = Money::from_cents(amount).percent(rate).cents() * 10_000;
+        assert!((rounded - exact).abs() <= 5_000, "{} at {}", amount, rate);
+      }
+    }
+  }
+
+  #[test]
+  fn splits_evenly() {
+    assert_eq!(vec![334, 333, 333], cents(Money::from_cents(10_00).split(3)));
+    assert_eq!(vec![-334, -333, -333], cents(Money::from_cents(-10_00).split(3)));
+    assert_eq!(vec![1, 0, 0], cents(Money::from_cents(1).split(3)));
+    assert!(Money::from_cents(1).split(0).is_empty());
+  }
+
+  #[test]
+  fn every_split_adds_up() {
+    for amount in 0..=2_000 {
+      for parts in 1..=12 {
+        let split = Money::from_cents(amount).split(parts);
+        let (max, min) = (split.iter().max().unwrap(), split.iter().min().unwrap());
+
+        assert_eq!(amount, split.iter().sum::<Money>().cents(), "{} in {}", amount, parts);
+        assert!(max.cents() - min.cents() <= 1, "{} in {}", amount, parts);
+      }
+    }
+  }
+
+  #[test]
+  fn allocates_by_weight() {
+    assert_eq!(vec![75, 25], cents(Money::from_cents(1_00).allocate(&[3, 1])));
+    // 1/3 de 100 es 33.33 y 2/3 es 66.66: el centavo va al mayor residuo
+    assert_eq!(vec![33, 67], cents(Money::from_cents(1_00).allocate(&[1, 2])));
+    assert_eq!(vec![0, 5, 0], cents(Money::from_cents(5).allocate(&[0, 1, 0])));
+    assert_eq!(vec![2, 2, 1], cents(Money::from_cents(5).allocate(&[0, 0, 0])));
+  }
+
+  #[test]
+  fn every_allocation_adds_up() {
+    let weights: [&[i64]; 5] = [&[1, 1, 1], &[1_00, 2_50, 37], &[7, 0, 13, 1], &[9_99], &[1, 999_999]];
+
+    for amount in -300..=3_000 {
+      for weights in weights.iter() {
+        let parts = Money::from_cents(amount).allocate(weights);
+        assert_eq!(amount, parts.iter().sum::<Money>().cents(), "{} by {:?}", amount, weights);
+      }
+    }
+  }
+}
@@ -0,0 +1,388 @@
+/* -------------------- Cierre del dia -------------------- */
+/*
+  Junta las cuentas pagadas del dia ("serving::Check") en un reporte:
+  ventas por categoria y por mesero, comensales por hora, cuenta
+  promedio, anulaciones y cortesias. Se imprime como tabla de texto
+  ("Display") o se exporta a CSV para abrirlo en una hoja de calculo.
+
+  Las ventas son netas: el subtotal menos los descuentos, sin impuestos
+  ni propinas. El descuento de cada cuenta se reparte entre sus platillos
+  segun su precio ("Money::allocate"), asi las ventas por categoria, por
+  mesero y por hora suman exactamente las ventas del dia.
+
+  Los comensales cuentan en la hora en que se cobro su cuenta.
+*/
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::front_of_house::serving::Check;
+use crate::menu::{Category, MenuItem};
+use crate::money::Money;
+
+// Cuantos y cuanto valian
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Tally {
+  pub count: u32,
+  pub amount: Money,
+}
+
+impl Tally {
+  fn add(&mut self, items: &[MenuItem]) {
+    self.count += items.len() as u32;
+    self.amount += items.iter().map(|item| item.price).sum();
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CategorySales {
+  pub category: Category,
+  pub items: u32,
+  pub sales: Money,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ServerSales {
+  pub server: String,
+  pub checks: u32,
+  pub covers: u32,
+  pub sales: Money,
+  pub tips: Money,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct HourSales {
+  // La hora del reloj, de 0 a 23
+  pub hour: u32,
+  pub checks: u32,
+  pub covers: u32,
+  pub sales: Money,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DayReport {
+  pub checks: u32,
+  pub covers: u32,
+  pub sales: Money,
+  pub discounts: Money,
+  pub tax: Money,
+  pub tips: Money,
+  // En el orden de "Category::ALL", solo las que vendieron algo
+  pub categories: Vec<CategorySales>,
+  // Por nombre
+  pub servers: Vec<ServerSales>,
+  // En el orden del dia, aunque pase de la medianoche
+  pub hours: Vec<HourSales>,
+  pub voids: Tally,
+  pub comps: Tally,
+}
+
+impl DayReport {
+  // "opens_at" es el minuto del dia en que se abrio; las cuentas cuentan
+  // sus minutos desde ahi
+  pub fn new<'a, I: IntoIterator<Item = &'a Check>>(checks: I, opens_at: u32) -> DayReport {
+    let mut report = DayReport::default();
+    let mut categories: BTreeMap<usize, CategorySales> = BTreeMap::new();
+    let mut servers: BTreeMap<&str, ServerSales> = BTreeMap::new();
+    let mut hours: BTreeMap<u32, HourSales> = BTreeMap::new();
+
+    for check in checks {
+      let totals = &check.totals;
+      let sales = totals.subtotal - totals.discount;
+      report.checks += 1;
+      report.covers += check.covers;
+      report.sales += sales;
+      report.discounts += totals.discount;
+      report.tax += totals.tax;
+      report.tips += totals.tip;
+      report.voids.add(&check.voids);
+      report.comps.add(&check.comps);
+
+      let weights: Vec<i64> = check.items.iter().map(|item| item.price.cents()).collect();
+      for (item, net) in check.items.iter().zip(sales.allocate(&weights)) {
+        let position = Category::ALL.iter().position(|&category| category == item.category).unwrap_or_default();
+        let category = categories.entry(position).or_insert(CategorySales {
+          category: item.category,
+          items: 0,
+          sales: Money::ZERO,
+        });
+        category.items += 1;
+        category.sales += net;
+      }
+
+      let server = servers
+        .entry(check.server.as_str())
+        .or_insert_with(|| ServerSales { server: check.server.clone(), ..ServerSales::default() });
+      server.checks += 1;
+      server.covers += check.covers;
+      server.sales += sales;
+      server.tips += totals.tip;
+
+      // Horas desde la medianoche del dia en que se abrio
+      let hour = (opens_at + check.paid_at) / 60;
+      let hour = hours.entry(hour).or_insert(HourSales { hour: hour % 24, ..HourSales::default() });
+      hour.checks += 1;
+      hour.covers += check.covers;
+      hour.sales += sales;
+    }
+
+    report.categories = categories.into_values().collect();
+    report.servers = servers.into_values().collect();
+    report.hours = hours.into_values().collect();
+    report
+  }
+
+  // Las ventas entre las cuentas, redondeado al centavo
+  pub fn average_check(&self) -> Money {
+    if self.checks == 0 {
+      return Money::ZERO;
+    }
+    let checks = i64::from(self.checks);
+    Money::from_cents((self.sales.cents() + checks / 2) / checks)
+  }
+
+  // Una fila por dato: "cantidad" son platillos en las categorias,
+  // cuentas en los meseros y comensales en las horas
+  pub fn to_csv(&self) -> String {
+    let header = ["seccion", "concepto", "cantidad", "importe"].map(String::from);
+    let mut rows: Vec<[String; 4]> = vec![header];
+    let mut day = |concept: &str, count: Option<u32>, amount: Option<Money>| rows.push(row("dia", concept, count, amount));
+
+    day("cuentas", Some(self.checks), None);
+    day("comensales", Some(self.covers), None);
+    day("ventas", None, Some(self.sales));
+    day("descuentos", None, Some(self.discounts));
+    day("impuestos", None, Some(self.tax));
+    day("propinas", None, Some(self.tips));
+    day("cuenta promedio", None, Some(self.average_check()));
+    day("anulaciones", Some(self.voids.count), Some(self.voids.amount));
+    day("cortesias", Some(self.comps.count), Some(self.comps.amount));
+
+    for category in &self.categories {
+      rows.push(row("categoria", category.category.name(), Some(category.items), Some(category.sales)));
+    }
+    for server in &self.servers {
+      rows.push(row("mesero", server_name(server), Some(server.checks), Some(server.sales)));
+    }
+    for server in &self.servers {
+      rows.push(row("propinas", server_name(server), None, Some(server.tips)));
+    }
+    for hour in &self.hours {
+      rows.push(row("hora", &clock(hour.hour), Some(hour.covers), Some(hour.sales)));
+    }
+
+    let mut csv = String::new();
+    for fields in rows {
+      let fields: Vec<String> = fields.iter().map(|field| quote(field)).collect();
+      csv.push_str(&fields.join(","));
+      csv.push('\n');
+    }
+    csv
+  }
+}
+
+impl fmt::Display for DayReport {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    writeln!(f, "Cierre del dia")?;
+    writeln!(f, "Cuentas: {}, comensales: {}, cuenta promedio: {}", self.checks, self.covers, self.average_check())?;
+    writeln!(
+      f,
+      "Ventas: {}, descuentos: {}, impuestos: {}, propinas: {}",
+      self.sales, self.discounts, self.tax, self.tips
+    )?;
+    writeln!(f, "Anulaciones: {} ({}), cortesias: {} ({})", self.voids.count, self.voids.amount, self.comps.count, self.comps.amount)?;
+
+    writeln!(f)?;
+    writeln!(f, "{:<12} {:>9} {:>11}", "Categoria", "Platillos", "Ventas")?;
+    for category in &self.categories {
+      writeln!(f, "{:<12} {:>9} {:>11}", category.category.name(), category.items, category.sales.to_string())?;
+    }
+
+    writeln!(f)?;
+    writeln!(f, "{:<16} {:>7} {:>10} {:>11} {:>10}", "Mesero", "Cuentas", "Comensales", "Ventas", "Propinas")?;
+    for server in &self.servers {
+      writeln!(
+        f,
+        "{:<16} {:>7} {:>10} {:>11} {:>10}",
+        server_name(server),
+        server.checks,
+        server.covers,
+        server.sales.to_string(),
+        server.tips.to_string()
+      )?;
+    }
+
+    writeln!(f)?;
+    write!(f, "{:<5} {:>7} {:>10} {:>11}", "Hora", "Cuentas", "Comensales", "Ventas")?;
+    for hour in &self.hours {
+      write!(f, "\n{:<5} {:>7} {:>10} {:>11}", clock(hour.hour), hour.checks, hour.covers, hour.sales.to_string())?;
+    }
+    Ok(())
+  }
+}
+
+fn server_name(server: &ServerSales) -> &str {
+  if server.server.is_empty() {
+    "(sin asignar)"
+  } else {
+    &server.server
+  }
+}
+
+fn clock(hour: u32) -> String {
+  format!("{:02}:00", hour)
+}
+
+fn row(section: &str, concept: &str, count: Option<u32>, amount: Option<Money>) -> [String; 4] {
+  [
+    section.to_string(),
+    concept.to_string(),
+    count.map(|count| count.to_string()).unwrap_or_default(),
+    amount.map(decimal).unwrap_or_default(),
+  ]
+}
+
+// Sin el signo de pesos, para que la hoja de calculo lo lea como numero
+fn decimal(amount: Money) -> String {
+  let sign = if amount < Money::ZERO { "-" } else { "" };
+  let cents = amount.cents().unsigned_abs();
+  format!("{}{}.{:02}", sign, cents / 100, cents % 100)
+}
+
+// Entre comillas si trae comas, comillas o saltos de linea
+fn quote(field: &str) -> String {
+  if field.contains([',', '"', '\n', '\r']) {
+    format!("\"{}\"", field.replace('"', "\"\""))
+  } else {
+    field.to_string()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::front_of_house::billing::{Discount, Tender, Tip};
+  use crate::front_of_house::hosting::Table;
+  use crate::front_of_house::serving;
+
+  fn item(name: &str, category: Category, cents: i64) -> MenuItem {
+    MenuItem { category, ..MenuItem::new(name, cents) }
+  }
+
+  // Una orden de principio a fin; "discount" y "tip" en centavos
+  fn check(server: &str, covers: u32, items: Vec<MenuItem>, paid_at: u32, discount: i64, tip: i64) -> Check {
+    let mut order = serving::take_order(1, &Table::new(1, 4), items).unwrap();
+    order.assign(server, covers);
+    serving::send_to_kitchen(&mut order).unwrap();
+    serving::serve_order(&mut order).unwrap();
+
+    let mut bill = order.bill(1600);
+    bill.discount(Discount::Amount(Money::from_cents(discount))).unwrap();
+    bill.tip(Tip::Amount(Money::from_cents(tip))).unwrap();
+    let balance = bill.balance();
+    bill.pay(Tender::Card(balance)).unwrap();
+    serving::take_payment(&mut order, &bill, paid_at).unwrap()
+  }
+
+  fn evening() -> Vec<Check> {
+    let burger = || item("Hamburguesa", Category::Main, 12_00);
+    let fries = || item("Papas", Category::Side, 4_00);
+    let coffee = || item("Cafe", Category::Drink, 2_50);
+
+    // Un platillo regalado y otro anulado antes de servir
+    let mut order = serving::take_order(2, &Table::new(2, 2), vec![burger(), coffee(), fries()]).unwrap();
+    order.assign("Beto", 2);
+    serving::send_to_kitchen(&mut order).unwrap();
+    order.void(2).unwrap();
+    serving::serve_order(&mut order).unwrap();
+    order.comp(1).unwrap();
+    let bill = order.bill(1600);
+    let mut paid = bill.clone();
+    paid.pay(Tender::Cash(bill.balance())).unwrap();
+    let comped = serving::take_payment(&mut order, &paid, 130).unwrap();
+
+    vec![
+      check("Ana", 4, vec![burger(), burger(), fries()], 50, 0, 5_00),
+      // $3 de descuento sobre $16: $2.25 a la hamburguesa y $0.75 a las papas
+      check("Beto", 3, vec![burger(), fries()], 70, 3_00, 2_00),
+      comped,
+      check("Ana", 1, vec![coffee()], 370, 0, 0),
+    ]
+  }
+
+  #[test]
+  fn aggregates_the_day() {
+    let report = DayReport::new(&evening(), 18 * 60);
+
+    assert_eq!(4, report.checks);
+    assert_eq!(10, report.covers);
+    assert_eq!(Money::from_cents(28_00 + 13_00 + 12_00 + 2_50), report.sales);
+    assert_eq!(Money::from_cents(3_00), report.discounts);
+    assert_eq!(Money::from_cents(7_00), report.tips);
+    assert_eq!(Money::from_cents(13_88), report.average_check());
+    assert_eq!(Tally { count: 1, amount: Money::from_cents(4_00) }, report.voids);
+    assert_eq!(Tally { count: 1, amount: Money::from_cents(2_50) }, report.comps);
+
+    let categories: Vec<(&str, u32, i64)> =
+      report.categories.iter().map(|c| (c.category.name(), c.items, c.sales.cents())).collect();
+    assert_eq!(vec![("main", 4, 45_75), ("side", 2, 7_25), ("drink", 1, 2_50)], categories);
+    assert_eq!(report.sales, report.categories.iter().map(|c| c.sales).sum());
+
+    let servers: Vec<(&str, u32, u32, i64, i64)> =
+      report.servers.iter().map(|s| (s.server.as_str(), s.checks, s.covers, s.sales.cents(), s.tips.cents())).collect();
+    assert_eq!(vec![("Ana", 2, 5, 30_50, 5_00), ("Beto", 2, 5, 25_00, 2_00)], servers);
+
+    // La ultima cuenta se cobro pasada la medianoche
+    let hours: Vec<(u32, u32, u32)> = report.hours.iter().map(|h| (h.hour, h.checks, h.covers)).collect();
+    assert_eq!(vec![(18, 1, 4), (19, 1, 3), (20, 1, 2), (0, 1, 1)], hours);
+  }
+
+  #[test]
+  fn exports_csv() {
+    let mut checks = evening();
+    checks.truncate(1);
+    checks[0].server = "Ana, la de la barra".to_string();
+
+    let expected = "\
+seccion,concepto,cantidad,importe
+dia,cuentas,1,
+dia,comensales,4,
+dia,ventas,,28.00
+dia,descuentos,,0.00
+dia,impuestos,,4.48
+dia,propinas,,5.00
+dia,cuenta promedio,,28.00
+dia,anulaciones,0,0.00
+dia,cortesias,0,0.00
+categoria,main,2,24.00
+categoria,side,1,4.00
+mesero,\"Ana, la de la barra\",1,28.00
+propinas,\"Ana, la de la barra\",,5.00
+hora,18:00,4,28.00
+";
+    assert_eq!(expected, DayReport::new(&checks, 18 * 60).to_csv());
+    assert_eq!("\"di \"\"hola\"\"\"", quote("di \"hola\""));
+    assert_eq!("-0.05", decimal(Money::from_cents(-5)));
+  }
+
+  #[test]
+  fn prints_a_table() {
+    let text = DayReport::new(&evening(), 18 * 60).to_string();
+    let lines: Vec<&str> = text.lines().collect();
+
+    assert_eq!("Cuentas: 4, comensales: 10, cuenta promedio: $13.88", lines[1]);
+    assert_eq!("Anulaciones: 1 ($4.00), cortesias: 1 ($2.50)", lines[3]);
+    assert_eq!("main                 4      $45.75", lines[6]);
+    assert_eq!("Ana                    2          5      $30.50      $5.00", lines[11]);
+    assert_eq!("00:00       1          1       $2.50", lines[lines.len() - 1]);
+  }
+
+  #[test]
+  fn a_quiet_day() {
+    let report = DayReport::new(&[], 18 * 60);
+
+    assert_eq!(Money::ZERO, report.average_check());
+    assert!(report.categories.is_empty() && report.hours.is_empty());
+    assert_eq!(10, report.to_csv().lines().count());
+  }
+}
@@ -0,0 +1,555 @@
+/* -------------------- Simulacion de una noche -------------------- */
+/*
+  Una simulacion de eventos discretos: en lugar de avanzar minuto por
+  minuto, se salta al siguiente evento (llega un grupo, un grupo pide,
+  la cocina termina un platillo, un grupo paga) y se atiende.
+
+  Cada grupo sigue el camino completo del modulo:
+
+    hosting::add_to_waitlist -> hosting::seat_at_table
+      -> serving::take_order -> serving::send_to_kitchen
+      -> Kitchen::cook_order -> serving::serve_order
+      -> billing::Bill -> serving::take_payment -> hosting::leave
+
+  Todo lo aleatorio sale de un generador con semilla, asi la misma
+  semilla da la misma noche. De vez en cuando se anula un platillo antes
+  de cocinarlo o se regala uno al cobrar, para que el cierre del dia
+  ("reporting.rs") tenga de todo.
+*/
+
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use std::fmt;
+
+use rand::prng::ChaChaRng;
+use rand::{Rng, SeedableRng};
+
+use crate::back_of_house::inventory::Ingredient;
+use crate::back_of_house::{Kitchen, Ready, Station};
+use crate::front_of_house::billing::{Tender, Tip};
+use crate::front_of_house::hosting::{Host, Seating, Table};
+use crate::front_of_house::serving::{self, Check, Order};
+use crate::menu::{Category, Menu};
+use crate::money::Money;
+
+// 16%
+pub const TAX_RATE: u32 = 1600;
+// Lo que tarda un grupo en pedir despues de sentarse
+pub const ORDER_MINUTES: u32 = 5;
+// El promedio minimo entre llegadas. Con menos casi todas caen en el mismo
+// minuto y el reloj de la simulacion nunca avanza.
+pub const MIN_ARRIVAL_EVERY: f64 = 0.1;
+
+// Las 18:00, en minutos del dia
+pub const OPENS_AT: u32 = 18 * 60;
+
+// Cada quien atiende una seccion de cuatro mesas
+pub const SERVERS: [&str; 3] = ["Ana", "Beto", "Carla"];
+
+// El menu de ejemplo, el mismo de "menu.txt"
+pub const SAMPLE_MENU: &str = include_str!("../menu.txt");
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Config {
+  pub seed: u64,
+  // Minutos en los que siguen llegando grupos
+  pub minutes: u32,
+  // Minutos promedio entre un grupo y el siguiente
+  pub arrival_every: f64,
+  // Si les dicen que esperen mas que esto, se van
+  pub patience: u32,
+  // Minuto del dia en que empieza, para los horarios del menu
+  pub opens_at: u32,
+}
+
+impl Config {
+  // Una noche de 18:00 a 22:00
+  pub fn new(seed: u64) -> Config {
+    Config { seed, minutes: 240, arrival_every: 5.0, patience: 60, opens_at: OPENS_AT }
+  }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Report {
+  pub parties: u32,
+  pub guests: u32,
+  pub seated: u32,
+  // No quisieron esperar lo que se les dijo
+  pub walked_out: u32,
+  // Se sentaron pero ya no habia nada que pudieran pedir
+  pub unserved: u32,
+  pub paid: u32,
+  // Minutos que esperaron en la fila, sumando a todos los que se sentaron
+  pub wait_minutes: u32,
+  // Minutos que cada mesa estuvo ocupada, sumando todas las mesas
+  pub table_minutes: u32,
+  pub tables: u32,
+  // Cuando se fue el ultimo grupo
+  pub closed_at: u32,
+  pub sales: Money,
+  pub tax: Money,
+  pub tips: Money,
+  // En el orden en que se acabaron
+  pub eighty_sixed: Vec<String>,
+  // Las cuentas pagadas, para el cierre del dia
+  pub checks: Vec<Check>,
+}
+
+impl Report {
+  // En minutos
+  pub fn average_wait(&self) -> f64 {
+    if self.seated == 0 {
+      return 0.0;
+    }
+    f64::from(self.wait_minutes) / f64::from(self.seated)
+  }
+
+  // De 0 a 1: que parte del tiempo abierto estuvieron ocupadas las mesas
+  pub fn utilization(&self) -> f64 {
+    let open = self.tables * self.closed_at;
+    if open == 0 {
+      return 0.0;
+    }
+    f64::from(self.table_minutes) / f64::from(open)
+  }
+
+  // Todo lo que se cobro
+  pub fn revenue(&self) -> Money {
+    self.sales + self.tax + self.tips
+  }
+}
+
+impl fmt::Display for Report {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    writeln!(
+      f,
+      "Grupos: {} ({} personas), sentados {}, se fueron {}, sin servicio {}",
+      self.parties, self.guests, self.seated, self.walked_out, self.unserved
+    )?;
+    writeln!(f, "Espera promedio: {:.1} min", self.average_wait())?;
+    writeln!(f, "Ocupacion de mesas: {:.1}%", self.utilization() * 100.0)?;
+    writeln!(f, "Cuentas pagadas: {}", self.paid)?;
+    writeln!(f, "Ventas: {}, impuestos: {}, propinas: {}", self.sales, self.tax, self.tips)?;
+    writeln!(f, "Total cobrado: {}", self.revenue())?;
+    if !self.eighty_sixed.is_empty() {
+      writeln!(f, "Se acabaron: {}", self.eighty_sixed.join(", "))?;
+    }
+    write!(f, "El ultimo grupo se fue a los {} minutos", self.closed_at)
+  }
+}
+
+// El orden importa cuando dos eventos caen en el mismo minuto: primero
+// llegan, despues piden y al final pagan
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Event {
+  Arrive,
+  Order(u32),
+  Pay(u32),
+}
+
+struct Visit {
+  seating: Seating,
+  order: Option<Order>,
+}
+
+pub struct Simulation {
+  config: Config,
+  rng: ChaChaRng,
+  menu: Menu,
+  host: Host,
+  kitchen: Kitchen,
+  // (minuto, numero de evento, evento): el numero desempata en orden de
+  // llegada
+  events: BinaryHeap<Reverse<(u32, u64, Event)>>,
+  scheduled: u64,
+  visits: HashMap<u32, Visit>,
+  report: Report,
+}
+
+impl Simulation {
+  pub fn new(config: Config, menu: Menu, kitchen: Kitchen, tables: Vec<Table>) -> Simulation {
+    assert!(
+      config.arrival_every.is_finite() && config.arrival_every >= MIN_ARRIVAL_EVERY,
+      "los grupos llegan cada {} minutos, tiene que ser al menos {}",
+      config.arrival_every,
+      MIN_ARRIVAL_EVERY
+    );
+    let rng = ChaChaRng::seed_from_u64(config.seed);
+    let report = Report { tables: tables.len() as u32, ..Report::default() };
+
+    Simulation {
+      config,
+      rng,
+      menu,
+      host: Host::new(tables),
+      kitchen,
+      events: BinaryHeap::new(),
+      scheduled: 0,
+      visits: HashMap::new(),
+      report,
+    }
+  }
+
+  pub fn run(mut self) -> Report {
+    let first = self.gap();
+    if first < self.config.minutes {
+      self.schedule(first, Event::Arrive);
+    }
+
+    loop {
+      let next_event = self.events.peek().map(|Reverse((minute, _, _))| *minute);
+      let now = match (next_event, self.kitchen.next_done()) {
+        (Some(event), Some(kitchen)) => event.min(kitchen),
+        (Some(minute), None) | (None, Some(minute)) => minute,
+        (None, None) => break,
+      };
+
+      // Lo que sale de la cocina se sirve antes que cualquier otra cosa
+      // del mismo minuto
+      let elapsed = now - self.kitchen.now();
+      for ready in self.kitchen.advance(elapsed) {
+        self.serve(ready);
+      }
+
+      if next_event == Some(now) {
+        if let Some(Reverse((_, _, event))) = self.events.pop() {
+          match event {
+            Event::Arrive => self.arrive(now),
+            Event::Order(visit) => self.order(visit, now),
+            Event::Pay(visit) => self.pay(visit, now),
+          }
+        }
+      }
+    }
+
+    self.report
+  }
+
+  fn schedule(&mut self, minute: u32, event: Event) {
+    self.scheduled += 1;
+    self.events.push(Reverse((minute, self.scheduled, event)));
+  }
+
+  // Minutos hasta el siguiente grupo: una distribucion exponencial, la
+  // de llegadas que no se ponen de acuerdo entre si
+  fn gap(&mut self) -> u32 {
+    let uniform: f64 = self.rng.gen_range(f64::MIN_POSITIVE, 1.0);
+    (-uniform.ln() * self.config.arrival_every).round() as u32
+  }
+
+  fn arrive(&mut self, now: u32) {
+    const SIZES: [u32; 12] = [1, 2, 2, 2, 2, 3, 3, 4, 4, 4, 5, 6];
+
+    self.report.parties += 1;
+    let size = SIZES[self.rng.gen_range(0, SIZES.len())];
+    let name = format!("Grupo {}", self.report.parties);
+    self.report.guests += size;
+
+    match self.host.add_to_waitlist(&name, size, now) {
+      Ok(quote) if quote <= self.config.patience => {}
+      Ok(_) => {
+        self.host.leave_waitlist(&name);
+        self.report.walked_out += 1;
+      }
+      Err(_) => self.report.walked_out += 1,
+    }
+
+    let next = now + self.gap();
+    if next < self.config.minutes {
+      self.schedule(next, Event::Arrive);
+    }
+
+    self.seat(now);
+  }
+
+  fn seat(&mut self, now: u32) {
+    while let Some(seating) = self.host.seat_at_table(now) {
+      self.report.seated += 1;
+      self.report.wait_minutes += seating.waited;
+
+      let visit = self.report.seated;
+      self.visits.insert(visit, Visit { seating, order: None });
+      self.schedule(now + ORDER_MINUTES, Event::Order(visit));
+    }
+  }
+
+  // Cada quien pide un plato y a veces un acompanamiento, postre y
+  // bebida, de lo que hay a esa hora y alcanza en la cocina
+  fn order(&mut self, visit: u32, now: u32) {
+    let size = self.visits[&visit].seating.size;
+    let mut dishes: Vec<String> = Vec::new();
+
+    for _ in 0..size {
+      let main = if self.rng.gen_bool(0.7) { Category::Main } else { Category::Starter };
+      self.pick(main, now, &mut dishes);
+      for &(category, chance) in [(Category::Side, 0.5), (Category::Dessert, 0.3), (Category::Drink, 0.6)].iter() {
+        if self.rng.gen_bool(chance) {
+          self.pick(category, now, &mut dishes);
+        }
+      }
+    }
+
+    let items = dishes.iter().filter_map(|name| self.menu.get(name).cloned()).collect();
+    let number = self.visits[&visit].seating.tables[0];
+    let table = self.host.tables().iter().copied().find(|table| table.number == number);
+
+    let mut taken = table.and_then(|table| serving::take_order(visit, &table, items).ok());
+    if let Some(order) = taken.as_mut() {
+      order.assign(SERVERS[(number as usize - 1) / 4 % SERVERS.len()], size);
+      // Alguien cambio de opinion
+      if order.items().len() > 1 && self.rng.gen_bool(0.05) {
+        let _ = order.void(order.items().len() - 1);
+      }
+    }
+
+    let sent = taken.and_then(|mut order| {
+      let ticket = serving::send_to_kitchen(&mut order).ok()?;
+      self.kitchen.cook_order(&ticket).ok()?;
+      Some(order)
+    });
+
+    for dish in self.kitchen.inventory().eighty_sixed() {
+      if !self.report.eighty_sixed.iter().any(|known| known == dish) {
+        self.report.eighty_sixed.push(dish.to_string());
+      }
+    }
+    self.kitchen.take_alerts();
+
+    match sent {
+      Some(order) => {
+        if let Some(visit) = self.visits.get_mut(&visit) {
+          visit.order = Some(order);
+        }
+      }
+      None => {
+        self.report.unserved += 1;
+        self.free_table(visit, now);
+      }
+    }
+  }
+
+  fn pick(&mut self, category: Category, now: u32, dishes: &mut Vec<String>) {
+    let minute = (self.config.opens_at + now) % (24 * 60);
+    let inventory = self.kitchen.inventory();
+
+    let choices: Vec<&str> = self
+      .menu
+      .category(category)
+      .filter(|item| item.is_available(minute))
+      .filter(|item| {
+        let mut together = dishes.clone();
+        together.push(item.name.clone());
+        inventory.can_make_all(&together)
+      })
+      .map(|item| item.name.as_str())
+      .collect();
+
+    if !choices.is_empty() {
+      let choice = choices[self.rng.gen_range(0, choices.len())].to_string();
+      dishes.push(choice);
+    }
+  }
+
+  // Comen entre 25 y 45 minutos y piden la cuenta
+  fn serve(&mut self, ready: Ready) {
+    let served = self
+      .visits
+      .get_mut(&ready.order)
+      .and_then(|visit| visit.order.as_mut())
+      .map(|order| serving::serve_order(order).is_ok())
+      .unwrap_or(false);
+
+    if served {
+      let eating = self.rng.gen_range(25, 46);
+      self.schedule(ready.at + eating, Event::Pay(ready.order));
+    }
+  }
+
+  fn pay(&mut self, visit: u32, now: u32) {
+    const TIPS: [u32; 4] = [1000, 1500, 1800, 2000];
+    let tip = TIPS[self.rng.gen_range(0, TIPS.len())];
+
+    // Algo no salio bien y la casa invita
+    let comp = self.rng.gen_bool(0.05);
+
+    if let Some(order) = self.visits.get_mut(&visit).and_then(|visit| visit.order.as_mut()) {
+      if comp {
+        let _ = order.comp(0);
+      }
+      let mut bill = order.bill(TAX_RATE);
+      bill.tip(Tip::Percent(tip)).expect("un porcentaje nunca es negativo");
+      let balance = bill.balance();
+
+      // Si todo se regalo no hay nada que pagar
+      let paid = if balance == Money::ZERO || bill.pay(Tender::Card(balance)).is_ok() {
+        serving::take_payment(order, &bill, now).ok()
+      } else {
+        None
+      };
+      if let Some(check) = paid {
+        let totals = check.totals;
+        self.report.paid += 1;
+        self.report.sales += totals.subtotal - totals.discount;
+        self.report.tax += totals.tax;
+        self.report.tips += totals.tip;
+        self.report.checks.push(check);
+      }
+    }
+
+    self.free_table(visit, now);
+  }
+
+  fn free_table(&mut self, visit: u32, now: u32) {
+    if let Some(visit) = self.visits.remove(&visit) {
+      let seating = visit.seating;
+      if self.host.leave(seating.tables[0]).is_ok() {
+        self.report.table_minutes += seating.tables.len() as u32 * (now - seating.since);
+      }
+    }
+
+    self.report.closed_at = self.report.closed_at.max(now);
+    self.seat(now);
+  }
+}
+
+// Mesas para dos en la barra, para cuatro en el salon y una grande
+pub fn floor() -> Vec<Table> {
+  let seats = [2, 2, 2, 2, 4, 4, 4, 4, 4, 6];
+  seats.iter().enumerate().map(|(i, &seats)| Table::new(i as u32 + 1, seats)).collect()
+}
+
+// Una cocina para el menu: cada platillo va a la estacion de su
+// categoria, y los del menu de ejemplo tienen receta. Cada estacion
+// hace un platillo a la vez, por eso los tiempos son por platillo.
+pub fn kitchen(menu: &Menu) -> Kitchen {
+  let mut kitchen = Kitchen::new();
+
+  for item in menu.items() {
+    let (station, prep) = match item.category {
+      Category::Main => (Station::Grill, 4),
+      Category::Side => (Station::Fryer, 3),
+      Category::Starter => (Station::Salad, 3),
+      Category::Dessert => (Station::Salad, 1),
+      Category::Drink => (Station::Salad, 1),
+    };
+    kitchen.route(&item.name, station, prep);
+  }
+
+  let inventory = kitchen.inventory_mut();
+  let ingredients = [
+    ("carne", 9_000, 12_00, 1000, 1_500),
+    ("pan", 60, 40, 1, 10),
+    ("queso", 3_000, 10_00, 1000, 500),
+    ("lechuga", 4_000, 3_00, 1000, 600),
+    ("tortilla", 300, 10, 1, 50),
+    ("huevo", 60, 30, 1, 12),
+    ("leche", 8_000, 2_00, 1000, 1_000),
+    ("papa", 15_000, 2_00, 1000, 2_000),
+    ("cebolla", 4_000, 2_50, 1000, 600),
+    ("harina", 5_000, 1_50, 1000, 500),
+    ("cafe", 1_500, 25_00, 1000, 200),
+  ];
+  for &(name, stock, cost, per, threshold) in ingredients.iter() {
+    inventory.add(Ingredient::new(name, stock, Money::from_cents(cost), per, threshold));
+  }
+
+  let recipes: [(&str, &[(&str, u32)]); 7] = [
+    ("Hamburguesa", &[("carne", 150), ("pan", 1), ("queso", 30)]),
+    ("Ensalada", &[("lechuga", 150), ("queso", 20), ("harina", 20)]),
+    ("Chilaquiles", &[("tortilla", 6), ("huevo", 2), ("queso", 40)]),
+    ("Papas", &[("papa", 250)]),
+    ("Aros de cebolla", &[("cebolla", 200), ("harina", 60), ("huevo", 1)]),
+    ("Flan", &[("huevo", 2), ("leche", 150)]),
+    ("Cafe", &[("cafe", 15)]),
+  ];
+  for &(dish, recipe) in recipes.iter() {
+    inventory.recipe(dish, recipe).expect("every ingredient is in the inventory");
+  }
+
+  kitchen
+}
+
+// La noche con el menu y la cocina de ejemplo
+pub fn sample(config: Config) -> Simulation {
+  let menu = Menu::parse(SAMPLE_MENU).expect("menu.txt is valid");
+  let kitchen = kitchen(&menu);
+
+  Simulation::new(config, menu, kitchen, floor())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::reporting::DayReport;
+
+  #[test]
+  fn same_seed_same_night() {
+    let report = sample(Config::new(7)).run();
+
+    assert_eq!(report, sample(Config::new(7)).run());
+    assert_ne!(report, sample(Config::new(8)).run());
+  }
+
+  #[test]
+  fn every_party_is_accounted_for() {
+    for seed in 0..20 {
+      let report = sample(Config::new(seed)).run();
+
+      assert!(report.parties > 0, "seed {}", seed);
+      assert_eq!(report.parties, report.seated + report.walked_out, "seed {}", seed);
+      assert_eq!(report.seated, report.paid + report.unserved, "seed {}", seed);
+      assert!(report.utilization() <= 1.0, "seed {}", seed);
+      assert!(report.closed_at >= report.table_minutes / report.tables, "seed {}", seed);
+    }
+  }
+
+  #[test]
+  fn revenue_adds_up() {
+    let report = sample(Config::new(3)).run();
+
+    assert!(report.sales > Money::ZERO);
+    // El impuesto es el 16% de lo vendido, redondeado cuenta por cuenta
+    let tax = report.sales.percent(TAX_RATE).cents();
+    assert!((report.tax.cents() - tax).abs() <= i64::from(report.paid));
+    assert_eq!(report.sales + report.tax + report.tips, report.revenue());
+  }
+
+  #[test]
+  fn the_day_closes_with_every_check() {
+    let config = Config::new(5);
+    let report = sample(config.clone()).run();
+    let day = DayReport::new(&report.checks, config.opens_at);
+
+    assert_eq!(report.paid, day.checks);
+    assert_eq!((report.sales, report.tax, report.tips), (day.sales, day.tax, day.tips));
+    assert!(day.comps.count > 0 || day.voids.count > 0);
+    assert_eq!(SERVERS.len(), day.servers.len());
+    assert!(day.hours.iter().all(|hour| (18..=23).contains(&hour.hour) || hour.hour < 2));
+  }
+
+  #[test]
+  fn a_busy_night_runs_out_of_something() {
+    let config = Config { arrival_every: 2.0, ..Config::new(11) };
+    let report = sample(config).run();
+
+    assert!(!report.eighty_sixed.is_empty());
+    assert!(report.walked_out > 0);
+  }
+
+  #[test]
+  #[should_panic(expected = "al menos 0.1")]
+  fn groups_cannot_arrive_all_at_once() {
+    sample(Config { arrival_every: 0.0, ..Config::new(1) });
+  }
+
+  #[test]
+  fn nobody_comes() {
+    let config = Config { minutes: 0, ..Config::new(1) };
+    let report = sample(config).run();
+
+    assert_eq!(0, report.parties);
+    assert_eq!(0.0, report.utilization());
+    assert_eq!(0.0, report.average_wait());
+    assert_eq!(Money::ZERO, report.revenue());
+  }
+}
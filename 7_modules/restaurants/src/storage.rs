@@ -0,0 +1,825 @@
+/* -------------------- Bitacora en disco -------------------- */
+/*
+  Todo lo que cambia el estado del restaurante (mesas, ordenes y pagos)
+  es un "Event". Antes de escribirlo se aplica en memoria, asi un evento
+  invalido nunca llega al archivo; despues se agrega al final de la
+  bitacora y se sincroniza con el disco.
+
+  Al abrir, el estado se reconstruye aplicando los eventos en el mismo
+  orden. Funciona porque "Host" y las ordenes son deterministas: los
+  mismos eventos dan las mismas mesas y los mismos numeros.
+
+  La primera linea dice la version del formato y despues va un registro
+  por linea:
+
+    restaurants log 1
+    <crc32 en hex> <campo>|<campo>|...
+
+  Los campos escapan "\" como "\\", "|" como "\p" y los saltos de linea
+  como "\n". Si el proceso muere a medio escribir, la ultima linea queda
+  sin salto de linea o con otro checksum: se descarta y el archivo se
+  corta ahi. Un registro danado que no es el ultimo no se puede haber
+  cortado al escribir, asi que eso es un error y no se toca nada. Tampoco
+  se corta un registro con el checksum correcto que no se entiende: se
+  escribio completo, y borrarlo seria perder datos.
+
+  Un archivo sin el encabezado no se toca: no es una bitacora, o es de
+  otra version que esta no sabe leer.
+*/
+
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+
+use crate::front_of_house::billing::{BillError, Discount, Tender, Tip};
+use crate::front_of_house::hosting::{Host, HostingError, Reservation, Seating, Table};
+use crate::front_of_house::serving::{self, Check, Order, OrderError, Ticket};
+use crate::menu::{Category, MenuItem};
+use crate::money::Money;
+
+// La primera linea de cada bitacora
+const HEADER: &str = "restaurants log 1";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Event {
+  Waitlist { name: String, size: u32, at: u32 },
+  // Sienta a quien siga en la fila, si cabe
+  Seat { at: u32 },
+  Leave { table: u32 },
+  Reserve { name: String, size: u32, at: u32 },
+  CheckIn { reservation: u32, at: u32 },
+  Expire { at: u32 },
+  // Los comensales son los del grupo sentado en la mesa
+  TakeOrder { order: u32, table: u32, server: String, items: Vec<(String, Category, Money)> },
+  SendToKitchen { order: u32 },
+  // "item" es la posicion del platillo en la orden
+  Void { order: u32, item: u32 },
+  Comp { order: u32, item: u32 },
+  Serve { order: u32 },
+  // La tasa en puntos base
+  Pay { order: u32, at: u32, tax_rate: u32, discount: Money, tip: Money, tenders: Vec<Tender> },
+}
+
+// Lo que devolvio cada evento al aplicarse
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+  Quoted(u32),
+  Seated(Option<Seating>),
+  Left(Seating),
+  Reserved(u32),
+  CheckedIn,
+  Expired(Vec<Reservation>),
+  Taken,
+  Ticket(Ticket),
+  Voided(MenuItem),
+  Comped,
+  Served,
+  Paid(Payment),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Payment {
+  pub check: Check,
+  pub tenders: Vec<Tender>,
+}
+
+#[derive(Debug)]
+pub enum StoreError {
+  Hosting(HostingError),
+  Order(OrderError),
+  Bill(BillError),
+  UnknownOrder(u32),
+  UnknownTable(u32),
+  DuplicateOrder(u32),
+  // El evento se aplico pero no se pudo escribir: hay que volver a abrir
+  // la bitacora para que memoria y disco coincidan
+  Io(io::Error),
+}
+
+impl fmt::Display for StoreError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      StoreError::Hosting(e) => write!(f, "{}", e),
+      StoreError::Order(e) => write!(f, "{}", e),
+      StoreError::Bill(e) => write!(f, "{}", e),
+      StoreError::UnknownOrder(order) => write!(f, "no existe la orden {}", order),
+      StoreError::UnknownTable(table) => write!(f, "no existe la mesa {}", table),
+      StoreError::DuplicateOrder(order) => write!(f, "la orden {} ya existe", order),
+      StoreError::Io(e) => write!(f, "no se pudo escribir la bitacora: {}", e),
+    }
+  }
+}
+
+impl Error for StoreError {}
+
+impl From<HostingError> for StoreError {
+  fn from(e: HostingError) -> StoreError {
+    StoreError::Hosting(e)
+  }
+}
+
+impl From<OrderError> for StoreError {
+  fn from(e: OrderError) -> StoreError {
+    StoreError::Order(e)
+  }
+}
+
+impl From<BillError> for StoreError {
+  fn from(e: BillError) -> StoreError {
+    StoreError::Bill(e)
+  }
+}
+
+/* ---- Estado en memoria ---- */
+
+pub struct Restaurant {
+  host: Host,
+  orders: BTreeMap<u32, Order>,
+  payments: Vec<Payment>,
+}
+
+impl Restaurant {
+  pub fn new(tables: Vec<Table>) -> Restaurant {
+    Restaurant { host: Host::new(tables), orders: BTreeMap::new(), payments: Vec::new() }
+  }
+
+  pub fn host(&self) -> &Host {
+    &self.host
+  }
+
+  pub fn orders(&self) -> impl Iterator<Item = &Order> {
+    self.orders.values()
+  }
+
+  pub fn order(&self, order: u32) -> Option<&Order> {
+    self.orders.get(&order)
+  }
+
+  pub fn payments(&self) -> &[Payment] {
+    &self.payments
+  }
+
+  // Para el cierre del dia
+  pub fn checks(&self) -> impl Iterator<Item = &Check> {
+    self.payments.iter().map(|payment| &payment.check)
+  }
+
+  // Todo o nada: si hay error el estado no cambia
+  pub fn apply(&mut self, event: &Event) -> Result<Outcome, StoreError> {
+    let outcome = match event {
+      Event::Waitlist { name, size, at } => Outcome::Quoted(self.host.add_to_waitlist(name, *size, *at)?),
+      Event::Seat { at } => Outcome::Seated(self.host.seat_at_table(*at)),
+      Event::Leave { table } => Outcome::Left(self.host.leave(*table)?),
+      Event::Reserve { name, size, at } => Outcome::Reserved(self.host.reserve(name, *size, *at)?),
+      Event::CheckIn { reservation, at } => {
+        self.host.check_in(*reservation, *at)?;
+        Outcome::CheckedIn
+      }
+      Event::Expire { at } => Outcome::Expired(self.host.expire(*at)),
+      Event::TakeOrder { order, table, server, items } => {
+        if self.orders.contains_key(order) {
+          return Err(StoreError::DuplicateOrder(*order));
+        }
+        let table = self
+          .host
+          .tables()
+          .iter()
+          .find(|t| t.number == *table)
+          .ok_or(StoreError::UnknownTable(*table))?;
+
+        let items = items
+          .iter()
+          .map(|(name, category, price)| MenuItem { category: *category, ..MenuItem::new(name, price.cents()) })
+          .collect();
+        let mut taken = serving::take_order(*order, table, items)?;
+        taken.assign(server, self.host.seating_at(table.number).map_or(0, |seating| seating.size));
+        self.orders.insert(*order, taken);
+        Outcome::Taken
+      }
+      Event::SendToKitchen { order } => Outcome::Ticket(serving::send_to_kitchen(self.order_mut(*order)?)?),
+      Event::Void { order, item } => Outcome::Voided(self.order_mut(*order)?.void(*item as usize)?),
+      Event::Comp { order, item } => {
+        self.order_mut(*order)?.comp(*item as usize)?;
+        Outcome::Comped
+      }
+      Event::Serve { order } => {
+        serving::serve_order(self.order_mut(*order)?)?;
+        Outcome::Served
+      }
+      Event::Pay { order, at, tax_rate, discount, tip, tenders } => {
+        let order = self.orders.get_mut(order).ok_or(StoreError::UnknownOrder(*order))?;
+
+        let mut bill = order.bill(*tax_rate);
+        if *discount != Money::ZERO {
+          bill.discount(Discount::Amount(*discount))?;
+        }
+        if *tip != Money::ZERO {
+          bill.tip(Tip::Amount(*tip))?;
+        }
+        for &tender in tenders {
+          bill.pay(tender)?;
+        }
+        let check = serving::take_payment(order, &bill, *at)?;
+
+        let payment = Payment { check, tenders: tenders.clone() };
+        self.payments.push(payment.clone());
+        Outcome::Paid(payment)
+      }
+    };
+
+    Ok(outcome)
+  }
+
+  fn order_mut(&mut self, order: u32) -> Result<&mut Order, StoreError> {
+    self.orders.get_mut(&order).ok_or(StoreError::UnknownOrder(order))
+  }
+}
+
+/* ---- La bitacora ---- */
+
+pub struct Store {
+  path: PathBuf,
+  // Para reconstruir el estado en "reopen"
+  tables: Vec<Table>,
+  file: File,
+  restaurant: Restaurant,
+  events: usize,
+  truncated: u64,
+}
+
+impl Store {
+  // Abre la bitacora (o la crea) y reconstruye el estado
+  pub fn open<P: AsRef<Path>>(path: P, tables: Vec<Table>) -> io::Result<Store> {
+    let path = path.as_ref();
+    let mut file = OpenOptions::new().read(true).append(true).create(true).open(path)?;
+
+    let mut bytes = Vec::new();
+    file.read_to_end(&mut bytes)?;
+    let header = format!("{}\n", HEADER);
+    if header.as_bytes().starts_with(&bytes) {
+      // Nueva, o el encabezado quedo a medio escribir
+      file.set_len(0)?;
+      file.write_all(header.as_bytes())?;
+      file.sync_all()?;
+    } else if !bytes.starts_with(header.as_bytes()) {
+      let first = bytes.split(|&byte| byte == b'\n').next().unwrap_or_default();
+      return Err(invalid(format!("no es una bitacora de \"{}\": {}", HEADER, String::from_utf8_lossy(first))));
+    }
+    let start = header.len().min(bytes.len());
+    let (events, valid) = recover(&bytes, start)?;
+
+    let truncated = (bytes.len() - valid) as u64;
+    if truncated > 0 {
+      file.set_len(valid as u64)?;
+      file.sync_all()?;
+    }
+
+    let mut restaurant = Restaurant::new(tables.clone());
+    for (i, event) in events.iter().enumerate() {
+      restaurant.apply(event).map_err(|e| invalid(format!("el registro {} no se puede aplicar: {}", i + 1, e)))?;
+    }
+
+    Ok(Store { path: path.to_path_buf(), tables, file, restaurant, events: events.len(), truncated })
+  }
+
+  // Vuelve a abrir la bitacora y a reconstruir el estado. Despues de un
+  // "StoreError::Io" el estado en memoria puede tener un evento que no se
+  // guardo, o el archivo medio registro que hay que cortar.
+  pub fn reopen(&mut self) -> io::Result<()> {
+    *self = Store::open(&self.path, self.tables.clone())?;
+    Ok(())
+  }
+
+  pub fn restaurant(&self) -> &Restaurant {
+    &self.restaurant
+  }
+
+  // Cuantos eventos hay en la bitacora
+  pub fn events(&self) -> usize {
+    self.events
+  }
+
+  // Los bytes del registro incompleto que se corto al abrir
+  pub fn truncated(&self) -> u64 {
+    self.truncated
+  }
+
+  pub fn execute(&mut self, event: Event) -> Result<Outcome, StoreError> {
+    let outcome = self.restaurant.apply(&event)?;
+
+    self.file.write_all(record(&event).as_bytes()).map_err(StoreError::Io)?;
+    self.file.sync_data().map_err(StoreError::Io)?;
+    self.events += 1;
+
+    Ok(outcome)
+  }
+}
+
+fn record(event: &Event) -> String {
+  let payload = encode(event);
+  format!("{:08x} {}\n", crc32(payload.as_bytes()), payload)
+}
+
+// Los eventos buenos y hasta que byte llegan
+fn recover(bytes: &[u8], start: usize) -> io::Result<(Vec<Event>, usize)> {
+  let mut events = Vec::new();
+  let mut start = start;
+
+  while start < bytes.len() {
+    let end = match bytes[start..].iter().position(|&byte| byte == b'\n') {
+      Some(newline) => start + newline,
+      // Sin salto de linea: se corto a medio escribir
+      None => break,
+    };
+
+    let line = events.len() + 1;
+    match verify(&bytes[start..end]) {
+      Some(payload) => {
+        let event = decode(payload).ok_or_else(|| invalid(format!("el registro {} no se entiende: {}", line, payload)))?;
+        events.push(event);
+      }
+      None if end + 1 == bytes.len() => break,
+      None => return Err(invalid(format!("el registro {} esta danado y no es el ultimo", line))),
+    }
+    start = end + 1;
+  }
+
+  Ok((events, start))
+}
+
+// El contenido del registro si su checksum cuadra
+fn verify(line: &[u8]) -> Option<&str> {
+  let line = std::str::from_utf8(line).ok()?;
+  let (checksum, payload) = line.split_once(' ')?;
+
+  if u32::from_str_radix(checksum, 16).ok()? != crc32(payload.as_bytes()) {
+    return None;
+  }
+  Some(payload)
+}
+
+fn invalid(message: String) -> io::Error {
+  io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+// CRC-32 de IEEE, el de zip y ethernet
+fn crc32(bytes: &[u8]) -> u32 {
+  let mut crc = !0u32;
+
+  for &byte in bytes {
+    crc ^= u32::from(byte);
+    for _ in 0..8 {
+      let mask = (crc & 1).wrapping_neg();
+      crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+    }
+  }
+
+  !crc
+}
+
+/* ---- Formato de los eventos ---- */
+
+fn encode(event: &Event) -> String {
+  let mut fields: Vec<String> = Vec::new();
+  let mut push = |field: &dyn fmt::Display| fields.push(field.to_string());
+
+  match event {
+    Event::Waitlist { name, size, at } => {
+      push(&"waitlist");
+      push(name);
+      push(size);
+      push(at);
+    }
+    Event::Seat { at } => {
+      push(&"seat");
+      push(at);
+    }
+    Event::Leave { table } => {
+      push(&"leave");
+      push(table);
+    }
+    Event::Reserve { name, size, at } => {
+      push(&"reserve");
+      push(name);
+      push(size);
+      push(at);
+    }
+    Event::CheckIn { reservation, at } => {
+      push(&"check-in");
+      push(reservation);
+      push(at);
+    }
+    Event::Expire { at } => {
+      push(&"expire");
+      push(at);
+    }
+    Event::TakeOrder { order, table, server, items } => {
+      push(&"order");
+      push(order);
+      push(table);
+      push(server);
+      for (name, category, price) in items {
+        push(name);
+        push(&category.name());
+        push(&price.cents());
+      }
+    }
+    Event::SendToKitchen { order } => {
+      push(&"kitchen");
+      push(order);
+    }
+    Event::Void { order, item } => {
+      push(&"void");
+      push(order);
+      push(item);
+    }
+    Event::Comp { order, item } => {
+      push(&"comp");
+      push(order);
+      push(item);
+    }
+    Event::Serve { order } => {
+      push(&"serve");
+      push(order);
+    }
+    Event::Pay { order, at, tax_rate, discount, tip, tenders } => {
+      push(&"pay");
+      push(order);
+      push(at);
+      push(tax_rate);
+      push(&discount.cents());
+      push(&tip.cents());
+      for tender in tenders {
+        let (kind, amount) = match tender {
+          Tender::Cash(amount) => ("cash", amount),
+          Tender::Card(amount) => ("card", amount),
+        };
+        push(&kind);
+        push(&amount.cents());
+      }
+    }
+  }
+
+  fields.iter().map(|field| escape(field)).collect::<Vec<_>>().join("|")
+}
+
+fn decode(payload: &str) -> Option<Event> {
+  let fields = unescape(payload)?;
+  let number = |i: usize| fields.get(i)?.parse::<u32>().ok();
+  let money = |i: usize| fields.get(i)?.parse::<i64>().ok().map(Money::from_cents);
+  let count = |n: usize| if fields.len() == n { Some(()) } else { None };
+
+  let event = match fields.first()?.as_str() {
+    "waitlist" => {
+      count(4)?;
+      Event::Waitlist { name: fields[1].clone(), size: number(2)?, at: number(3)? }
+    }
+    "seat" => {
+      count(2)?;
+      Event::Seat { at: number(1)? }
+    }
+    "leave" => {
+      count(2)?;
+      Event::Leave { table: number(1)? }
+    }
+    "reserve" => {
+      count(4)?;
+      Event::Reserve { name: fields[1].clone(), size: number(2)?, at: number(3)? }
+    }
+    "check-in" => {
+      count(3)?;
+      Event::CheckIn { reservation: number(1)?, at: number(2)? }
+    }
+    "expire" => {
+      count(2)?;
+      Event::Expire { at: number(1)? }
+    }
+    "order" if fields.len() >= 4 && (fields.len() - 4) % 3 == 0 => {
+      let items = (4..fields.len())
+        .step_by(3)
+        .map(|i| Some((fields[i].clone(), Category::parse(&fields[i + 1])?, money(i + 2)?)))
+        .collect::<Option<_>>()?;
+      Event::TakeOrder { order: number(1)?, table: number(2)?, server: fields[3].clone(), items }
+    }
+    "kitchen" => {
+      count(2)?;
+      Event::SendToKitchen { order: number(1)? }
+    }
+    "void" => {
+      count(3)?;
+      Event::Void { order: number(1)?, item: number(2)? }
+    }
+    "comp" => {
+      count(3)?;
+      Event::Comp { order: number(1)?, item: number(2)? }
+    }
+    "serve" => {
+      count(2)?;
+      Event::Serve { order: number(1)? }
+    }
+    "pay" if fields.len() % 2 == 0 => {
+      let tenders = (6..fields.len())
+        .step_by(2)
+        .map(|i| match fields[i].as_str() {
+          "cash" => Some(Tender::Cash(money(i + 1)?)),
+          "card" => Some(Tender::Card(money(i + 1)?)),
+          _ => None,
+        })
+        .collect::<Option<_>>()?;
+      Event::Pay { order: number(1)?, at: number(2)?, tax_rate: number(3)?, discount: money(4)?, tip: money(5)?, tenders }
+    }
+    _ => return None,
+  };
+
+  Some(event)
+}
+
+fn escape(field: &str) -> String {
+  let mut escaped = String::with_capacity(field.len());
+
+  for c in field.chars() {
+    match c {
+      '\\' => escaped.push_str("\\\\"),
+      '|' => escaped.push_str("\\p"),
+      '\n' => escaped.push_str("\\n"),
+      '\r' => escaped.push_str("\\r"),
+      _ => escaped.push(c),
+    }
+  }
+
+  escaped
+}
+
+// None si hay un escape que no existe
+fn unescape(payload: &str) -> Option<Vec<String>> {
+  let mut fields = vec![String::new()];
+  let mut chars = payload.chars();
+
+  while let Some(c) = chars.next() {
+    let field = fields.last_mut()?;
+    match c {
+      '|' => fields.push(String::new()),
+      '\\' => field.push(match chars.next()? {
+        '\\' => '\\',
+        'p' => '|',
+        'n' => '\n',
+        'r' => '\r',
+        _ => return None,
+      }),
+      _ => field.push(c),
+    }
+  }
+
+  Some(fields)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::front_of_house::serving::OrderStatus;
+  use std::env;
+  use std::fs;
+  use std::path::PathBuf;
+
+  fn temp_path(name: &str) -> PathBuf {
+    let path = env::temp_dir().join(format!("restaurants-{}-{}", std::process::id(), name));
+    let _ = fs::remove_file(&path);
+    path
+  }
+
+  fn tables() -> Vec<Table> {
+    vec![Table::new(1, 2), Table::new(2, 4)]
+  }
+
+  fn money(cents: i64) -> Money {
+    Money::from_cents(cents)
+  }
+
+  // Ana se sienta, pide, le sirven y paga; Luis sigue comiendo
+  fn evening() -> Vec<Event> {
+    vec![
+      Event::Waitlist { name: "Ana".to_string(), size: 2, at: 0 },
+      Event::Waitlist { name: "Luis | familia".to_string(), size: 4, at: 1 },
+      Event::Seat { at: 2 },
+      Event::Seat { at: 2 },
+      Event::TakeOrder {
+        order: 1,
+        table: 1,
+        server: "Beto".to_string(),
+        items: vec![("Tacos".to_string(), Category::Main, money(8_50)), ("Agua".to_string(), Category::Drink, money(2_00))],
+      },
+      Event::TakeOrder { order: 2, table: 2, server: "Beto".to_string(), items: vec![("Sopa".to_string(), Category::Starter, money(6_00))] },
+      Event::SendToKitchen { order: 1 },
+      Event::SendToKitchen { order: 2 },
+      Event::Void { order: 1, item: 1 },
+      Event::Serve { order: 1 },
+      Event::Pay { order: 1, at: 50, tax_rate: 1600, discount: money(50), tip: money(1_00), tenders: vec![Tender::Cash(money(10_28))] },
+      Event::Leave { table: 1 },
+    ]
+  }
+
+  #[test]
+  fn checksums() {
+    assert_eq!(0xCBF4_3926, crc32(b"123456789"));
+    assert_eq!(0, crc32(b""));
+  }
+
+  #[test]
+  fn every_event_round_trips() {
+    let mut events = evening();
+    events.push(Event::Reserve { name: "a\\b\nc".to_string(), size: 3, at: 90 });
+    events.push(Event::CheckIn { reservation: 1, at: 80 });
+    events.push(Event::Expire { at: 120 });
+    events.push(Event::Comp { order: 2, item: 0 });
+    events.push(Event::Pay {
+      order: 3,
+      at: 0,
+      tax_rate: 0,
+      discount: Money::ZERO,
+      tip: Money::ZERO,
+      tenders: vec![Tender::Card(money(5_00)), Tender::Cash(money(-1))],
+    });
+    events.push(Event::TakeOrder { order: 4, table: 1, server: String::new(), items: Vec::new() });
+
+    for event in events {
+      let payload = encode(&event);
+      assert!(!payload.contains('\n'), "{}", payload);
+      assert_eq!(Some(event), decode(&payload));
+    }
+
+    assert_eq!("waitlist|Luis \\p familia|4|1", encode(&evening()[1]));
+    assert_eq!(None, decode("seat|1|2"));
+    assert_eq!(None, decode("order|1|2|Ana|Tacos|main"));
+    assert_eq!(None, decode("order|1|2|Ana|Tacos|sopa|100"));
+    assert_eq!(None, decode("pay|1|0|0|0|0|cheque|100"));
+    assert_eq!(None, decode("leave|\\x"));
+  }
+
+  #[test]
+  fn state_survives_a_restart() {
+    let path = temp_path("restart");
+    {
+      let mut store = Store::open(&path, tables()).unwrap();
+      for event in evening() {
+        store.execute(event).unwrap();
+      }
+    }
+
+    let mut store = Store::open(&path, tables()).unwrap();
+    let restaurant = store.restaurant();
+    assert_eq!(12, store.events());
+    assert_eq!(0, store.truncated());
+    assert_eq!(None, restaurant.host().seating_at(1));
+    assert_eq!("Luis | familia", restaurant.host().seating_at(2).unwrap().name);
+    assert_eq!(OrderStatus::Paid, restaurant.order(1).unwrap().status());
+    assert_eq!(OrderStatus::Cooking, restaurant.order(2).unwrap().status());
+    let check = restaurant.checks().next().unwrap();
+    assert_eq!(money(10_28), check.totals.total);
+    assert_eq!(("Beto", 2, 50), (check.server.as_str(), check.covers, check.paid_at));
+    assert_eq!("Agua", check.voids[0].name);
+
+    // Y se sigue escribiendo donde se quedo
+    store.execute(Event::Serve { order: 2 }).unwrap();
+    drop(store);
+    let store = Store::open(&path, tables()).unwrap();
+    assert_eq!(OrderStatus::Served, store.restaurant().order(2).unwrap().status());
+
+    fs::remove_file(&path).unwrap();
+  }
+
+  #[test]
+  fn invalid_events_are_not_written() {
+    let path = temp_path("invalid");
+    let mut store = Store::open(&path, tables()).unwrap();
+
+    assert!(matches!(store.execute(Event::Serve { order: 7 }), Err(StoreError::UnknownOrder(7))));
+    assert!(matches!(store.execute(Event::Leave { table: 1 }), Err(StoreError::Hosting(HostingError::TableFree(1)))));
+    let nowhere = Event::TakeOrder { order: 1, table: 9, server: String::new(), items: vec![("Agua".to_string(), Category::Drink, money(1_00))] };
+    assert!(matches!(store.execute(nowhere), Err(StoreError::UnknownTable(9))));
+
+    assert_eq!(0, store.events());
+    assert_eq!(format!("{}\n", HEADER), fs::read_to_string(&path).unwrap());
+
+    fs::remove_file(&path).unwrap();
+  }
+
+  #[test]
+  fn torn_records_are_cut_off() {
+    let path = temp_path("torn");
+    {
+      let mut store = Store::open(&path, tables()).unwrap();
+      for event in evening().into_iter().take(3) {
+        store.execute(event).unwrap();
+      }
+    }
+    let good = fs::metadata(&path).unwrap().len();
+
+    // El proceso murio a medio escribir
+    let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+    file.write_all(b"1b2c3d4e seat|").unwrap();
+    drop(file);
+
+    let mut store = Store::open(&path, tables()).unwrap();
+    assert_eq!(3, store.events());
+    assert_eq!(14, store.truncated());
+    assert_eq!(good, fs::metadata(&path).unwrap().len());
+    assert_eq!("Ana", store.restaurant().host().seating_at(1).unwrap().name);
+
+    store.execute(Event::Seat { at: 3 }).unwrap();
+    drop(store);
+    assert_eq!(4, Store::open(&path, tables()).unwrap().events());
+
+    fs::remove_file(&path).unwrap();
+  }
+
+  #[test]
+  fn reopening_forgets_what_was_not_written() {
+    let path = temp_path("reopen");
+    let mut store = Store::open(&path, tables()).unwrap();
+    for event in evening().into_iter().take(3) {
+      store.execute(event).unwrap();
+    }
+    let good = fs::metadata(&path).unwrap().len();
+    store.execute(Event::Seat { at: 3 }).unwrap();
+    assert!(store.restaurant().host().seating_at(2).is_some());
+
+    // Como si el disco solo hubiera guardado parte del ultimo registro
+    OpenOptions::new().write(true).open(&path).unwrap().set_len(good + 5).unwrap();
+    store.reopen().unwrap();
+    assert_eq!((3, 5), (store.events(), store.truncated()));
+    assert_eq!(None, store.restaurant().host().seating_at(2));
+
+    store.execute(Event::Seat { at: 4 }).unwrap();
+    drop(store);
+    assert_eq!(4, Store::open(&path, tables()).unwrap().events());
+
+    fs::remove_file(&path).unwrap();
+  }
+
+  // Un registro con su checksum
+  fn line(payload: &str) -> String {
+    format!("{:08x} {}\n", crc32(payload.as_bytes()), payload)
+  }
+
+  #[test]
+  fn a_bad_checksum_at_the_end_is_torn() {
+    let path = temp_path("checksum");
+    fs::write(&path, format!("{}\n{}00000000 seat|2\n", HEADER, line("seat|1"))).unwrap();
+
+    let store = Store::open(&path, tables()).unwrap();
+    assert_eq!(1, store.events());
+    assert_eq!(16, store.truncated());
+
+    fs::remove_file(&path).unwrap();
+  }
+
+  #[test]
+  fn complete_records_are_never_cut() {
+    let path = temp_path("unknown");
+    let text = format!("{}\n{}{}", HEADER, line("seat|1"), line("dance|1"));
+    fs::write(&path, &text).unwrap();
+
+    let error = Store::open(&path, tables()).err().unwrap();
+    assert_eq!(io::ErrorKind::InvalidData, error.kind());
+    assert!(error.to_string().contains("el registro 2 no se entiende"), "{}", error);
+    assert_eq!(text, fs::read_to_string(&path).unwrap());
+
+    fs::remove_file(&path).unwrap();
+  }
+
+  #[test]
+  fn damage_in_the_middle_is_an_error() {
+    let path = temp_path("damaged");
+    let text = format!("{}\n{}00000000 seat|2\n{}", HEADER, line("seat|1"), line("seat|1"));
+    fs::write(&path, &text).unwrap();
+
+    let error = Store::open(&path, tables()).err().unwrap();
+    assert_eq!(io::ErrorKind::InvalidData, error.kind());
+    // No se corto nada
+    assert_eq!(text, fs::read_to_string(&path).unwrap());
+
+    fs::remove_file(&path).unwrap();
+  }
+
+  #[test]
+  fn new_logs_start_with_the_header() {
+    let path = temp_path("header");
+    fs::write(&path, &HEADER[..5]).unwrap();
+
+    let mut store = Store::open(&path, tables()).unwrap();
+    store.execute(Event::Seat { at: 0 }).unwrap();
+    assert_eq!(format!("{}\n{}", HEADER, line("seat|0")), fs::read_to_string(&path).unwrap());
+
+    // Otra version, o algo que no es una bitacora: no se toca
+    for text in &["restaurants log 2\n".to_string(), line("seat|1")] {
+      fs::write(&path, text).unwrap();
+      assert_eq!(io::ErrorKind::InvalidData, Store::open(&path, tables()).err().unwrap().kind());
+      assert_eq!(*text, fs::read_to_string(&path).unwrap());
+    }
+
+    fs::remove_file(&path).unwrap();
+  }
+}